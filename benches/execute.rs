@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, Criterion};
+use detsys_srv::{policy, resolver::SrvResolver, SrvClient, SrvRecord};
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+struct Record(u16);
+
+impl SrvRecord for Record {
+    type Target = str;
+
+    fn target(&self) -> &Self::Target {
+        "example.com"
+    }
+
+    fn port(&self) -> u16 {
+        self.0
+    }
+
+    fn priority(&self) -> u16 {
+        0
+    }
+
+    fn weight(&self) -> u16 {
+        1
+    }
+}
+
+struct Resolver(u16);
+
+#[async_trait]
+impl SrvResolver for Resolver {
+    type Record = Record;
+    type Error = std::io::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        _srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let records = (0..self.0).map(|i| Record(8000 + i)).collect();
+        Ok((records, Instant::now() + Duration::from_secs(3600)))
+    }
+}
+
+fn bench_execute<P: policy::Policy + Default>(c: &mut Criterion, name: &str, records: u16) {
+    let client = SrvClient::<_, P>::new_with_resolver(
+        "_http._tcp.example.com",
+        "https://fallback.example.com".parse().unwrap(),
+        None,
+        Resolver(records),
+    );
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            futures::executor::block_on(
+                client.execute(|url| async move { Ok::<_, Infallible>(url.port()) }),
+            )
+        })
+    });
+}
+
+fn execute(c: &mut Criterion) {
+    bench_execute::<policy::Affinity>(c, "execute affinity 1 record", 1);
+    bench_execute::<policy::Affinity>(c, "execute affinity 3 records", 3);
+    bench_execute::<policy::Rfc2782>(c, "execute rfc2782 1 record", 1);
+    bench_execute::<policy::Rfc2782>(c, "execute rfc2782 3 records", 3);
+}
+
+criterion_group!(benches, execute);
+criterion_main!(benches);
@@ -0,0 +1,233 @@
+//! Address ranges SRV targets may not resolve to.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// A range of IP addresses sharing a common prefix, e.g. `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Creates a range of the addresses sharing the first `prefix_len` bits of
+    /// `addr`, or `None` if `prefix_len` is longer than the address.
+    pub fn new(addr: impl Into<IpAddr>, prefix_len: u8) -> Option<Self> {
+        let addr = addr.into();
+        let max_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        (prefix_len <= max_len).then_some(Self { addr, prefix_len })
+    }
+
+    /// Gets the first address of the range as it was given.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Gets the number of leading bits addresses in the range share.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Gets the range with the bits of its address past its prefix cleared.
+    #[cfg_attr(not(feature = "hickory"), allow(dead_code))]
+    pub(crate) fn masked(self) -> Self {
+        let addr = match self.addr {
+            IpAddr::V4(net) => {
+                let net = mask(u32::from(net).into(), 32, self.prefix_len);
+                IpAddr::V4(Ipv4Addr::from(net as u32))
+            }
+            IpAddr::V6(net) => IpAddr::V6(Ipv6Addr::from(mask(net.into(), 128, self.prefix_len))),
+        };
+        Self { addr, ..self }
+    }
+
+    /// Determines if an address is within the range. IPv4-mapped IPv6
+    /// addresses are treated as the IPv4 addresses they map.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => prefix_eq(
+                u32::from(net).into(),
+                u32::from(addr).into(),
+                32,
+                self.prefix_len,
+            ),
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                prefix_eq(net.into(), addr.into(), 128, self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_eq(net: u128, addr: u128, bits: u8, prefix_len: u8) -> bool {
+    match bits - prefix_len {
+        shift if shift >= 128 => true,
+        shift => net >> shift == addr >> shift,
+    }
+}
+
+fn mask(addr: u128, bits: u8, prefix_len: u8) -> u128 {
+    match bits - prefix_len {
+        shift if shift >= 128 => 0,
+        shift => addr >> shift << shift,
+    }
+}
+
+/// Set of address ranges that SRV targets are forbidden from resolving to,
+/// protecting against SRV answers that point a client at internal services.
+///
+/// The default set covers unspecified, loopback, private (RFC 1918 and
+/// unique local), shared (CGNAT), and link-local addresses, which includes
+/// the cloud metadata services at `169.254.169.254` and `fd00:ec2::254`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForbiddenAddrs {
+    ranges: Vec<IpRange>,
+}
+
+impl ForbiddenAddrs {
+    /// Creates a set of forbidden address ranges.
+    pub fn new(ranges: impl IntoIterator<Item = IpRange>) -> Self {
+        Self {
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    /// Adds a range to a set of forbidden address ranges.
+    pub fn with_range(mut self, range: IpRange) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Gets the forbidden address ranges.
+    pub fn ranges(&self) -> &[IpRange] {
+        &self.ranges
+    }
+
+    /// Determines if an address is forbidden.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(addr))
+    }
+}
+
+impl Default for ForbiddenAddrs {
+    fn default() -> Self {
+        let v4 = |a, b, c, d, len| IpRange::new(Ipv4Addr::new(a, b, c, d), len).unwrap();
+        let v6 = |addr: &str, len| IpRange::new(addr.parse::<Ipv6Addr>().unwrap(), len).unwrap();
+        Self::new([
+            v4(0, 0, 0, 0, 8),
+            v4(10, 0, 0, 0, 8),
+            v4(100, 64, 0, 0, 10),
+            v4(127, 0, 0, 0, 8),
+            v4(169, 254, 0, 0, 16),
+            v4(172, 16, 0, 0, 12),
+            v4(192, 168, 0, 0, 16),
+            v6("::", 128),
+            v6("::1", 128),
+            v6("fc00::", 7),
+            v6("fe80::", 10),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forbidden(addr: &str) -> bool {
+        ForbiddenAddrs::default().contains(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn default_ranges() {
+        for addr in [
+            "10.1.2.3",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "172.31.255.255",
+            "192.168.0.1",
+            "::1",
+            "fd00:ec2::254",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(forbidden(addr), "{addr} should be forbidden");
+        }
+        for addr in ["8.8.8.8", "172.32.0.1", "100.128.0.1", "2001:db8::1"] {
+            assert!(!forbidden(addr), "{addr} should be allowed");
+        }
+    }
+
+    #[test]
+    fn range_bounds() {
+        assert!(IpRange::new(Ipv4Addr::LOCALHOST, 33).is_none());
+        let all = IpRange::new(Ipv6Addr::UNSPECIFIED, 0).unwrap();
+        assert!(all.contains("2001:db8::1".parse().unwrap()));
+        assert!(!all.contains("8.8.8.8".parse().unwrap()));
+        let one = IpRange::new(Ipv4Addr::new(1, 2, 3, 4), 32).unwrap();
+        assert!(one.contains("1.2.3.4".parse().unwrap()));
+        assert!(!one.contains("1.2.3.5".parse().unwrap()));
+    }
+
+    /// Resolver producing records for hosts whose addresses change to
+    /// internal ones after their first lookup, like with DNS rebinding.
+    #[derive(Debug, Default)]
+    struct RebindingResolver {
+        looked_up: std::sync::Mutex<std::collections::HashSet<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::resolver::SrvResolver for RebindingResolver {
+        type Record = crate::OwnedSrvRecord;
+        type Error = std::io::Error;
+
+        async fn get_srv_records_unordered(
+            &self,
+            _srv: &str,
+        ) -> Result<(Vec<Self::Record>, std::time::Instant), Self::Error> {
+            let records = ["partly", "fully"]
+                .into_iter()
+                .map(|host| Self::Record::new(format!("{host}.example.com"), 443, 1, 100))
+                .collect();
+            let valid_until = std::time::Instant::now() + std::time::Duration::from_secs(60);
+            Ok((records, valid_until))
+        }
+
+        async fn get_ip_addrs(
+            &self,
+            host: &str,
+        ) -> Result<(Vec<IpAddr>, std::time::Instant), Self::Error> {
+            let rebound = !self.looked_up.lock().unwrap().insert(host.to_owned());
+            let addrs = match (host, rebound) {
+                (_, false) => vec!["8.8.8.8"],
+                ("partly.example.com", true) => vec!["8.8.8.8", "10.0.0.1"],
+                (_, true) => vec!["127.0.0.1"],
+            };
+            let addrs = addrs.into_iter().map(|addr| addr.parse().unwrap());
+            Ok((addrs.collect(), std::time::Instant::now()))
+        }
+    }
+
+    #[test]
+    fn resolved_addrs_are_rechecked() {
+        let client = crate::SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            RebindingResolver::default(),
+        )
+        .forbidden_addrs(ForbiddenAddrs::default());
+        let resolved = futures::executor::block_on(client.resolve_socket_addrs()).unwrap();
+        let resolved = resolved
+            .iter()
+            .map(|target| (target.uri().host_str().unwrap(), target.addrs()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            [("partly.example.com", &["8.8.8.8:443".parse().unwrap()][..])]
+        );
+    }
+}
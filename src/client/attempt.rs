@@ -0,0 +1,231 @@
+//! Attempts of executions noted with a client's policy.
+
+use super::policy::Policy;
+use std::time::Duration;
+use url::Url;
+
+/// How an execution hedges slow attempts, see
+/// [`SrvClient::execute_hedged`](super::SrvClient::execute_hedged).
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub(crate) struct Hedge {
+    /// Maximum number of attempts in flight at once, at least one
+    pub(crate) max_in_flight: usize,
+    /// Time after which an attempt that hasn't finished is hedged
+    pub(crate) delay: Duration,
+}
+
+/// Attempt on a SRV target noted with a client's policy, which is noted as
+/// cancelled if it's dropped before it finishes, e.g. because its execution
+/// was cancelled or its future dropped, so that policies don't count it as
+/// in flight forever.
+pub(crate) struct PendingAttempt<'a, P: Policy> {
+    policy: &'a P,
+    url: &'a Url,
+    finished: bool,
+}
+
+impl<'a, P: Policy> PendingAttempt<'a, P> {
+    /// Notes the start of an attempt on `url` with `policy`.
+    pub(crate) fn start(policy: &'a P, url: &'a Url) -> Self {
+        policy.note_attempt(url);
+        Self {
+            policy,
+            url,
+            finished: false,
+        }
+    }
+
+    /// Marks the attempt as finished, leaving its outcome to be noted by the
+    /// caller.
+    pub(crate) fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl<P: Policy> Drop for PendingAttempt<'_, P> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.policy.note_cancelled(self.url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        policy::{LeastLatency, Policy},
+        resolver::StaticResolver,
+        ExecuteError, SrvClient,
+    };
+    use futures::{executor::block_on, future, FutureExt};
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    fn client<P: Policy>(policy: P) -> SrvClient<StaticResolver, P> {
+        let resolver = StaticResolver::new(
+            [
+                ("a.example.com", 443, 1, 1),
+                ("b.example.com", 443, 2, 1),
+                ("c.example.com", 443, 3, 1),
+            ],
+            Duration::from_secs(60),
+        );
+        SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+        .policy(policy)
+    }
+
+    fn in_flight<P: Policy>(client: &SrvClient<StaticResolver, P>) -> usize {
+        let uris = block_on(client.cached_uris()).unwrap();
+        let stats = client.policy.stats().unwrap();
+        uris.iter()
+            .filter_map(|uri| stats.get(uri))
+            .map(|stat| stat.in_flight())
+            .sum()
+    }
+
+    #[test]
+    fn execute_with_cancellation() {
+        let client = client(LeastLatency::default());
+        let cancel = AtomicBool::new(false);
+        let attempted = Mutex::new(Vec::new());
+        let cancelled = future::poll_fn(|_| match cancel.load(Ordering::Relaxed) {
+            true => std::task::Poll::Ready(()),
+            false => std::task::Poll::Pending,
+        });
+        let res = block_on(client.execute_with_cancellation(cancelled, |uri| {
+            attempted.lock().unwrap().push(uri);
+            // Cancel between the first attempt and the second
+            cancel.store(true, Ordering::Relaxed);
+            async { Err::<(), _>(std::io::Error::other("failed")) }
+        }));
+        assert!(matches!(res, Err(ExecuteError::Cancelled)));
+        assert_eq!(attempted.into_inner().unwrap().len(), 1);
+
+        // Attempts in progress are abandoned, and no longer in flight
+        let res = block_on(client.execute_with_cancellation(async {}, |_| {
+            future::pending::<Result<(), std::io::Error>>()
+        }));
+        assert!(matches!(res, Err(ExecuteError::Cancelled)));
+        assert_eq!(in_flight(&client), 0);
+    }
+
+    #[test]
+    fn dropped_executions_finish_attempts() {
+        let client = client(LeastLatency::default());
+        block_on(client.cached_uris()).unwrap();
+        let execution = client.execute(|_| future::pending::<Result<(), std::io::Error>>());
+        assert!(execution.now_or_never().is_none());
+        assert_eq!(in_flight(&client), 0);
+
+        let mut stream =
+            Box::pin(client.execute_stream(|_| future::pending::<Result<(), std::io::Error>>()));
+        assert!(futures::StreamExt::next(&mut stream)
+            .now_or_never()
+            .is_none());
+        drop(stream);
+        assert_eq!(in_flight(&client), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn execute_hedged() {
+        let client = client(crate::policy::Affinity::default());
+        let start = tokio::time::Instant::now();
+        let attempts = Mutex::new(Vec::new());
+        let attempts = &attempts;
+        // Targets take the given seconds to finish, failing if negative
+        let execute = |max_in_flight, secs: &'static [(&'static str, i64)]| {
+            client.execute_hedged(max_in_flight, Duration::from_secs(1), move |uri| {
+                let host = uri.host_str().unwrap().to_owned();
+                attempts
+                    .lock()
+                    .unwrap()
+                    .push((host.clone(), start.elapsed().as_secs()));
+                let secs = secs
+                    .iter()
+                    .find(|(name, _)| host.starts_with(name))
+                    .map_or(0, |(_, secs)| *secs);
+                async move {
+                    tokio::time::sleep(Duration::from_secs(secs.unsigned_abs())).await;
+                    match secs {
+                        0.. => Ok(host),
+                        _ => Err(format!("{host} failed")),
+                    }
+                }
+            })
+        };
+        let take = || {
+            let attempts = attempts.lock().unwrap().drain(..).collect::<Vec<_>>();
+            let secs = start.elapsed().as_secs();
+            (attempts, secs)
+        };
+        let at = |host: &str, secs| (format!("{host}.example.com"), secs);
+
+        // The slow first target is hedged after a second, and the second one
+        // wins
+        let host = execute(2, &[("a", 10), ("b", 2)]).await.unwrap();
+        assert_eq!(host, "b.example.com");
+        assert_eq!(take(), (vec![at("a", 0), at("b", 1)], 3));
+
+        // Affinity now prefers the winner; a failure starts the next attempt
+        // right away, and at most two are in flight
+        let host = execute(2, &[("b", -1), ("a", 5), ("c", 2)]).await.unwrap();
+        assert_eq!(host, "c.example.com");
+        assert_eq!(take(), (vec![at("b", 3), at("a", 4), at("c", 5)], 7));
+
+        // Without hedging, attempts are sequential
+        let host = execute(1, &[("c", 3)]).await.unwrap();
+        assert_eq!(host, "c.example.com");
+        assert_eq!(take(), (vec![at("c", 7)], 10));
+
+        // Once every target failed, the fallback is used
+        let host = execute(3, &[("c", -3), ("a", -5), ("b", -1)])
+            .await
+            .unwrap();
+        assert_eq!(host, "fallback.example.com");
+        assert_eq!(
+            take(),
+            (
+                vec![
+                    at("c", 10),
+                    at("a", 11),
+                    at("b", 12),
+                    ("fallback.example.com".to_owned(), 16)
+                ],
+                16
+            )
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn hedged_attempts_are_finished() {
+        let client = client(LeastLatency::default());
+        let host = client
+            .execute_hedged(2, Duration::from_secs(1), |uri| async move {
+                let host = uri.host_str().unwrap().to_owned();
+                let secs = match host.as_str() {
+                    "a.example.com" => 10,
+                    _ => 2,
+                };
+                tokio::time::sleep(Duration::from_secs(secs)).await;
+                Ok::<_, std::io::Error>(host)
+            })
+            .await
+            .unwrap();
+        assert_eq!(host, "b.example.com");
+        // The losing attempt was abandoned rather than left in flight
+        assert_eq!(in_flight(&client), 0);
+    }
+}
@@ -0,0 +1,112 @@
+//! Blocking executions, for programs without an async runtime.
+
+use super::{policy, SrvClient};
+use crate::resolver::SrvResolver;
+use std::{
+    fmt::Display,
+    future::{self, Future},
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+use url::Url;
+
+impl<Resolver: SrvResolver, Policy: policy::Policy> SrvClient<Resolver, Policy> {
+    /// Performs an operation on a client's targets like
+    /// [`SrvClient::execute`], blocking the current thread until it
+    /// succeeds or every target failed, for programs without an async
+    /// runtime. The client's cache, policy, and allowed suffixes are used as
+    /// they are by asynchronous executions.
+    ///
+    /// Lookups are driven on the current thread, so the client's resolver
+    /// must not need a runtime of its own: resolvers that block, like
+    /// `SystemResolver` and `StdResolver`, or that answer right away, like
+    /// [`StaticResolver`](crate::resolver::StaticResolver), work, while
+    /// hickory's Tokio resolver doesn't. Neither does a lookup budget, which
+    /// needs a Tokio runtime for its timer.
+    ///
+    /// ```
+    /// # use detsys_srv::{resolver::StaticResolver, SrvClient};
+    /// # use std::time::Duration;
+    /// let resolver = StaticResolver::new([("a.example.com", 443, 1, 1)], Duration::from_secs(60));
+    /// let client = SrvClient::<_>::new_with_resolver(
+    ///     "_http._tcp.example.com",
+    ///     "https://fallback.example.com".parse().unwrap(),
+    ///     None,
+    ///     resolver,
+    /// );
+    /// let host = client
+    ///     .execute_blocking(|uri| Ok::<_, std::io::Error>(uri.host_str().unwrap().to_owned()))
+    ///     .unwrap();
+    /// assert_eq!(host, "a.example.com");
+    /// ```
+    pub fn execute_blocking<T, E>(&self, mut func: impl FnMut(Url) -> Result<T, E>) -> Result<T, E>
+    where
+        E: Display,
+    {
+        block_on(self.execute(|uri| future::ready(func(uri))))
+    }
+}
+
+/// Wakes a thread parked by [`block_on`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drives a future to completion on the current thread, parking it while the
+/// future is pending.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resolver::StaticResolver, CachedTargets, SrvClient};
+    use std::time::Duration;
+
+    #[test]
+    fn fails_over_without_runtime() {
+        let resolver = StaticResolver::new(
+            [("a.example.com", 443, 1, 1), ("b.example.com", 443, 2, 1)],
+            Duration::from_secs(60),
+        );
+        let client = SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+        let mut attempted = Vec::new();
+        let host = client
+            .execute_blocking(|uri| {
+                let host = uri.host_str().unwrap().to_owned();
+                attempted.push(host.clone());
+                match host.as_str() {
+                    "a.example.com" => Err(std::io::Error::other("a is down")),
+                    _ => Ok(host),
+                }
+            })
+            .unwrap();
+        assert_eq!(host, "b.example.com");
+        assert_eq!(attempted, ["a.example.com", "b.example.com"]);
+
+        // The cache is populated for later executions
+        assert!(matches!(
+            client.try_cached_targets(),
+            CachedTargets::Fresh(uris) if uris.len() == 2
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+//! DNS-SD (RFC 6763) browsing of service instances.
+
+use super::{Error, SrvClient};
+use crate::resolver::SrvResolver;
+use futures_util::future::join_all;
+use std::time::Instant;
+use url::Url;
+
+/// A named instance of a DNS-SD service found by [`SrvClient::browse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInstance {
+    name: String,
+    targets: Vec<Url>,
+    txt: Vec<String>,
+    valid_until: Instant,
+}
+
+impl ServiceInstance {
+    /// Full name of the instance, e.g. `Appliance 1._detsys._tcp.example.com.`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The instance's targets, parsed from its SRV records like the client's.
+    pub fn targets(&self) -> &[Url] {
+        &self.targets
+    }
+
+    /// Strings of the instance's TXT records, e.g. `key=value` pairs.
+    pub fn txt(&self) -> &[String] {
+        &self.txt
+    }
+
+    /// Time the instance is valid until, the earliest expiry of its PTR, SRV,
+    /// and TXT records.
+    pub fn valid_until(&self) -> Instant {
+        self.valid_until
+    }
+}
+
+impl<Resolver: SrvResolver, Policy: super::policy::Policy> SrvClient<Resolver, Policy> {
+    /// Browses the instances of the DNS-SD service named by the client's SRV
+    /// name (e.g. `_detsys._tcp.example.com`), looking up the instances'
+    /// SRV and TXT records concurrently. Returns the instances, grouped by
+    /// name, along with the time they're valid until.
+    ///
+    /// Instances whose SRV records can't be looked up are omitted; use
+    /// [`DnsSdResolver`](crate::resolver::DnsSdResolver) to execute operations
+    /// on the targets of every instance.
+    pub async fn browse(&self) -> Result<(Vec<ServiceInstance>, Instant), Error<Resolver::Error>> {
+        let (names, ptr_valid_until) = self
+            .resolver
+            .get_ptr_records(&self.srv.load().name)
+            .await
+            .map_err(Error::Lookup)?;
+        let instances = join_all(names.into_iter().map(|name| async move {
+            let instance = self.browse_instance(&name).await;
+            if let Err(e) = &instance {
+                tracing::trace!(instance = %name, %e, "Failed to look up DNS-SD instance");
+            }
+            instance.ok()
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let valid_until = instances
+            .iter()
+            .map(ServiceInstance::valid_until)
+            .fold(ptr_valid_until, Instant::min);
+        Ok((instances, valid_until))
+    }
+
+    async fn browse_instance(&self, name: &str) -> Result<ServiceInstance, String> {
+        let (targets, srv_valid_until) = {
+            let (records, valid_until) = self
+                .resolver
+                .get_srv_records(name)
+                .await
+                .map_err(|e| e.to_string())?;
+            let targets = records
+                .iter()
+                .filter_map(|record| self.parse_record(record).ok())
+                .collect::<Vec<_>>();
+            (targets, valid_until)
+        };
+        let (txt, valid_until) = match self.resolver.get_txt_records(name).await {
+            Ok((txt, txt_valid_until)) => (txt, srv_valid_until.min(txt_valid_until)),
+            Err(e) => {
+                tracing::trace!(instance = %name, %e, "Failed to look up DNS-SD instance TXT records");
+                (Vec::new(), srv_valid_until)
+            }
+        };
+        Ok(ServiceInstance {
+            name: name.to_owned(),
+            targets,
+            txt,
+            valid_until,
+        })
+    }
+}
@@ -0,0 +1,165 @@
+//! Lookup budgets of executions.
+
+use super::{commit::CommitPoint, policy, ExecuteError, Hedge, SrvClient};
+use crate::resolver::SrvResolver;
+#[cfg(feature = "tokio")]
+use futures_util::future::{self, Either};
+use futures_util::FutureExt;
+use std::{future::Future, pin::pin};
+use url::Url;
+
+impl<Resolver: SrvResolver, Policy: policy::Policy> SrvClient<Resolver, Policy> {
+    /// Refreshes a client's cache if needed and performs an operation on its
+    /// targets, like [`SrvClient::execute_inner`] without recording it.
+    ///
+    /// If the client has a [lookup budget](SrvClient::lookup_budget) and the
+    /// lookup exceeds it, the operation proceeds without waiting for it. The
+    /// lookup is still driven while the operation runs, but abandoned if the
+    /// operation finishes first, with the client's refreshers woken to
+    /// refresh its cache in the background instead.
+    pub(super) async fn execute_refreshing<T, E, Fut>(
+        &self,
+        cancel: impl Future<Output = ()>,
+        commit: Option<&CommitPoint>,
+        timed_out: fn(&E) -> bool,
+        hedge: Option<Hedge>,
+        func: impl FnMut(Url) -> Fut,
+    ) -> Result<T, ExecuteError<E>>
+    where
+        E: std::fmt::Display,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut func = func;
+        let mut cancel = pin!(cancel.fuse());
+        let refresh = pin!(self.get_valid_cache().fuse());
+        #[cfg(feature = "tokio")]
+        let refresh = match self.lookup_budget.filter(|_| !self.cache.load().valid()) {
+            Some(budget) => {
+                let mut refresh = refresh;
+                let over_budget = pin!(tokio::time::sleep(budget));
+                if let Either::Left((refreshed, _)) =
+                    future::select(refresh.as_mut(), over_budget).await
+                {
+                    let cancel = cancel.as_mut();
+                    return self
+                        .execute_refreshed(refreshed, cancel, commit, timed_out, hedge, &mut func)
+                        .await;
+                }
+                tracing::trace!(?budget, "SRV lookup over budget, proceeding without it");
+                self.refresh.wake.notify_one();
+                let candidates = match self.stale_within_budget {
+                    true => self.ordered_candidates(&self.cache.load()),
+                    false => Vec::new(),
+                };
+                // Keep driving the lookup so that it may still populate the
+                // cache, but without waiting on it
+                let execution = pin!(self.execute_on(
+                    candidates,
+                    cancel.as_mut(),
+                    commit,
+                    timed_out,
+                    hedge,
+                    &mut func,
+                ));
+                return match future::select(execution, refresh).await {
+                    Either::Left((res, _)) => res,
+                    Either::Right((_, execution)) => execution.await,
+                };
+            }
+            None => refresh,
+        };
+        let cancel = cancel.as_mut();
+        self.execute_refreshed(refresh.await, cancel, commit, timed_out, hedge, &mut func)
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use crate::{
+        resolver::{BoxError, FnResolver, FnResolverError, SrvResolver},
+        CachedTargets, OwnedSrvRecord, SrvClient,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    /// Creates a client whose lookups take five seconds to produce a record
+    /// for `a.example.com` valid for a minute of tokio's clock, counting them
+    /// in `lookups`.
+    fn client(
+        lookups: &Arc<AtomicUsize>,
+    ) -> SrvClient<impl SrvResolver<Record = OwnedSrvRecord, Error = FnResolverError>> {
+        let lookups = lookups.clone();
+        let resolver = FnResolver::new(move |_| {
+            lookups.fetch_add(1, Ordering::Relaxed);
+            async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                let record = OwnedSrvRecord::new("a.example.com", 443, 1, 100);
+                let valid_until = tokio::time::Instant::now() + Duration::from_secs(60);
+                Ok::<_, BoxError>((vec![record], valid_until.into_std()))
+            }
+        });
+        SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lookup_budget() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let client = Arc::new(client(&lookups).lookup_budget(Duration::from_millis(100)));
+        let start = tokio::time::Instant::now();
+        let attempts = Mutex::new(Vec::new());
+        let execute = || {
+            client.execute(|uri| {
+                let host = uri.host_str().unwrap().to_owned();
+                attempts.lock().unwrap().push((host, start.elapsed()));
+                async { Ok::<_, std::io::Error>(()) }
+            })
+        };
+
+        // The fallback is used once the budget is spent, without waiting for
+        // the lookup
+        execute().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+        assert_eq!(
+            attempts.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [(
+                "fallback.example.com".to_owned(),
+                Duration::from_millis(100)
+            )]
+        );
+        assert_eq!(client.try_cached_targets(), CachedTargets::Empty);
+
+        // A refresher finishes the lookup in the background
+        let refresher = client.spawn_refresher(Duration::from_secs(10));
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        assert!(matches!(
+            client.try_cached_targets(),
+            CachedTargets::Fresh(_)
+        ));
+        execute().await.unwrap();
+        assert_eq!(attempts.lock().unwrap()[0].0, "a.example.com");
+        assert_eq!(lookups.load(Ordering::Relaxed), 2);
+        refresher.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lookup_within_budget() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let client = client(&lookups).lookup_budget(Duration::from_secs(10));
+        let uri = client
+            .execute(|uri| async { Ok::<_, std::io::Error>(uri) })
+            .await
+            .unwrap();
+        assert_eq!(uri.host_str(), Some("a.example.com"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Validating builders of clients.
+
+use super::{policy, ConfigError, SrvClient};
+use http::uri::Scheme;
+use url::Url;
+
+/// Errors encountered when building a [`SrvClient`] with a
+/// [`SrvClientBuilder`].
+#[derive(Debug, thiserror::Error)]
+pub enum BuilderError {
+    /// Produced when no SRV name was set
+    #[error("no SRV name was set")]
+    MissingSrvName,
+    /// Produced when the SRV name is empty
+    #[error("SRV name is empty")]
+    EmptySrvName,
+    /// Produced when no fallback was set
+    #[error("no fallback was set")]
+    MissingFallback,
+    /// Produced when the fallback has no host
+    #[error("fallback `{fallback}` has no host")]
+    FallbackWithoutHost {
+        /// The fallback
+        fallback: Url,
+    },
+    /// Produced when a setting violates the client's configuration
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+/// Builder of a [`SrvClient`], validating its settings up front.
+///
+/// ```
+/// # use detsys_srv::{SrvClient, SrvClientBuilder};
+/// let client: SrvClient<()> = SrvClientBuilder::default()
+///     .srv_name("_http._tcp.example.com")
+///     .fallback("https://example.com".parse().unwrap())
+///     .allowed_suffix(url::Host::Domain("example.com.".into()))
+///     .path_prefix("/api")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct SrvClientBuilder<Resolver, Policy = policy::Affinity> {
+    resolver: Resolver,
+    policy: Policy,
+    srv_name: Option<String>,
+    fallback: Option<Url>,
+    extra_fallbacks: Vec<Url>,
+    allowed_suffixes: Option<Vec<url::Host>>,
+    scheme: Option<String>,
+    path_prefix: Option<String>,
+}
+
+impl<Resolver> SrvClientBuilder<Resolver> {
+    /// Creates a builder of a client using `resolver` and the default policy.
+    pub fn with_resolver(resolver: Resolver) -> Self {
+        Self {
+            resolver,
+            policy: Default::default(),
+            srv_name: None,
+            fallback: None,
+            extra_fallbacks: Vec::new(),
+            allowed_suffixes: None,
+            scheme: None,
+            path_prefix: None,
+        }
+    }
+}
+
+impl<Resolver, Policy: policy::Policy> SrvClientBuilder<Resolver, Policy> {
+    /// Sets the resolver of the client.
+    pub fn resolver<R>(self, resolver: R) -> SrvClientBuilder<R, Policy> {
+        SrvClientBuilder {
+            resolver,
+            policy: self.policy,
+            srv_name: self.srv_name,
+            fallback: self.fallback,
+            extra_fallbacks: self.extra_fallbacks,
+            allowed_suffixes: self.allowed_suffixes,
+            scheme: self.scheme,
+            path_prefix: self.path_prefix,
+        }
+    }
+
+    /// Sets the policy of the client.
+    pub fn policy<P: policy::Policy>(self, policy: P) -> SrvClientBuilder<Resolver, P> {
+        SrvClientBuilder {
+            policy,
+            resolver: self.resolver,
+            srv_name: self.srv_name,
+            fallback: self.fallback,
+            extra_fallbacks: self.extra_fallbacks,
+            allowed_suffixes: self.allowed_suffixes,
+            scheme: self.scheme,
+            path_prefix: self.path_prefix,
+        }
+    }
+
+    /// Sets the SRV name of the client, which is required.
+    pub fn srv_name(self, srv_name: impl ToString) -> Self {
+        Self {
+            srv_name: Some(srv_name.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets the fallback of the client, which is required.
+    pub fn fallback(self, fallback: Url) -> Self {
+        Self {
+            fallback: Some(fallback),
+            extra_fallbacks: Vec::new(),
+            ..self
+        }
+    }
+
+    /// Sets the fallbacks of the client, tried in order, as with
+    /// [`SrvClient::set_fallbacks`]. At least one is required.
+    pub fn fallbacks(self, fallbacks: Vec<Url>) -> Self {
+        let mut fallbacks = fallbacks.into_iter();
+        Self {
+            fallback: fallbacks.next(),
+            extra_fallbacks: fallbacks.collect(),
+            ..self
+        }
+    }
+
+    /// Adds a host suffix the client's targets may have. Targets aren't
+    /// restricted unless a suffix is added.
+    pub fn allowed_suffix(mut self, allowed_suffix: url::Host) -> Self {
+        self.allowed_suffixes
+            .get_or_insert_with(Vec::new)
+            .push(allowed_suffix);
+        self
+    }
+
+    /// Sets the http scheme of the client, as with [`SrvClient::http_scheme`].
+    pub fn http_scheme(self, http_scheme: Scheme) -> Self {
+        self.scheme(http_scheme)
+    }
+
+    /// Sets the scheme of the client, as with [`SrvClient::scheme`].
+    pub fn scheme(self, scheme: impl ToString) -> Self {
+        Self {
+            scheme: Some(scheme.to_string()),
+            ..self
+        }
+    }
+
+    /// Sets the path prefix of the client, as with
+    /// [`SrvClient::path_prefix`].
+    pub fn path_prefix(self, path_prefix: impl ToString) -> Self {
+        Self {
+            path_prefix: Some(path_prefix.to_string()),
+            ..self
+        }
+    }
+
+    /// Builds the client.
+    ///
+    /// Fails if the SRV name or fallback weren't set, the SRV name is empty,
+    /// the fallback has no host, or a setting violates the client's
+    /// configuration.
+    pub fn build(self) -> Result<SrvClient<Resolver, Policy>, BuilderError> {
+        let srv_name = self.srv_name.ok_or(BuilderError::MissingSrvName)?;
+        if srv_name.trim().trim_end_matches('.').is_empty() {
+            return Err(BuilderError::EmptySrvName);
+        }
+        let fallback = self.fallback.ok_or(BuilderError::MissingFallback)?;
+        for fallback in std::iter::once(&fallback).chain(&self.extra_fallbacks) {
+            if fallback.host().is_none() {
+                let fallback = fallback.clone();
+                return Err(BuilderError::FallbackWithoutHost { fallback });
+            }
+        }
+        let mut client = SrvClient::<Resolver>::new_with_resolver(
+            srv_name,
+            fallback,
+            self.allowed_suffixes,
+            self.resolver,
+        )
+        .policy(self.policy);
+        if !self.extra_fallbacks.is_empty() {
+            let fallbacks = std::iter::once(client.current_fallback());
+            client.set_fallbacks(fallbacks.chain(self.extra_fallbacks).collect())?;
+        }
+        if let Some(scheme) = self.scheme {
+            client = client.scheme(&scheme)?;
+        }
+        if let Some(path_prefix) = self.path_prefix {
+            client = client.path_prefix(path_prefix);
+        }
+        Ok(client)
+    }
+}
@@ -0,0 +1,182 @@
+//! Bursts of executions sharing a SRV lookup.
+
+use super::{policy::ParsedRecord, Cache};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use url::Url;
+
+/// One ordering of a cache's items, rotated for each execution using it so
+/// that a burst of executions is spread across targets.
+///
+/// Only the leading targets sharing the first target's tier (its priority,
+/// and whether it's of the preferred address family) are rotated, so that
+/// targets of lower tiers are still only used once higher ones failed, as
+/// RFC 2782 requires.
+#[derive(Debug)]
+pub(crate) struct Burst {
+    ordering: Box<[usize]>,
+    rotating: usize,
+    next: AtomicUsize,
+}
+
+impl Burst {
+    pub(crate) fn new<T>(
+        ordering: Box<[usize]>,
+        items: &[T],
+        to_record: impl Fn(&T) -> Option<&ParsedRecord>,
+    ) -> Self {
+        let tier = |i: usize| to_record(&items[i]).map(|r| (r.unpreferred_family, r.priority));
+        let first = ordering.first().map(|&i| tier(i));
+        let rotating = ordering
+            .iter()
+            .take_while(|&&i| Some(tier(i)) == first)
+            .count();
+        Self {
+            ordering,
+            rotating,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn candidates<T>(&self, cache: &Cache<T>, to_uri: impl Fn(&T) -> &Url) -> Vec<Url> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        self.rotated(start, cache, to_uri)
+    }
+
+    /// Gets the candidates the next execution will use without rotating the
+    /// ordering.
+    pub(crate) fn peek<T>(&self, cache: &Cache<T>, to_uri: impl Fn(&T) -> &Url) -> Vec<Url> {
+        self.rotated(self.next.load(Ordering::Relaxed), cache, to_uri)
+    }
+
+    fn rotated<T>(&self, start: usize, cache: &Cache<T>, to_uri: impl Fn(&T) -> &Url) -> Vec<Url> {
+        let (rotating, rest) = self.ordering.split_at(self.rotating);
+        let n = rotating.len();
+        (0..n)
+            .map(|i| rotating[(start + i) % n])
+            .chain(rest.iter().copied())
+            .map(|i| to_uri(&cache.items()[i]).clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        policy::Rfc2782,
+        resolver::{BoxError, FnResolver, FnResolverError, LookupErrorKind, SrvResolver},
+        Error, OwnedSrvRecord, SrvClient,
+    };
+    use futures::{executor::block_on, future};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Poll,
+        time::{Duration, Instant},
+    };
+
+    /// Creates a client whose lookups yield a few times before producing
+    /// `records`, or failing without any, so that concurrent executions all
+    /// wait on the same lookup, counting them in `lookups`.
+    fn client(
+        records: &'static [(&'static str, u16)],
+        lookups: &Arc<AtomicUsize>,
+    ) -> SrvClient<impl SrvResolver<Record = OwnedSrvRecord, Error = FnResolverError>, Rfc2782>
+    {
+        let lookups = lookups.clone();
+        let resolver = FnResolver::new(move |_| {
+            lookups.fetch_add(1, Ordering::Relaxed);
+            let mut yields = 0;
+            async move {
+                future::poll_fn(|cx| {
+                    yields += 1;
+                    if yields > 4 {
+                        return Poll::Ready(());
+                    }
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                })
+                .await;
+                if records.is_empty() {
+                    return Err(BoxError::from("lookup failed"));
+                }
+                let records = records
+                    .iter()
+                    .map(|&(host, priority)| OwnedSrvRecord::new(host, 443, priority, 100))
+                    .collect();
+                Ok((records, Instant::now() + Duration::from_secs(60)))
+            }
+        });
+        SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+        .policy(Rfc2782)
+        .spread_bursts(true)
+    }
+
+    #[test]
+    fn rotates_within_first_tier() {
+        let records = &[
+            ("a.example.com", 1),
+            ("b.example.com", 1),
+            ("c.example.com", 2),
+            ("d.example.com", 2),
+        ];
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let client = client(records, &lookups);
+        let orders = block_on(future::join_all((0..4).map(|_| async {
+            let mut attempted = Vec::new();
+            let _ = client
+                .execute(|uri| {
+                    let host = uri.host_str().unwrap().to_owned();
+                    attempted.push(host);
+                    async { Err::<(), _>(std::io::Error::other("failed")) }
+                })
+                .await;
+            attempted
+        })));
+        assert_eq!(lookups.load(Ordering::Relaxed), 1);
+
+        // Executions start on different targets of the first priority, but
+        // all try the second priority's targets last, in the same order
+        let firsts = orders
+            .iter()
+            .map(|order| order[0].as_str())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(firsts.len(), 2, "{orders:?}");
+        for order in &orders {
+            let mut first_tier = order[..2].to_vec();
+            first_tier.sort();
+            assert_eq!(first_tier, ["a.example.com", "b.example.com"]);
+            assert_eq!(order[2..], orders[0][2..], "{orders:?}");
+            assert_eq!(order[4], "fallback.example.com");
+        }
+    }
+
+    #[test]
+    fn waiters_share_lookup_errors() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let client = client(&[], &lookups);
+        let results = block_on(future::join_all((0..3).map(|_| client.cached_uris())));
+        assert_eq!(lookups.load(Ordering::Relaxed), 1);
+
+        // The execution that looked the name up gets its error, and the ones
+        // that waited on it a description of it rather than no targets
+        let mut results = results.into_iter();
+        assert!(matches!(results.next(), Some(Err(Error::Lookup(_)))));
+        for result in results {
+            let Err(error) = result else {
+                panic!("waiter didn't fail")
+            };
+            assert!(error.to_string().contains("lookup failed"), "{error}");
+            assert_eq!(
+                client.lookup_error_kind(&error),
+                Some(LookupErrorKind::Unknown)
+            );
+        }
+    }
+}
@@ -1,24 +1,58 @@
 //! Caches for SRV record targets.
 
-use std::time::Instant;
+use std::{
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
 
 #[derive(Debug)]
 /// A cache of items valid for a limited period of time.
 pub struct Cache<T> {
     valid_until: Instant,
-    items: Box<[T]>,
+    items: Arc<[T]>,
+    expired_at: OnceLock<Instant>,
 }
 
 impl<T> Cache<T> {
     /// Creates a new cache of items valid until some time.
     pub fn new(items: impl Into<Box<[T]>>, valid_until: Instant) -> Self {
-        let items = items.into();
-        Self { valid_until, items }
+        let items = Arc::from(items.into());
+        Self {
+            valid_until,
+            items,
+            expired_at: OnceLock::new(),
+        }
     }
 
-    /// Determines if a cache is valid.
+    /// Determines if a cache is valid, i.e. not empty, expired early, or
+    /// past the time it's valid until.
     pub fn valid(&self) -> bool {
-        !self.items.is_empty() && Instant::now() <= self.valid_until
+        !self.items.is_empty()
+            && self.expired_at.get().is_none()
+            && Instant::now() <= self.valid_until
+    }
+
+    /// Gets the time a cache is valid until, which is when it was expired if
+    /// that was earlier.
+    pub fn valid_until(&self) -> Instant {
+        match self.expired_at.get() {
+            Some(&expired_at) => expired_at.min(self.valid_until),
+            None => self.valid_until,
+        }
+    }
+
+    /// Expires a cache early, keeping its items.
+    pub fn expire(&self) {
+        self.expired_at.get_or_init(Instant::now);
+    }
+
+    /// Creates a cache of the same items valid until another time.
+    pub(crate) fn renewed(&self, valid_until: Instant) -> Self {
+        Self {
+            valid_until,
+            items: self.items.clone(),
+            expired_at: OnceLock::new(),
+        }
     }
 
     /// Gets the items stored in a cache.
@@ -55,6 +89,15 @@ mod tests {
         assert!(!cache.valid());
     }
 
+    #[test]
+    fn expire_invalidates() {
+        let cache = Cache::new(vec![()], Instant::now() + Duration::from_secs(1));
+        cache.expire();
+        assert!(!cache.valid());
+        assert!(cache.valid_until() <= Instant::now());
+        assert_eq!(cache.items().len(), 1);
+    }
+
     #[test]
     fn nonempty_and_fresh_is_valid() {
         let cache = Cache::new(vec![()], Instant::now() + Duration::from_secs(1));
@@ -0,0 +1,52 @@
+//! Commit points of attempts of non-idempotent operations.
+
+use super::ExecuteError;
+use arc_swap::ArcSwapOption;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Handle passed to each attempt of an operation by
+/// [`SrvClient::execute_with_commit`](super::SrvClient::execute_with_commit),
+/// used to signal that the attempt has had observable side effects.
+#[derive(Debug, Clone, Default)]
+pub struct AttemptGuard(Arc<AtomicBool>);
+
+impl AttemptGuard {
+    /// Marks the attempt as committed, e.g. once a request has been sent, so
+    /// that no other target is tried if it fails.
+    pub fn commit(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Determines if the attempt has been committed.
+    pub fn committed(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Guard of an execution's current attempt.
+#[derive(Debug, Default)]
+pub(crate) struct CommitPoint(ArcSwapOption<AtomicBool>);
+
+impl CommitPoint {
+    /// Creates the guard of a new attempt, which becomes the current one.
+    pub(crate) fn guard(&self) -> AttemptGuard {
+        let guard = AttemptGuard::default();
+        self.0.store(Some(guard.0.clone()));
+        guard
+    }
+
+    /// Marks the error of the current attempt as produced after it was
+    /// committed, if it was.
+    pub(crate) fn mark<E>(commit: Option<&Self>, err: ExecuteError<E>) -> ExecuteError<E> {
+        let committed = commit
+            .and_then(|commit| commit.0.load_full())
+            .is_some_and(|committed| committed.load(Ordering::Acquire));
+        match err {
+            ExecuteError::Failed(err) if committed => ExecuteError::Committed(err),
+            err => err,
+        }
+    }
+}
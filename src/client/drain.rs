@@ -0,0 +1,95 @@
+//! Grace periods for targets that disappear from DNS.
+
+use super::normalize::Redacted;
+use arc_swap::ArcSwap;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Targets that were removed from DNS but are kept as candidates, after all
+/// current targets, until their grace period ends or they fail.
+#[derive(Debug, Default)]
+pub(crate) struct Draining(ArcSwap<Vec<(Url, Instant)>>);
+
+impl Draining {
+    /// Notes a refresh replacing the `previous` targets with the `current`
+    /// ones, draining targets that disappeared and reinstating those that
+    /// reappeared.
+    pub(crate) fn update<'a>(
+        &self,
+        previous: impl IntoIterator<Item = &'a Url>,
+        current: &[&Url],
+        grace: Duration,
+        now: Instant,
+    ) {
+        let removed = previous
+            .into_iter()
+            .filter(|uri| !current.contains(uri))
+            .collect::<Vec<_>>();
+        self.0.rcu(|draining| {
+            let mut draining = draining
+                .iter()
+                .filter(|(uri, until)| {
+                    now < *until && !current.contains(&uri) && !removed.contains(&uri)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            for uri in &removed {
+                tracing::trace!(URI = %Redacted(uri), "Draining SRV target removed from DNS");
+            }
+            draining.extend(removed.iter().map(|&uri| (uri.clone(), now + grace)));
+            draining
+        });
+    }
+
+    /// Stops draining a target, e.g. because it failed.
+    pub(crate) fn remove(&self, uri: &Url) {
+        if !self.0.load().iter().any(|(draining, _)| draining == uri) {
+            return;
+        }
+        self.0.rcu(|draining| {
+            draining
+                .iter()
+                .filter(|(draining, _)| draining != uri)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+    }
+
+    /// Gets the targets whose grace period hasn't ended.
+    pub(crate) fn targets(&self) -> Vec<Url> {
+        let now = Instant::now();
+        self.0
+            .load()
+            .iter()
+            .filter(|(_, until)| now < *until)
+            .map(|(uri, _)| uri.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grace_period() {
+        let (a, b): (Url, Url) = (
+            "https://a.example.com".parse().unwrap(),
+            "https://b.example.com".parse().unwrap(),
+        );
+        let draining = Draining::default();
+        let grace = Duration::from_secs(60);
+        let now = Instant::now();
+
+        draining.update([&a, &b], &[&a], grace, now);
+        assert_eq!(draining.targets(), std::slice::from_ref(&b));
+        // Targets stay draining across refreshes in which they're still missing
+        draining.update([&a], &[&a], grace, now);
+        assert_eq!(draining.targets(), std::slice::from_ref(&b));
+        draining.update([&a], &[&a], grace, now + grace);
+        assert!(draining.targets().is_empty());
+
+        draining.update([&a, &b], &[&a], Duration::ZERO, now);
+        assert!(draining.targets().is_empty());
+    }
+}
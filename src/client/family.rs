@@ -0,0 +1,106 @@
+//! Families of the addresses targets resolve to.
+
+use std::net::IpAddr;
+use url::Url;
+
+/// Family of IP addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    /// IPv4 addresses
+    V4,
+    /// IPv6 addresses
+    V6,
+}
+
+impl IpFamily {
+    /// Determines if an address belongs to the family.
+    pub fn matches(&self, addr: IpAddr) -> bool {
+        match self {
+            Self::V4 => addr.is_ipv4(),
+            Self::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// How a [`SrvClient`](super::SrvClient) treats targets based on the families
+/// of the addresses they resolve to. Targets whose addresses can't be looked
+/// up are always treated as if they have addresses of every family.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpFamilyPreference {
+    /// Treat targets the same regardless of address family.
+    #[default]
+    Any,
+    /// Try targets with addresses of a family before those without within
+    /// each priority.
+    Prefer(IpFamily),
+    /// Don't use targets without addresses of a family, nor addresses of the
+    /// other family.
+    Require(IpFamily),
+}
+
+impl IpFamilyPreference {
+    /// Determines if an address may be connected to, which it may unless
+    /// another family is required.
+    pub fn allows(&self, addr: IpAddr) -> bool {
+        match self {
+            Self::Require(family) => family.matches(addr),
+            Self::Any | Self::Prefer(_) => true,
+        }
+    }
+
+    /// Determines if a URL may be connected to, which it may if its host is
+    /// a domain rather than an address.
+    pub(crate) fn allows_host(&self, uri: &Url) -> bool {
+        match uri.host() {
+            Some(url::Host::Ipv4(ip)) => self.allows(ip.into()),
+            Some(url::Host::Ipv6(ip)) => self.allows(ip.into()),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{policy::Rfc2782, resolver::StaticResolver, SrvClient};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    fn hosts(ip_family: IpFamilyPreference) -> Vec<String> {
+        let resolver = StaticResolver::new(
+            [
+                ("192.0.2.1", 443, 1, 1),
+                ("[2001:db8::1]", 443, 2, 1),
+                ("a.example.com", 443, 3, 1),
+            ],
+            Duration::from_secs(60),
+        );
+        let client = SrvClient::<_, Rfc2782>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+        .ip_family(ip_family);
+        client.add_trusted_extra_target("https://192.0.2.9".parse().unwrap(), 4, 100);
+        let (uris, _) = block_on(client.get_fresh_uri_candidates()).unwrap();
+        uris.iter()
+            .map(|uri| uri.host_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn required_family_drops_literal_targets() {
+        // Host names are left alone since their addresses can't be looked up
+        assert_eq!(
+            hosts(IpFamilyPreference::Require(IpFamily::V6)),
+            ["[2001:db8::1]", "a.example.com"]
+        );
+        assert_eq!(
+            hosts(IpFamilyPreference::Require(IpFamily::V4)),
+            ["192.0.2.1", "a.example.com", "192.0.2.9"]
+        );
+        assert_eq!(hosts(IpFamilyPreference::Prefer(IpFamily::V6)).len(), 4);
+        assert_eq!(hosts(IpFamilyPreference::Any).len(), 4);
+    }
+}
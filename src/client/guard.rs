@@ -0,0 +1,142 @@
+//! Guards vetting refreshed caches before they're stored.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// Summary of a refresh of a client's cache, passed to its
+/// [refresh guard](super::SrvClient::refresh_guard).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshSummary {
+    pub(crate) previous_targets: usize,
+    pub(crate) new_targets: usize,
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) previous_age: Option<Duration>,
+    pub(crate) new_ttl: Duration,
+}
+
+impl RefreshSummary {
+    pub(crate) fn new<'a>(
+        previous: impl IntoIterator<Item = &'a Url>,
+        new: impl IntoIterator<Item = &'a Url>,
+        previous_stored_at: Option<Instant>,
+        new_valid_until: Instant,
+    ) -> Self {
+        let target = |uri: &Url| {
+            format!(
+                "{}:{}",
+                uri.host_str().unwrap_or_default(),
+                uri.port_or_known_default().unwrap_or_default()
+            )
+        };
+        let previous = previous.into_iter().map(target).collect::<Vec<_>>();
+        let new = new.into_iter().map(target).collect::<Vec<_>>();
+        let now = Instant::now();
+        Self {
+            previous_targets: previous.len(),
+            new_targets: new.len(),
+            added: new
+                .iter()
+                .filter(|target| !previous.contains(target))
+                .cloned()
+                .collect(),
+            removed: previous
+                .iter()
+                .filter(|target| !new.contains(target))
+                .cloned()
+                .collect(),
+            previous_age: previous_stored_at.map(|at| now.saturating_duration_since(at)),
+            new_ttl: new_valid_until.saturating_duration_since(now),
+        }
+    }
+
+    /// Number of targets in the cache being replaced.
+    pub fn previous_targets(&self) -> usize {
+        self.previous_targets
+    }
+
+    /// Number of targets in the new cache.
+    pub fn new_targets(&self) -> usize {
+        self.new_targets
+    }
+
+    /// Targets in the new cache but not the one being replaced, as
+    /// `host:port`.
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    /// Targets in the cache being replaced but not the new one, as
+    /// `host:port`.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Time since the cache being replaced was stored, or `None` if no cache
+    /// has been stored.
+    pub fn previous_age(&self) -> Option<Duration> {
+        self.previous_age
+    }
+
+    /// Time until the new cache expires.
+    pub fn new_ttl(&self) -> Duration {
+        self.new_ttl
+    }
+}
+
+/// What a client does with a refreshed cache, decided by its
+/// [refresh guard](super::SrvClient::refresh_guard).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshDecision {
+    /// Store the new cache.
+    Accept,
+    /// Discard the new cache and keep using the one being replaced, for at
+    /// least a short grace period before refreshing again.
+    RejectKeepOld,
+    /// Store the new cache, logging a warning.
+    AcceptWithWarning,
+}
+
+/// Creates a refresh guard rejecting refreshes that shrink a client's targets
+/// by more than `max_shrink_percent` percent while the cache being replaced is
+/// younger than `max_age`, e.g. to ride out partial zone pushes.
+///
+/// ```
+/// # use detsys_srv::{shrink_guard, SrvClient};
+/// # use std::time::Duration;
+/// let client = SrvClient::<()>::new("_http._tcp.example.com", "https://example.com".parse().unwrap(), None)
+///     .refresh_guard(shrink_guard(50.0, Duration::from_secs(300)));
+/// ```
+pub fn shrink_guard(
+    max_shrink_percent: f64,
+    max_age: Duration,
+) -> impl Fn(&RefreshSummary) -> RefreshDecision + Send + Sync + 'static {
+    move |summary| {
+        let young = summary.previous_age.is_some_and(|age| age < max_age);
+        let shrunk = summary.previous_targets.saturating_sub(summary.new_targets);
+        let shrink_percent = match summary.previous_targets {
+            0 => 0.0,
+            previous => 100.0 * shrunk as f64 / previous as f64,
+        };
+        match young && shrink_percent > max_shrink_percent {
+            true => RefreshDecision::RejectKeepOld,
+            false => RefreshDecision::Accept,
+        }
+    }
+}
+
+type RefreshGuardFn = dyn Fn(&RefreshSummary) -> RefreshDecision + Send + Sync;
+
+/// Hook vetting refreshed caches.
+#[derive(Clone)]
+pub(crate) struct RefreshGuard(pub(crate) Arc<RefreshGuardFn>);
+
+impl fmt::Debug for RefreshGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RefreshGuard").finish_non_exhaustive()
+    }
+}
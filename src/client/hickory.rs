@@ -0,0 +1,52 @@
+//! Clients looking up SRV records with hickory.
+
+use super::{policy, IpRange, SrvClient};
+use crate::resolver::{hickory::TokioOptions, BindError, ClientSubnetResolver, DefaultResolver};
+use hickory_resolver::name_server::TokioConnectionProvider;
+
+impl<Policy: policy::Policy + Default> SrvClient<DefaultResolver, Policy> {
+    /// Creates a new client like [`SrvClient::new`], with a resolver reading
+    /// the system's DNS configuration and applying `options`, e.g. to send
+    /// lookups from a particular local address.
+    ///
+    /// Fails if `options` can't be applied, e.g. if its local address can't
+    /// be bound.
+    pub fn tokio_with(
+        srv_name: impl ToString,
+        fallback: url::Url,
+        allowed_suffixes: Option<Vec<url::Host>>,
+        options: &TokioOptions,
+    ) -> Result<Self, BindError> {
+        let resolver = DefaultResolver::with_options(options)?;
+        Ok(Self::new_with_resolver(
+            srv_name,
+            fallback,
+            allowed_suffixes,
+            resolver,
+        ))
+    }
+}
+
+impl<Policy: policy::Policy> SrvClient<DefaultResolver, Policy> {
+    /// Makes the client send `subnet` as the EDNS Client Subnet (RFC 7871) of
+    /// its lookups, so that authoritative servers tailoring answers to a
+    /// client's location see the client's subnet rather than its recursive
+    /// resolver's. Only the prefix of the subnet's address is sent.
+    ///
+    /// Lookups are sent with a [`ClientSubnetResolver`] to the name servers
+    /// and with the options of the client's resolver, bypassing its cache.
+    /// The subnet sent is reported in the client's
+    /// [snapshot](SrvClient::snapshot).
+    pub fn edns_client_subnet(
+        self,
+        subnet: IpRange,
+    ) -> SrvClient<ClientSubnetResolver<TokioConnectionProvider>, Policy> {
+        let resolver = ClientSubnetResolver::new(
+            self.resolver.config(),
+            self.resolver.options().clone(),
+            TokioConnectionProvider::default(),
+            subnet,
+        );
+        self.resolver(resolver)
+    }
+}
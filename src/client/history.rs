@@ -0,0 +1,89 @@
+//! Bounded histories of executions.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Outcome of one execution of an operation, kept by a client with an
+/// [execution history](super::SrvClient::execution_history).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionRecord {
+    /// Time the execution started.
+    pub started_at: SystemTime,
+    /// URL of the last attempt: the one that succeeded, or the last one that
+    /// failed. `None` if no attempt was started. Sensitive.
+    pub url: Option<String>,
+    /// Number of attempts started, including any on the fallback.
+    pub attempts: usize,
+    /// Time the execution took, including any lookup.
+    pub duration: Duration,
+    /// Whether the fallback was attempted.
+    pub fallback_used: bool,
+    /// Summary of the error the execution failed with, if it failed.
+    pub error: Option<String>,
+}
+
+/// The last executions of a client, oldest first.
+#[derive(Debug)]
+pub(crate) struct History {
+    capacity: usize,
+    records: Mutex<VecDeque<ExecutionRecord>>,
+}
+
+impl History {
+    /// Creates a history of up to `capacity` executions, or `None` if the
+    /// capacity is 0. Room for records is allocated as they're recorded, so
+    /// generous capacities cost nothing until they're used.
+    pub(crate) fn new(capacity: usize) -> Option<Self> {
+        (capacity > 0).then(|| Self {
+            capacity,
+            records: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// Records an execution, evicting the oldest if the history is full.
+    pub(crate) fn record(&self, record: ExecutionRecord) {
+        let mut records = self.records.lock().expect("history lock is never poisoned");
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    pub(crate) fn records(&self) -> Vec<ExecutionRecord> {
+        let records = self.records.lock().expect("history lock is never poisoned");
+        records.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(attempts: usize) -> ExecutionRecord {
+        ExecutionRecord {
+            started_at: SystemTime::UNIX_EPOCH,
+            url: None,
+            attempts,
+            duration: Duration::ZERO,
+            fallback_used: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn grows_as_recorded() {
+        let history = History::new(usize::MAX).unwrap();
+        history.record(record(1));
+        assert_eq!(history.records(), [record(1)]);
+
+        let history = History::new(2).unwrap();
+        for attempts in 1..=3 {
+            history.record(record(attempts));
+        }
+        assert_eq!(history.records(), [record(2), record(3)]);
+    }
+}
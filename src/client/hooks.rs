@@ -0,0 +1,197 @@
+//! Hooks for customizing how a client uses its targets.
+
+use super::{SvcbRecord, TlsaLookup};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use url::Url;
+
+/// Information about a target passed to a client's hooks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateMeta {
+    pub(crate) attempt: usize,
+    pub(crate) fallback: bool,
+    pub(crate) tlsa: Option<Arc<TlsaLookup>>,
+    pub(crate) host_name: Option<Arc<str>>,
+    pub(crate) svcb: Option<Arc<SvcbRecord>>,
+}
+
+impl CandidateMeta {
+    /// Zero-based index of the attempt within an execution.
+    pub fn attempt(&self) -> usize {
+        self.attempt
+    }
+
+    /// Determines if the target is the client's fallback, used as a last
+    /// resort.
+    pub fn fallback(&self) -> bool {
+        self.fallback
+    }
+
+    /// Result of looking up the target's TLSA records, if the client
+    /// [looks them up](super::SrvClient::lookup_tlsa).
+    pub fn tlsa(&self) -> Option<&TlsaLookup> {
+        self.tlsa.as_deref()
+    }
+
+    /// Host name of the SRV target the candidate stands for, if it was built
+    /// from [glue](super::SrvClient::use_glue) or an
+    /// [expanded address](super::SrvClient::expand_addresses) and so has an
+    /// IP address as its host, for use in TLS SNI and the `Host` header.
+    pub fn host_name(&self) -> Option<&str> {
+        self.host_name.as_deref()
+    }
+
+    /// HTTPS record the target came from, if the client
+    /// [looks them up](super::SrvClient::https_records), giving e.g. the
+    /// ALPN protocols it supports.
+    pub fn svcb(&self) -> Option<&SvcbRecord> {
+        self.svcb.as_deref()
+    }
+}
+
+type MapUrlFn = dyn Fn(Url, &CandidateMeta) -> Url + Send + Sync;
+
+/// Hook transforming each URL before it is passed to an operation.
+#[derive(Clone)]
+pub(crate) struct MapUrl(pub(crate) Arc<MapUrlFn>);
+
+impl MapUrl {
+    /// Transforms a URL, refusing transformations that change its host or
+    /// port since those would bypass the client's validation of its targets.
+    pub(crate) fn apply(&self, uri: &Url, meta: &CandidateMeta) -> Url {
+        let mapped = (self.0)(uri.clone(), meta);
+        if mapped.host() != uri.host()
+            || mapped.port_or_known_default() != uri.port_or_known_default()
+        {
+            tracing::trace!(
+                URI = %super::Redacted(uri),
+                "Ignoring mapped URL because it changes the target's host or port"
+            );
+            return uri.clone();
+        }
+        mapped
+    }
+}
+
+impl fmt::Debug for MapUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MapUrl").finish_non_exhaustive()
+    }
+}
+
+type OnAttemptFn = dyn Fn(&Url, Result<(), ()>, Duration) + Send + Sync;
+
+/// Hook observing whether each attempt of an execution succeeded and how
+/// long it took.
+#[derive(Clone)]
+pub(crate) struct OnAttempt(pub(crate) Arc<OnAttemptFn>);
+
+impl OnAttempt {
+    pub(crate) fn observe(&self, uri: &Url, succeeded: bool, elapsed: Duration) {
+        let result = if succeeded { Ok(()) } else { Err(()) };
+        (self.0)(uri, result, elapsed);
+    }
+}
+
+impl fmt::Debug for OnAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OnAttempt").finish_non_exhaustive()
+    }
+}
+
+type OnOrderFn = dyn Fn(&[Url], u64) + Send + Sync;
+
+/// Hook observing the ordering of targets each execution uses, sampled to
+/// one in every `every` executions.
+pub(crate) struct OnOrder {
+    hook: Arc<OnOrderFn>,
+    every: u64,
+    executions: AtomicU64,
+}
+
+impl OnOrder {
+    pub(crate) fn new(hook: Arc<OnOrderFn>) -> Self {
+        Self {
+            hook,
+            every: 1,
+            executions: Default::default(),
+        }
+    }
+
+    pub(crate) fn sampled(self, every: u64) -> Self {
+        Self {
+            every: every.max(1),
+            ..self
+        }
+    }
+
+    /// Passes an ordering of targets from the given cache generation to the
+    /// hook, if this execution is sampled.
+    pub(crate) fn observe(&self, ordering: &[Url], generation: u64) {
+        let execution = self.executions.fetch_add(1, Ordering::Relaxed);
+        if execution.is_multiple_of(self.every) {
+            (self.hook)(ordering, generation);
+        }
+    }
+}
+
+impl fmt::Debug for OnOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnOrder")
+            .field("every", &self.every)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{policy::Rfc2782, resolver::StaticResolver, SrvClient};
+    use futures::{executor::block_on, StreamExt};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[test]
+    fn on_attempt_sees_streamed_fallback() {
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let resolver = StaticResolver::new([("a.example.com", 443, 1, 1)], Duration::from_secs(60));
+        let client = SrvClient::<_, Rfc2782>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+        .on_attempt({
+            let observed = observed.clone();
+            move |uri, result, _elapsed| {
+                let host = uri.host_str().unwrap().to_owned();
+                observed.lock().unwrap().push((host, result));
+            }
+        });
+        let results = block_on(
+            client
+                .execute_stream(|uri| async move {
+                    match uri.host_str() {
+                        Some("fallback.example.com") => Ok(()),
+                        _ => Err("failed"),
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            *observed.lock().unwrap(),
+            [
+                ("a.example.com".to_owned(), Err(())),
+                ("fallback.example.com".to_owned(), Ok(())),
+            ]
+        );
+    }
+}
@@ -1,12 +1,25 @@
 use crate::{resolver::SrvResolver, Error, SrvClient, SrvRecord};
-use arc_swap::ArcSwapOption;
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
-use std::sync::Arc;
+use futures_util::future::join_all;
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 use url::Url;
 
-pub use super::Cache;
+pub use super::{Cache, TargetKey, TargetStat, TargetStats};
 
 /// Policy for [`SrvClient`] to use when selecting SRV targets to recommend.
+///
+/// Policies that keep per-target state (success counts, latency estimates,
+/// etc.) should store it in a [`TargetStats`], populating it from
+/// [`Policy::note_refresh`] and updating it from the `note_*` hooks.
 #[async_trait]
 pub trait Policy: Sized {
     /// Type of item stored in a client's cache.
@@ -21,13 +34,70 @@ pub trait Policy: Sized {
         client: &SrvClient<Resolver, Self>,
     ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>>;
 
+    /// Obtains a refreshed cache for a client of a policy wrapping this one,
+    /// like [`CircuitBreaker`]. By default the items are refreshed like for
+    /// any client caching them, so policies overriding how
+    /// [`Policy::refresh_cache`] obtains them should override this as well.
+    async fn refresh_wrapped_cache<Resolver, Wrapper>(
+        &self,
+        client: &SrvClient<Resolver, Wrapper>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>>
+    where
+        Self: Sync,
+        Self::CacheItem: RefreshableItem,
+        Resolver: SrvResolver,
+        Wrapper: Policy<CacheItem = Self::CacheItem> + Sync,
+    {
+        Self::CacheItem::refresh(client).await
+    }
+
     /// Creates an iterator of indices corresponding to cache items in the
     /// order a [`SrvClient`] should try using them to perform an operation.
+    ///
+    /// Only called with two or more items; clients try a lone cached item
+    /// directly and never ask a policy to order an empty cache.
     fn order(&self, items: &[Self::CacheItem]) -> Self::Ordering;
 
     /// Converts a reference to a cached item into a reference to a [`Url`].
     fn cache_item_to_uri(item: &Self::CacheItem) -> &Url;
 
+    /// Converts a reference to a cached item into a reference to the
+    /// [`ParsedRecord`] it was built from, if the policy caches records.
+    #[allow(unused_variables)]
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        None
+    }
+
+    /// Gets the per-target statistics kept by the policy, if any, for
+    /// inclusion in [`SrvClient::snapshot`].
+    fn stats(&self) -> Option<&TargetStats> {
+        None
+    }
+
+    /// Determines if a [`SrvClient`] may spread a burst of executions across
+    /// targets by rotating one ordering produced by [`Policy::order`] (see
+    /// [`SrvClient::spread_bursts`]). Policies whose first target is
+    /// deliberately the same for every execution should return `false`.
+    fn spreadable(&self) -> bool {
+        true
+    }
+
+    /// Determines if [`Policy::order`] may leave targets out, in which case a
+    /// [`SrvClient`] orders even a lone target rather than trying it
+    /// directly.
+    fn may_skip_targets(&self) -> bool {
+        false
+    }
+
+    /// Makes any policy adjustments following a refresh of a client's cache,
+    /// before the new `items` are stored.
+    #[allow(unused_variables)]
+    fn note_refresh(&self, items: &[Self::CacheItem]) {}
+
+    /// Makes any policy adjustments before an execution is attempted on `url`.
+    #[allow(unused_variables)]
+    fn note_attempt(&self, url: &Url) {}
+
     /// Makes any policy adjustments following a successful execution on `url`.
     #[allow(unused_variables)]
     fn note_success(&self, url: &Url) {}
@@ -35,13 +105,91 @@ pub trait Policy: Sized {
     /// Makes any policy adjustments following a failed execution on `uri`.
     #[allow(unused_variables)]
     fn note_failure(&self, url: &Url) {}
+
+    /// Makes any policy adjustments following an execution attempt on `url`
+    /// that finished with `outcome`. This is what clients call; by default it
+    /// calls [`Policy::note_success`] or [`Policy::note_failure`], so policies
+    /// that don't need the attempt's duration or kind of failure can
+    /// implement those instead.
+    fn note_outcome(&self, url: &Url, outcome: Outcome) {
+        match outcome.kind {
+            OutcomeKind::Success => self.note_success(url),
+            OutcomeKind::Failed | OutcomeKind::TimedOut => self.note_failure(url),
+        }
+    }
+
+    /// Makes any policy adjustments following an execution attempt on `url`
+    /// that was abandoned before it finished, e.g. because the execution was
+    /// cancelled or dropped. Policies counting attempts in flight from
+    /// [`Policy::note_attempt`] should stop counting it here.
+    #[allow(unused_variables)]
+    fn note_cancelled(&self, url: &Url) {}
+}
+
+/// How an execution attempt on a target finished, noted by
+/// [`Policy::note_outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// Whether the attempt succeeded, failed or timed out.
+    pub kind: OutcomeKind,
+    /// Time the attempt took.
+    pub elapsed: Duration,
+}
+
+impl Outcome {
+    /// Determines if the attempt succeeded.
+    pub fn is_success(&self) -> bool {
+        self.kind == OutcomeKind::Success
+    }
+}
+
+/// Kinds of [`Outcome`]s of execution attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutcomeKind {
+    /// The attempt succeeded
+    Success,
+    /// The attempt failed
+    Failed,
+    /// The attempt didn't finish within the per-attempt timeout of
+    /// [`SrvClient::execute_with_timeout`]
+    TimedOut,
+}
+
+/// Cache item that can be refreshed from a [`SrvClient`] of any policy, so
+/// that policies wrapping another, like [`CircuitBreaker`], can cache the
+/// items of the policy they wrap.
+#[async_trait]
+pub trait RefreshableItem: Sized + Send + Sync {
+    /// Obtains a refreshed cache of items for a client.
+    async fn refresh<Resolver: SrvResolver, P: Policy<CacheItem = Self> + Sync>(
+        client: &SrvClient<Resolver, P>,
+    ) -> Result<Cache<Self>, Error<Resolver::Error>>;
+}
+
+#[async_trait]
+impl RefreshableItem for Url {
+    async fn refresh<Resolver: SrvResolver, P: Policy<CacheItem = Self> + Sync>(
+        client: &SrvClient<Resolver, P>,
+    ) -> Result<Cache<Self>, Error<Resolver::Error>> {
+        let (uris, valid_until) = client.get_fresh_uri_candidates().await?;
+        Ok(Cache::new(uris, valid_until))
+    }
+}
+
+#[async_trait]
+impl RefreshableItem for ParsedRecord {
+    async fn refresh<Resolver: SrvResolver, P: Policy<CacheItem = Self> + Sync>(
+        client: &SrvClient<Resolver, P>,
+    ) -> Result<Cache<Self>, Error<Resolver::Error>> {
+        Rfc2782::refresh_records(client).await
+    }
 }
 
 /// Policy that selects targets based on past successes--if a target was used
 /// successfully in a past execution, it will be recommended first.
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct Affinity {
-    last_working_target: ArcSwapOption<Url>,
+    stats: TargetStats,
 }
 
 #[async_trait]
@@ -58,16 +206,32 @@ impl Policy for Affinity {
     }
 
     fn order(&self, uris: &[Url]) -> Self::Ordering {
-        let preferred = self.last_working_target.load();
-        Affinity::uris_preferring(uris, preferred.as_deref())
+        let preferred = uris
+            .iter()
+            .filter_map(|uri| Some((self.stats.get(uri)?.last_success()?, uri)))
+            .max_by_key(|&(last_success, _)| last_success)
+            .map(|(_, uri)| uri);
+        Affinity::uris_preferring(uris, preferred)
     }
 
     fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
         item
     }
 
+    fn stats(&self) -> Option<&TargetStats> {
+        Some(&self.stats)
+    }
+
+    fn spreadable(&self) -> bool {
+        false
+    }
+
+    fn note_refresh(&self, uris: &[Url]) {
+        self.stats.retain_targets(uris);
+    }
+
     fn note_success(&self, uri: &Url) {
-        self.last_working_target.store(Some(Arc::new(uri.clone())));
+        self.stats.record_success(uri);
     }
 }
 
@@ -119,24 +283,99 @@ impl Iterator for AffinityUrlIter {
 
 /// Policy that selects targets based on the algorithm in RFC 2782, reshuffling
 /// by weight for each selection.
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct Rfc2782;
 
+impl Rfc2782 {
+    /// Obtains a refreshed cache of parsed records for a client, regardless of
+    /// the client's policy.
+    async fn refresh_records<Resolver: SrvResolver, P: Policy>(
+        client: &SrvClient<Resolver, P>,
+    ) -> Result<Cache<ParsedRecord>, Error<Resolver::Error>> {
+        let (mut parsed, valid_until) = {
+            let (records, valid_until, glue) = client.get_parsed_candidate_records().await?;
+            let parsed = records.into_iter().collect::<Result<Vec<_>, _>>()?;
+            (client.apply_glue(parsed, &glue), valid_until)
+        };
+        parsed.extend(client.extra_records());
+        parsed
+            .iter_mut()
+            .for_each(|record| client.bias_weight(record));
+        let parsed = client.apply_ip_family(parsed).await;
+        let permitted = join_all(parsed.iter().map(|p| client.target_permitted(&p.uri))).await;
+        let parsed = parsed
+            .into_iter()
+            .zip(permitted)
+            .filter_map(|(parsed, permitted)| permitted.then_some(parsed))
+            .collect::<Vec<_>>();
+        Ok(Cache::new(parsed, valid_until))
+    }
+
+    fn order_with_rng(
+        records: &[ParsedRecord],
+        mut rng: impl rand::Rng,
+    ) -> <Self as Policy>::Ordering {
+        let mut indices = (0..records.len()).collect::<Vec<_>>();
+        indices.sort_by_cached_key(|&idx| records[idx].sort_key(&mut rng));
+        indices.into_iter()
+    }
+}
+
 /// Representation of a SRV record with its target and port parsed into a [`Url`].
+#[derive(Debug, Clone)]
 pub struct ParsedRecord {
-    uri: Url,
-    priority: u16,
-    weight: u16,
+    pub(crate) uri: Url,
+    pub(crate) priority: u16,
+    pub(crate) weight: u16,
+    /// Weight after applying a client's weight bias, wider than `weight` so
+    /// that biased weights saturate rather than overflow.
+    pub(crate) effective_weight: u32,
+    /// Whether the record's target lacks addresses of a client's preferred
+    /// [`IpFamily`](crate::IpFamily), sorting it after those that don't.
+    pub(crate) unpreferred_family: bool,
 }
 
 impl ParsedRecord {
-    fn new<Record: SrvRecord>(record: &Record, uri: Url) -> Self {
+    pub(crate) fn new<Record: SrvRecord>(record: &Record, uri: Url) -> Self {
+        Self::from_parts(uri, record.priority(), record.weight())
+    }
+
+    pub(crate) fn from_parts(uri: Url, priority: u16, weight: u16) -> Self {
         Self {
             uri,
-            priority: record.priority(),
-            weight: record.weight(),
+            priority,
+            weight,
+            effective_weight: weight.into(),
+            unpreferred_family: false,
         }
     }
+
+    /// Gets the record's target and port as a [`Url`].
+    pub fn uri(&self) -> &Url {
+        &self.uri
+    }
+
+    /// Gets the record's priority.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Gets the record's weight, as it appeared in DNS.
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Gets the record's weight after applying the client's weight bias (see
+    /// [`SrvClient::weight_bias`]).
+    pub fn effective_weight(&self) -> u32 {
+        self.effective_weight
+    }
+
+    pub(crate) fn sort_key(&self, rng: impl rand::Rng) -> (u16, bool, std::cmp::Reverse<u64>) {
+        let (priority, weight) =
+            crate::record::wide_sort_key(self.priority, self.effective_weight, rng);
+        (priority, self.unpreferred_family, weight)
+    }
 }
 
 #[async_trait]
@@ -148,31 +387,663 @@ impl Policy for Rfc2782 {
         &self,
         client: &SrvClient<Resolver, Self>,
     ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
-        let (records, valid_until) = client.get_srv_records().await?;
-        let parsed = records
+        Rfc2782::refresh_records(client).await
+    }
+
+    fn order(&self, records: &[ParsedRecord]) -> Self::Ordering {
+        Rfc2782::order_with_rng(records, rand::rng())
+    }
+
+    fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
+        &item.uri
+    }
+
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        Some(item)
+    }
+}
+
+/// Policy that selects targets like [`Rfc2782`], but ramps up the weight of
+/// targets that newly appear in DNS so that they aren't sent a full share of
+/// traffic while their caches are cold.
+///
+/// A new target's weight starts at a tenth of its full weight and grows
+/// linearly to its full weight over the warm-up period. Targets present when
+/// a client first populates its cache are considered warm already.
+#[derive(Debug)]
+pub struct SlowStart {
+    warm_up: Duration,
+    populated: AtomicBool,
+    /// Time each target was first seen, or `None` for targets present in the
+    /// first population of the cache.
+    first_seen: ArcSwap<HashMap<TargetKey, Option<Instant>>>,
+}
+
+impl SlowStart {
+    /// Fraction of its full weight a new target starts with.
+    const INITIAL_FRACTION: f64 = 0.1;
+
+    /// Creates a policy ramping new targets up to full weight over `warm_up`.
+    pub fn new(warm_up: Duration) -> Self {
+        Self {
+            warm_up,
+            populated: AtomicBool::new(false),
+            first_seen: Default::default(),
+        }
+    }
+
+    /// Gets the duration over which new targets are ramped up to full weight.
+    pub fn warm_up(&self) -> Duration {
+        self.warm_up
+    }
+
+    fn note_refresh_at(&self, records: &[ParsedRecord], now: Instant) {
+        let warm = !self.populated.swap(true, Ordering::AcqRel);
+        self.first_seen.rcu(|old| {
+            records
+                .iter()
+                .map(|record| {
+                    let key = TargetKey::new(&record.uri);
+                    let first_seen = match old.get(&key) {
+                        Some(&first_seen) => first_seen,
+                        None if warm => None,
+                        None => Some(now),
+                    };
+                    (key, first_seen)
+                })
+                .collect::<HashMap<_, _>>()
+        });
+    }
+
+    /// Gets the fraction of its full weight a target is given at `now`.
+    fn ramp(&self, uri: &Url, now: Instant) -> f64 {
+        let first_seen = self.first_seen.load();
+        let Some(Some(first_seen)) = first_seen.get(&TargetKey::new(uri)) else {
+            return 1.0;
+        };
+        let elapsed = now.saturating_duration_since(*first_seen);
+        if elapsed >= self.warm_up {
+            return 1.0;
+        }
+        let progress = elapsed.as_secs_f64() / self.warm_up.as_secs_f64();
+        Self::INITIAL_FRACTION + (1.0 - Self::INITIAL_FRACTION) * progress
+    }
+
+    fn order_at(
+        &self,
+        records: &[ParsedRecord],
+        rng: impl rand::Rng,
+        now: Instant,
+    ) -> <Self as Policy>::Ordering {
+        let ramped = records
             .iter()
             .map(|record| {
-                client
-                    .parse_record(record)
-                    .map(|uri| ParsedRecord::new(record, uri))
+                let ramp = self.ramp(&record.uri, now);
+                ParsedRecord {
+                    effective_weight: (f64::from(record.effective_weight) * ramp).ceil() as u32,
+                    ..record.clone()
+                }
             })
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Cache::new(parsed, valid_until))
+            .collect::<Vec<_>>();
+        Rfc2782::order_with_rng(&ramped, rng)
+    }
+}
+
+#[async_trait]
+impl Policy for SlowStart {
+    type CacheItem = ParsedRecord;
+    type Ordering = <Vec<usize> as IntoIterator>::IntoIter;
+
+    async fn refresh_cache<Resolver: SrvResolver>(
+        &self,
+        client: &SrvClient<Resolver, Self>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
+        Rfc2782::refresh_records(client).await
     }
 
     fn order(&self, records: &[ParsedRecord]) -> Self::Ordering {
-        let mut indices = (0..records.len()).collect::<Vec<_>>();
-        let mut rng = rand::rng();
-        indices.sort_by_cached_key(|&idx| {
-            let (priority, weight) = (records[idx].priority, records[idx].weight);
-            crate::record::sort_key(priority, weight, &mut rng)
-        });
+        self.order_at(records, rand::rng(), Instant::now())
+    }
+
+    fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
+        &item.uri
+    }
+
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        Some(item)
+    }
+
+    fn note_refresh(&self, records: &[ParsedRecord]) {
+        self.note_refresh_at(records, Instant::now());
+    }
+}
+
+/// Policy that selects targets in proportion to their RFC 2782 weights over
+/// many executions, rather than by chance for each one like [`Rfc2782`].
+///
+/// Within each priority, targets are selected by smooth weighted round-robin:
+/// each attempt on a target credits every target of its priority with its
+/// weight, and the attempted target then pays back the total weight of its
+/// priority. Orderings put the target that would have the most credit first,
+/// without changing any credit, so that only attempts advance the
+/// selection. A target with twice the weight of another is thus tried first
+/// twice as often, interleaved rather than in runs. Targets of weight 0 are
+/// tried after the others of their priority, unless all targets of their
+/// priority have weight 0, in which case they take turns.
+#[derive(Debug, Default)]
+pub struct WeightedRoundRobin {
+    state: Mutex<RoundRobin>,
+}
+
+/// Selection state of a [`WeightedRoundRobin`].
+#[derive(Debug, Default)]
+struct RoundRobin {
+    /// Credit of each target, carried across attempts.
+    credits: HashMap<TargetKey, i64>,
+    /// Tier and selection weight of each cached target, as of the last
+    /// refresh.
+    targets: HashMap<TargetKey, ((u16, bool), i64)>,
+}
+
+impl WeightedRoundRobin {
+    fn state(&self) -> std::sync::MutexGuard<'_, RoundRobin> {
+        self.state
+            .lock()
+            .expect("round-robin lock is never poisoned")
+    }
+
+    /// Gets the tier and selection weight of each record, where targets of
+    /// weight 0 count as weight 1 in tiers where they all have weight 0.
+    fn weights(records: &[ParsedRecord]) -> Vec<((u16, bool), i64)> {
+        let tier = |record: &ParsedRecord| (record.priority, record.unpreferred_family);
+        records
+            .iter()
+            .map(|record| {
+                let weightless = records
+                    .iter()
+                    .filter(|other| tier(other) == tier(record))
+                    .all(|other| other.effective_weight == 0);
+                let weight = match weightless {
+                    true => 1,
+                    false => i64::from(record.effective_weight),
+                };
+                (tier(record), weight)
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Policy for WeightedRoundRobin {
+    type CacheItem = ParsedRecord;
+    type Ordering = <Vec<usize> as IntoIterator>::IntoIter;
+
+    async fn refresh_cache<Resolver: SrvResolver>(
+        &self,
+        client: &SrvClient<Resolver, Self>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
+        Rfc2782::refresh_records(client).await
+    }
+
+    fn order(&self, records: &[ParsedRecord]) -> Self::Ordering {
+        let weights = Self::weights(records);
+        let state = self.state();
+        let mut ordering = (0..records.len())
+            .map(|idx| {
+                let (tier, weight) = weights[idx];
+                let credit = state
+                    .credits
+                    .get(&TargetKey::new(&records[idx].uri))
+                    .copied()
+                    .unwrap_or_default();
+                (tier, weight == 0, std::cmp::Reverse(credit + weight), idx)
+            })
+            .collect::<Vec<_>>();
+        ordering.sort_unstable();
+        ordering
+            .into_iter()
+            .map(|(_, _, _, idx)| idx)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
+        &item.uri
+    }
+
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        Some(item)
+    }
+
+    fn spreadable(&self) -> bool {
+        // Rotating orderings would skew selections away from the weights
+        false
+    }
+
+    fn note_refresh(&self, records: &[ParsedRecord]) {
+        let targets = records
+            .iter()
+            .map(|record| TargetKey::new(&record.uri))
+            .zip(Self::weights(records))
+            .collect::<HashMap<_, _>>();
+        let mut state = self.state();
+        state.credits.retain(|key, _| targets.contains_key(key));
+        state.targets = targets;
+    }
+
+    fn note_attempt(&self, url: &Url) {
+        let key = TargetKey::new(url);
+        let mut state = self.state();
+        let RoundRobin { credits, targets } = &mut *state;
+        let Some(&(tier, _)) = targets.get(&key) else {
+            return;
+        };
+        let mut total = 0;
+        for (&target, &(_, weight)) in targets.iter().filter(|(_, (other, _))| *other == tier) {
+            *credits.entry(target).or_default() += weight;
+            total += weight;
+        }
+        *credits.entry(key).or_default() -= total;
+    }
+}
+
+/// Policy that selects the targets responding fastest, by an exponentially
+/// weighted moving average (EWMA) of the latency of successful attempts,
+/// for targets whose priorities and weights don't reflect how far away they
+/// are.
+///
+/// Targets no attempt has finished on yet are tried first, so that they get a
+/// sample, ordered among themselves like [`Rfc2782`] (so orderings of targets
+/// that are all unseen are exactly those of [`Rfc2782`]). Targets are then
+/// ordered by ascending average latency regardless of priority, followed by
+/// those whose attempts have all failed.
+#[derive(Debug)]
+pub struct LeastLatency {
+    smoothing: f64,
+    /// Statistics of each target, with their average latency in seconds as
+    /// their value.
+    stats: TargetStats,
+}
+
+impl Default for LeastLatency {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.3,
+            stats: TargetStats::default(),
+        }
+    }
+}
+
+impl LeastLatency {
+    /// Sets the weight of each new latency sample in a target's average, from
+    /// 0 (exclusive; new samples barely move the average) to 1 (only the last
+    /// sample counts). Defaults to 0.3.
+    pub fn smoothing(self, smoothing: f64) -> Self {
+        Self {
+            smoothing: smoothing.clamp(f64::MIN_POSITIVE, 1.0),
+            ..self
+        }
+    }
+
+    /// Gets the average latency of successful attempts on `url`, if any.
+    pub fn average_latency(&self, url: &Url) -> Option<Duration> {
+        let stat = self.stats.get(url)?;
+        (stat.successes() > 0).then(|| Duration::from_secs_f64(stat.value()))
+    }
+
+    fn order_with_rng(
+        &self,
+        records: &[ParsedRecord],
+        rng: impl rand::Rng,
+    ) -> <Self as Policy>::Ordering {
+        // Unseen, then by average latency, then those that only failed
+        let key = |record: &ParsedRecord| match self.stats.get(&record.uri) {
+            Some(stat) if stat.successes() > 0 => (1, stat.value()),
+            Some(stat) if stat.failures() > 0 => (2, 0.0),
+            _ => (0, 0.0),
+        };
+        let keys = records.iter().map(key).collect::<Vec<_>>();
+        let mut indices = Rfc2782::order_with_rng(records, rng).collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| keys[a].partial_cmp(&keys[b]).unwrap_or(CmpOrdering::Equal));
         indices.into_iter()
     }
+}
+
+#[async_trait]
+impl Policy for LeastLatency {
+    type CacheItem = ParsedRecord;
+    type Ordering = <Vec<usize> as IntoIterator>::IntoIter;
+
+    async fn refresh_cache<Resolver: SrvResolver>(
+        &self,
+        client: &SrvClient<Resolver, Self>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
+        Rfc2782::refresh_records(client).await
+    }
+
+    fn order(&self, records: &[ParsedRecord]) -> Self::Ordering {
+        self.order_with_rng(records, rand::rng())
+    }
 
     fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
         &item.uri
     }
+
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        Some(item)
+    }
+
+    fn stats(&self) -> Option<&TargetStats> {
+        Some(&self.stats)
+    }
+
+    fn note_refresh(&self, records: &[ParsedRecord]) {
+        self.stats
+            .retain_targets(records.iter().map(|record| &record.uri));
+    }
+
+    fn note_attempt(&self, url: &Url) {
+        self.stats.record_attempt(url);
+    }
+
+    fn note_outcome(&self, url: &Url, outcome: Outcome) {
+        let Some(stat) = self.stats.get(url) else {
+            return;
+        };
+        if !outcome.is_success() {
+            stat.record_failure();
+            return;
+        }
+        let sample = outcome.elapsed.as_secs_f64();
+        let first = stat.successes() == 0;
+        stat.update_value(|average| match first {
+            true => sample,
+            false => self.smoothing * sample + (1.0 - self.smoothing) * average,
+        });
+        stat.record_success();
+    }
+
+    fn note_cancelled(&self, url: &Url) {
+        self.stats.record_cancelled(url);
+    }
+}
+
+/// Policy wrapping another, excluding targets that keep failing from its
+/// orderings for a cooldown period rather than trying them on every
+/// execution.
+///
+/// Once a target fails a number of times in a row, its circuit opens and it's
+/// left out of orderings until the cooldown since its last failure elapses.
+/// It's then half-open: included again, but only while no other execution is
+/// attempting it, so that a single execution probes whether it has recovered.
+/// A probe that hasn't finished within another cooldown, like one whose
+/// execution was cancelled, is presumed abandoned and another is admitted.
+/// A success closes its circuit, while a failure opens it for another
+/// cooldown. If every circuit is open, executions use the client's fallbacks.
+///
+/// Like orderings of other policies, circuits don't apply to lone targets,
+/// which clients try directly.
+///
+/// ```
+/// # use detsys_srv::{policy::{CircuitBreaker, Rfc2782}, SrvClient};
+/// # use detsys_srv::resolver::FnResolver;
+/// # use std::time::Duration;
+/// # let client = SrvClient::<_>::new_with_resolver(
+/// #     "_http._tcp.example.com",
+/// #     "https://example.com".parse().unwrap(),
+/// #     None,
+/// #     FnResolver::new(|_| async { Ok((vec![], std::time::Instant::now())) }),
+/// # );
+/// let client = client.policy(CircuitBreaker::new(Rfc2782, 5, Duration::from_secs(30)));
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker<P> {
+    inner: P,
+    threshold: u32,
+    cooldown: Duration,
+    /// Statistics of each target, with the number of consecutive failures as
+    /// their value.
+    stats: TargetStats,
+}
+
+impl<P> CircuitBreaker<P> {
+    /// Creates a policy wrapping `inner`, opening the circuit of targets that
+    /// fail `threshold` times in a row for `cooldown`. Thresholds below 1 are
+    /// treated as 1.
+    pub fn new(inner: P, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            inner,
+            threshold: threshold.max(1),
+            cooldown,
+            stats: TargetStats::default(),
+        }
+    }
+
+    /// Gets the wrapped policy.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Gets the number of consecutive failures that opens a target's circuit.
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Gets how long a target's circuit stays open after its last failure.
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Determines if `uri` may be tried at `now`.
+    fn admits(&self, uri: &Url, now: Instant) -> bool {
+        let Some(stat) = self.stats.get(uri) else {
+            return true;
+        };
+        if stat.value() < f64::from(self.threshold) {
+            return true;
+        }
+        let open_until = stat.last_failure().map(|at| at + self.cooldown);
+        let probing = stat.in_flight() > 0
+            && stat
+                .last_attempt()
+                .is_some_and(|probed| now < probed + self.cooldown);
+        open_until.is_none_or(|open_until| now >= open_until) && !probing
+    }
+}
+
+impl<P: Policy> CircuitBreaker<P> {
+    fn order_at(&self, items: &[P::CacheItem], now: Instant) -> std::vec::IntoIter<usize> {
+        self.inner
+            .order(items)
+            .filter(|&idx| self.admits(P::cache_item_to_uri(&items[idx]), now))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+#[async_trait]
+impl<P> Policy for CircuitBreaker<P>
+where
+    P: Policy + Sync,
+    P::CacheItem: RefreshableItem,
+{
+    type CacheItem = P::CacheItem;
+    type Ordering = <Vec<usize> as IntoIterator>::IntoIter;
+
+    async fn refresh_cache<Resolver: SrvResolver>(
+        &self,
+        client: &SrvClient<Resolver, Self>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
+        self.inner.refresh_wrapped_cache(client).await
+    }
+
+    fn order(&self, items: &[Self::CacheItem]) -> Self::Ordering {
+        self.order_at(items, Instant::now())
+    }
+
+    fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
+        P::cache_item_to_uri(item)
+    }
+
+    fn cache_item_to_record(item: &Self::CacheItem) -> Option<&ParsedRecord> {
+        P::cache_item_to_record(item)
+    }
+
+    fn stats(&self) -> Option<&TargetStats> {
+        self.inner.stats().or(Some(&self.stats))
+    }
+
+    fn spreadable(&self) -> bool {
+        self.inner.spreadable()
+    }
+
+    fn may_skip_targets(&self) -> bool {
+        self.inner.may_skip_targets()
+    }
+
+    fn note_refresh(&self, items: &[Self::CacheItem]) {
+        self.stats
+            .retain_targets(items.iter().map(P::cache_item_to_uri));
+        self.inner.note_refresh(items);
+    }
+
+    fn note_attempt(&self, url: &Url) {
+        self.stats.record_attempt(url);
+        self.inner.note_attempt(url);
+    }
+
+    fn note_outcome(&self, url: &Url, outcome: Outcome) {
+        if let Some(stat) = self.stats.get(url) {
+            if outcome.is_success() {
+                stat.record_success();
+                stat.set_value(0.0);
+            } else {
+                stat.record_failure();
+                stat.update_value(|failures| failures + 1.0);
+                if stat.value() == f64::from(self.threshold) {
+                    tracing::debug!(%url, cooldown = ?self.cooldown, "Opening circuit of failing target");
+                }
+            }
+        }
+        self.inner.note_outcome(url, outcome);
+    }
+
+    fn note_cancelled(&self, url: &Url) {
+        self.stats.record_cancelled(url);
+        self.inner.note_cancelled(url);
+    }
+}
+
+/// Type of the closure ordering targets for a [`FnPolicy`].
+type OrderFn = Box<dyn Fn(&[Url]) -> Vec<usize> + Send + Sync>;
+
+/// Type of the closures observing executions of a [`FnPolicy`].
+type UrlHook = Box<dyn Fn(&Url) + Send + Sync>;
+
+/// Policy that orders targets with a closure, for simple orderings that don't
+/// justify implementing [`Policy`], optionally observing executions with
+/// closures as well.
+///
+/// The ordering closure produces the indices of the targets to try, in order,
+/// and may leave targets out, even when there's only one.
+///
+/// ```
+/// # use detsys_srv::{policy::FnPolicy, SrvClient};
+/// # use detsys_srv::resolver::FnResolver;
+/// # let client = SrvClient::<_>::new_with_resolver(
+/// #     "_http._tcp.example.com",
+/// #     "https://example.com".parse().unwrap(),
+/// #     None,
+/// #     FnResolver::new(|_| async { Ok((vec![], std::time::Instant::now())) }),
+/// # );
+/// let reversed = FnPolicy::new(|uris| (0..uris.len()).rev().collect())
+///     .on_failure(|uri| eprintln!("{uri} failed"));
+/// let client = client.policy(reversed);
+/// ```
+pub struct FnPolicy {
+    order: OrderFn,
+    on_success: Option<UrlHook>,
+    on_failure: Option<UrlHook>,
+}
+
+impl FnPolicy {
+    /// Creates a policy ordering targets with `order`.
+    pub fn new(order: impl Fn(&[Url]) -> Vec<usize> + Send + Sync + 'static) -> Self {
+        Self {
+            order: Box::new(order),
+            on_success: None,
+            on_failure: None,
+        }
+    }
+
+    /// Sets a closure called following each successful execution on a target.
+    pub fn on_success(self, on_success: impl Fn(&Url) + Send + Sync + 'static) -> Self {
+        Self {
+            on_success: Some(Box::new(on_success)),
+            ..self
+        }
+    }
+
+    /// Sets a closure called following each failed execution on a target.
+    pub fn on_failure(self, on_failure: impl Fn(&Url) + Send + Sync + 'static) -> Self {
+        Self {
+            on_failure: Some(Box::new(on_failure)),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Debug for FnPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnPolicy")
+            .field("on_success", &self.on_success.is_some())
+            .field("on_failure", &self.on_failure.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Policy for FnPolicy {
+    type CacheItem = Url;
+    type Ordering = <Vec<usize> as IntoIterator>::IntoIter;
+
+    async fn refresh_cache<Resolver: SrvResolver>(
+        &self,
+        client: &SrvClient<Resolver, Self>,
+    ) -> Result<Cache<Self::CacheItem>, Error<Resolver::Error>> {
+        let (uris, valid_until) = client.get_fresh_uri_candidates().await?;
+        Ok(Cache::new(uris, valid_until))
+    }
+
+    fn order(&self, uris: &[Url]) -> Self::Ordering {
+        let mut ordering = (self.order)(uris);
+        ordering.retain(|&idx| idx < uris.len());
+        ordering.into_iter()
+    }
+
+    fn cache_item_to_uri(item: &Self::CacheItem) -> &Url {
+        item
+    }
+
+    fn spreadable(&self) -> bool {
+        false
+    }
+
+    fn may_skip_targets(&self) -> bool {
+        true
+    }
+
+    fn note_success(&self, uri: &Url) {
+        if let Some(on_success) = &self.on_success {
+            on_success(uri);
+        }
+    }
+
+    fn note_failure(&self, uri: &Url) {
+        if let Some(on_failure) = &self.on_failure {
+            on_failure(uri);
+        }
+    }
 }
 
 #[test]
@@ -205,10 +1076,8 @@ fn balance_uris_iter_order() {
 
     let cache = priorities
         .iter()
-        .map(|(uri, &priority)| ParsedRecord {
-            uri: uri.clone(),
-            priority,
-            weight: rand::random::<u8>() as u16,
+        .map(|(uri, &priority)| {
+            ParsedRecord::from_parts(uri.clone(), priority, rand::random::<u8>() as u16)
         })
         .collect::<Vec<_>>();
 
@@ -226,3 +1095,377 @@ fn balance_uris_iter_order() {
         ordered(Rfc2782.order(&cache));
     }
 }
+
+#[test]
+fn affinity_prefers_most_recent_success() {
+    let a: Url = "https://a.example.com".parse().unwrap();
+    let b: Url = "https://b.example.com".parse().unwrap();
+    let c: Url = "https://c.example.com".parse().unwrap();
+    let cache = vec![a.clone(), b.clone(), c.clone()];
+    let policy = Affinity::default();
+    policy.note_refresh(&cache);
+    let first = |policy: &Affinity| &cache[policy.order(&cache).next().unwrap()];
+
+    assert_eq!(first(&policy), &a);
+    policy.note_success(&c);
+    assert_eq!(first(&policy), &c);
+    policy.note_success(&b);
+    assert_eq!(first(&policy), &b);
+
+    // Stats of targets that disappear from the cache are evicted
+    policy.note_refresh(&cache[..1]);
+    policy.note_refresh(&cache);
+    assert_eq!(first(&policy), &a);
+}
+
+#[test]
+fn weight_bias_shifts_selection() {
+    use rand::SeedableRng;
+
+    let biased = ParsedRecord {
+        effective_weight: 30,
+        ..ParsedRecord::from_parts("https://a.use1.example.com".parse().unwrap(), 1, 10)
+    };
+    let unbiased = ParsedRecord::from_parts("https://b.usw2.example.com".parse().unwrap(), 1, 10);
+    let records = [biased, unbiased];
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(2782);
+    let trials = 10_000;
+    let biased_first = (0..trials)
+        .filter(|_| Rfc2782::order_with_rng(&records, &mut rng).next() == Some(0))
+        .count();
+    // Weights of 30 and 10 put the biased record first 5/6 of the time
+    assert!((8_000..8_700).contains(&biased_first), "{biased_first}");
+}
+
+#[test]
+fn slow_start_ramps_new_targets() {
+    use rand::SeedableRng;
+
+    let record = |host: &str| {
+        let uri = format!("https://{host}.example.com").parse().unwrap();
+        ParsedRecord::from_parts(uri, 1, 100)
+    };
+    let (old, new) = (record("old"), record("new"));
+    let policy = SlowStart::new(Duration::from_secs(100));
+    let start = Instant::now();
+
+    // Everything is new on the first population, so nothing is ramped
+    policy.note_refresh_at(std::slice::from_ref(&old), start);
+    assert_eq!(policy.ramp(&old.uri, start), 1.0);
+
+    policy.note_refresh_at(&[old.clone(), new.clone()], start);
+    let at = |secs| start + Duration::from_secs(secs);
+    assert_eq!(policy.ramp(&new.uri, at(0)), 0.1);
+    assert!((policy.ramp(&new.uri, at(50)) - 0.55).abs() < 1e-9);
+    assert_eq!(policy.ramp(&new.uri, at(100)), 1.0);
+    assert_eq!(policy.ramp(&old.uri, at(0)), 1.0);
+
+    // First-seen times are carried across refreshes
+    policy.note_refresh_at(&[old.clone(), new.clone()], at(50));
+    assert!((policy.ramp(&new.uri, at(50)) - 0.55).abs() < 1e-9);
+
+    let records = [old, new];
+    let mut rng = rand::rngs::StdRng::seed_from_u64(2782);
+    let mut new_first = |secs| {
+        (0..10_000)
+            .filter(|_| policy.order_at(&records, &mut rng, at(secs)).next() == Some(1))
+            .count()
+    };
+    // Weights of 10 and 100 put the new record first 1/20 of the time
+    let (cold, warm) = (new_first(0), new_first(100));
+    assert!((300..700).contains(&cold), "{cold}");
+    assert!((4_700..5_300).contains(&warm), "{warm}");
+}
+
+#[test]
+fn weighted_round_robin_converges_to_weights() {
+    let record = |host: &str, priority, weight| {
+        let uri = format!("https://{host}.example.com").parse().unwrap();
+        ParsedRecord::from_parts(uri, priority, weight)
+    };
+    let records = [
+        record("a", 1, 100),
+        record("backup", 2, 1000),
+        record("b", 1, 200),
+        record("c", 1, 300),
+        record("d", 1, 0),
+    ];
+    let policy = WeightedRoundRobin::default();
+    policy.note_refresh(&records);
+    // Executions attempt the first target of each ordering
+    let select = |policy: &WeightedRoundRobin, records: &[ParsedRecord]| {
+        let first = policy.order(records).next().unwrap();
+        policy.note_attempt(&records[first].uri);
+        first
+    };
+
+    // Orderings alone don't advance the selection
+    let ordering = policy.order(&records).collect::<Vec<_>>();
+    assert_eq!(policy.order(&records).collect::<Vec<_>>(), ordering);
+
+    let trials = 10_000;
+    let mut firsts = [0usize; 5];
+    for _ in 0..trials {
+        let ordering = policy.order(&records).collect::<Vec<_>>();
+        assert_eq!(ordering.len(), records.len());
+        assert_eq!(ordering[3..], [4, 1]);
+        firsts[select(&policy, &records)] += 1;
+    }
+    // Weights of 100, 200 and 300 put targets first 1/6, 2/6 and 3/6 of the
+    // time, within 1%
+    for (idx, expected) in [(0, 1_667), (2, 3_333), (3, 5_000)] {
+        let observed = firsts[idx];
+        assert!(observed.abs_diff(expected) <= trials / 100, "{firsts:?}");
+    }
+    assert_eq!(firsts[1] + firsts[4], 0);
+
+    // Selections are interleaved rather than in runs
+    let run = (0..6)
+        .map(|_| select(&policy, &records))
+        .collect::<Vec<_>>();
+    assert!(
+        run.windows(3).all(|w| w[0] != w[1] || w[1] != w[2]),
+        "{run:?}"
+    );
+
+    // Weightless targets take turns
+    let weightless = [record("a", 1, 0), record("b", 1, 0)];
+    let fresh = WeightedRoundRobin::default();
+    fresh.note_refresh(&weightless);
+    let firsts = (0..4)
+        .map(|_| select(&fresh, &weightless))
+        .collect::<Vec<_>>();
+    assert_eq!(firsts, [0, 1, 0, 1]);
+
+    // Credit of targets that disappear from the cache is evicted
+    policy.note_refresh(&records[..1]);
+    assert_eq!(policy.state().credits.len(), 1);
+}
+
+#[test]
+fn circuit_breaker_excludes_failing_targets() {
+    let uris =
+        ["https://a.example.com", "https://b.example.com"].map(|uri| uri.parse::<Url>().unwrap());
+    let policy = CircuitBreaker::new(Affinity::default(), 2, Duration::from_secs(30));
+    policy.note_refresh(&uris);
+    let order = |now| policy.order_at(&uris, now).collect::<Vec<_>>();
+    let outcome = |kind| Outcome {
+        kind,
+        elapsed: Duration::from_millis(10),
+    };
+    let fail = |uri| {
+        policy.note_attempt(uri);
+        policy.note_outcome(uri, outcome(OutcomeKind::Failed));
+    };
+
+    fail(&uris[0]);
+    assert_eq!(order(Instant::now()), [0, 1]);
+    fail(&uris[0]);
+    assert_eq!(order(Instant::now()), [1]);
+    let failed = policy.stats.get(&uris[0]).unwrap().last_failure().unwrap();
+
+    // Half-open after the cooldown, admitting a single probe
+    let cooled = failed + Duration::from_secs(30);
+    assert_eq!(order(cooled - Duration::from_secs(1)), [1]);
+    assert_eq!(order(cooled), [0, 1]);
+    policy.note_attempt(&uris[0]);
+    assert_eq!(order(cooled), [1]);
+    // A probe that never finishes doesn't keep the circuit from half-opening
+    let probed = policy.stats.get(&uris[0]).unwrap().last_attempt().unwrap();
+    assert_eq!(order(probed + Duration::from_secs(30)), [0, 1]);
+
+    // A failed probe reopens the circuit, while a successful one closes it
+    policy.note_outcome(&uris[0], outcome(OutcomeKind::TimedOut));
+    assert_eq!(order(cooled), [1]);
+    policy.note_attempt(&uris[0]);
+    policy.note_outcome(&uris[0], outcome(OutcomeKind::Success));
+    // Outcomes are passed on to the wrapped policy
+    let inner = policy.inner().stats().unwrap().get(&uris[0]).unwrap();
+    assert!(inner.last_success().is_some());
+    assert_eq!(order(Instant::now()), [0, 1]);
+    fail(&uris[0]);
+    assert_eq!(order(Instant::now()), [0, 1]);
+
+    // Open circuits of every target leave nothing to try
+    fail(&uris[0]);
+    fail(&uris[1]);
+    fail(&uris[1]);
+    assert!(order(Instant::now()).is_empty());
+}
+
+#[test]
+fn circuit_breaker_refreshes_like_inner_policy() {
+    use crate::resolver::StaticResolver;
+
+    /// Policy caching a pinned target instead of the resolved ones.
+    struct Pinned(Url);
+
+    impl Pinned {
+        fn cache(&self) -> Cache<Url> {
+            Cache::new(
+                vec![self.0.clone()],
+                Instant::now() + Duration::from_secs(60),
+            )
+        }
+    }
+
+    #[async_trait]
+    impl Policy for Pinned {
+        type CacheItem = Url;
+        type Ordering = std::ops::Range<usize>;
+
+        async fn refresh_cache<Resolver: SrvResolver>(
+            &self,
+            _client: &SrvClient<Resolver, Self>,
+        ) -> Result<Cache<Url>, Error<Resolver::Error>> {
+            Ok(self.cache())
+        }
+
+        async fn refresh_wrapped_cache<Resolver, Wrapper>(
+            &self,
+            _client: &SrvClient<Resolver, Wrapper>,
+        ) -> Result<Cache<Url>, Error<Resolver::Error>>
+        where
+            Resolver: SrvResolver,
+            Wrapper: Policy<CacheItem = Url> + Sync,
+        {
+            Ok(self.cache())
+        }
+
+        fn order(&self, uris: &[Url]) -> Self::Ordering {
+            0..uris.len()
+        }
+
+        fn cache_item_to_uri(item: &Url) -> &Url {
+            item
+        }
+    }
+
+    let pinned: Url = "https://pinned.example.com".parse().unwrap();
+    let client = SrvClient::<_>::new_with_resolver(
+        "_http._tcp.example.com",
+        "https://fallback.example.com".parse().unwrap(),
+        None,
+        StaticResolver::new([("a.example.com", 443, 1, 1)], Duration::from_secs(60)),
+    )
+    .policy(CircuitBreaker::new(
+        Pinned(pinned.clone()),
+        1,
+        Duration::from_secs(30),
+    ));
+    let uris = futures::executor::block_on(client.cached_uris()).unwrap();
+    assert_eq!(uris, [pinned]);
+}
+
+#[test]
+fn least_latency_orders_by_average_latency() {
+    use rand::SeedableRng;
+
+    let record = |host: &str, priority| {
+        let uri = format!("https://{host}.example.com").parse().unwrap();
+        ParsedRecord::from_parts(uri, priority, 100)
+    };
+    let records = [
+        record("a", 1),
+        record("b", 1),
+        record("c", 2),
+        record("d", 2),
+    ];
+    let policy = LeastLatency::default().smoothing(0.5);
+    policy.note_refresh(&records);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(2782);
+    let mut order = || {
+        policy
+            .order_with_rng(&records, &mut rng)
+            .collect::<Vec<_>>()
+    };
+    let note = |idx: usize, kind, millis| {
+        let outcome = Outcome {
+            kind,
+            elapsed: Duration::from_millis(millis),
+        };
+        policy.note_attempt(&records[idx].uri);
+        policy.note_outcome(&records[idx].uri, outcome);
+    };
+
+    // Unseen targets are ordered like Rfc2782, by priority
+    for _ in 0..10 {
+        let ordering = order();
+        assert!(ordering[..2].contains(&0) && ordering[..2].contains(&1));
+    }
+
+    note(0, OutcomeKind::Success, 80);
+    note(2, OutcomeKind::Success, 20);
+    note(3, OutcomeKind::Success, 40);
+    // Unseen targets come first
+    assert_eq!(order(), [1, 2, 3, 0]);
+    note(1, OutcomeKind::TimedOut, 1000);
+    assert_eq!(order(), [2, 3, 0, 1]);
+
+    // Averages move halfway towards new samples
+    note(2, OutcomeKind::Success, 100);
+    let average = policy.average_latency(&records[2].uri).unwrap();
+    assert_eq!(average, Duration::from_millis(60));
+    assert_eq!(order(), [3, 2, 0, 1]);
+    note(1, OutcomeKind::Success, 10);
+    assert_eq!(order(), [1, 3, 2, 0]);
+    assert_eq!(
+        policy.average_latency(&records[1].uri),
+        Some(Duration::from_millis(10))
+    );
+}
+
+#[test]
+fn fn_policy_order() {
+    let uris = [
+        "https://a.example.com",
+        "https://canary.example.com",
+        "https://b.example.com",
+    ]
+    .map(|uri| uri.parse::<Url>().unwrap());
+    let canaries = FnPolicy::new(|uris| {
+        (0..uris.len())
+            .filter(|&idx| uris[idx].as_str().contains("canary"))
+            .collect()
+    });
+    assert_eq!(canaries.order(&uris).collect::<Vec<_>>(), [1]);
+
+    // Out of range indices are dropped
+    let failures = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counted = failures.clone();
+    let policy = FnPolicy::new(|uris| (0..=uris.len()).rev().collect()).on_failure(move |_| {
+        counted.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(policy.order(&uris).collect::<Vec<_>>(), [2, 1, 0]);
+    policy.note_failure(&uris[0]);
+    policy.note_success(&uris[0]);
+    assert_eq!(failures.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn fn_policy_orders_lone_targets() {
+    let resolver = crate::resolver::StaticResolver::new(
+        [("a.example.com", 443, 1, 1)],
+        Duration::from_secs(60),
+    );
+    let client = SrvClient::<_>::new_with_resolver(
+        "_http._tcp.example.com",
+        "https://fallback.example.com".parse().unwrap(),
+        None,
+        resolver,
+    )
+    .policy(FnPolicy::new(|uris| {
+        (0..uris.len())
+            .filter(|&idx| uris[idx].as_str().contains("canary"))
+            .collect()
+    }));
+    let uris = futures::executor::block_on(client.cached_uris()).unwrap();
+    assert!(uris.is_empty(), "{uris:?}");
+    let host =
+        futures::executor::block_on(client.execute(|uri| async move {
+            Ok::<_, std::io::Error>(uri.host_str().map(str::to_owned))
+        }))
+        .unwrap();
+    assert_eq!(host.as_deref(), Some("fallback.example.com"));
+}
@@ -0,0 +1,55 @@
+//! Probing whether a service is advertised.
+
+use crate::SrvRecord;
+use arc_swap::ArcSwapOption;
+use std::{sync::Arc, time::Instant};
+
+/// Whether a service is advertised in DNS, produced by
+/// [`SrvClient::exists`](super::SrvClient::exists).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServicePresence {
+    /// The service is advertised by SRV records with this many targets.
+    Advertised {
+        /// Number of targets advertised
+        targets: usize,
+    },
+    /// The service is explicitly not provided, advertised only by SRV records
+    /// with the root target `.` per RFC 2782.
+    ExplicitlyNotProvided,
+    /// The SRV name doesn't exist or has no SRV records.
+    NotAdvertised,
+}
+
+impl ServicePresence {
+    pub(crate) fn of_records(records: &[impl SrvRecord]) -> Self {
+        let targets = records
+            .iter()
+            .filter(|record| !record.is_root_target())
+            .count();
+        match targets {
+            0 if records.is_empty() => Self::NotAdvertised,
+            0 => Self::ExplicitlyNotProvided,
+            targets => Self::Advertised { targets },
+        }
+    }
+}
+
+/// The answer to a client's last probe, remembered until it expires.
+#[derive(Debug, Default)]
+pub(crate) struct Presence(ArcSwapOption<(ServicePresence, Instant)>);
+
+impl Presence {
+    pub(crate) fn get(&self, now: Instant) -> Option<ServicePresence> {
+        let presence = self.0.load();
+        let (presence, valid_until) = presence.as_deref()?;
+        (now <= *valid_until).then_some(*presence)
+    }
+
+    pub(crate) fn store(&self, presence: ServicePresence, valid_until: Instant) {
+        self.0.store(Some(Arc::new((presence, valid_until))));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.0.store(None);
+    }
+}
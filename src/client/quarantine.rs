@@ -0,0 +1,262 @@
+//! Operator-driven temporary exclusion of SRV targets.
+
+use arc_swap::ArcSwap;
+use std::{
+    fmt,
+    net::Ipv6Addr,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// A target excluded from use by [`SrvClient::quarantine`] until some time.
+///
+/// [`SrvClient::quarantine`]: super::SrvClient::quarantine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quarantined {
+    host: String,
+    port: Option<u16>,
+    until: Instant,
+}
+
+impl Quarantined {
+    /// Parses a URL, `host:port` pair, or bare host (matching any port).
+    /// IPv6 addresses may be bracketed, and must be if a port follows them.
+    fn new(target: &str, until: Instant) -> Self {
+        let (host, port) = match Url::parse(target) {
+            Ok(url) if url.host_str().is_some() => (
+                normalize_host(url.host_str().unwrap_or_default()),
+                url.port_or_known_default(),
+            ),
+            _ => match target.rsplit_once(':') {
+                Some((host, port))
+                    if port.parse::<u16>().is_ok()
+                        && (!host.contains(':') || host.starts_with('[')) =>
+                {
+                    (normalize_host(host), port.parse().ok())
+                }
+                _ => (normalize_host(target), None),
+            },
+        };
+        Self { host, port, until }
+    }
+
+    /// Host of the quarantined target, without any trailing dot, or without
+    /// brackets if it's an IPv6 address.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Port of the quarantined target, or `None` if every port is quarantined.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Time the quarantine expires.
+    pub fn until(&self) -> Instant {
+        self.until
+    }
+
+    fn same_target(&self, other: &Self) -> bool {
+        self.host == other.host && self.port == other.port
+    }
+
+    fn matches(&self, uri: &Url, now: Instant) -> bool {
+        now < self.until
+            && uri
+                .host_str()
+                .is_some_and(|host| normalize_host(host) == self.host)
+            && self
+                .port
+                .is_none_or(|port| uri.port_or_known_default() == Some(port))
+    }
+}
+
+impl fmt::Display for Quarantined {
+    /// Formats the target as its host, followed by its port if only one is
+    /// quarantined.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.port, self.host.contains(':')) {
+            (Some(port), true) => write!(f, "[{}]:{port}", self.host),
+            (Some(port), false) => write!(f, "{}:{port}", self.host),
+            (None, _) => f.write_str(&self.host),
+        }
+    }
+}
+
+/// Normalizes a host for comparison: IPv6 addresses are unbracketed and in
+/// their canonical form, and domains are lowercase without a trailing dot.
+fn normalize_host(host: &str) -> String {
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    match unbracketed.parse::<Ipv6Addr>() {
+        Ok(addr) => addr.to_string(),
+        Err(_) => host.trim_end_matches('.').to_ascii_lowercase(),
+    }
+}
+
+/// Set of quarantined targets, readable without locking.
+#[derive(Debug, Default)]
+pub(crate) struct Quarantine(ArcSwap<Vec<Quarantined>>);
+
+impl Quarantine {
+    /// Length of quarantines whose end can't be represented.
+    const INDEFINITE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+    /// Quarantines a target for `duration`, or for a century if the end of
+    /// the quarantine can't be represented.
+    pub(crate) fn add(&self, target: &str, duration: Duration) {
+        let now = Instant::now();
+        let until = now
+            .checked_add(duration)
+            .unwrap_or_else(|| now + Self::INDEFINITE);
+        let entry = Quarantined::new(target, until);
+        self.0.rcu(|entries| {
+            let mut entries = entries
+                .iter()
+                .filter(|e| now < e.until && !e.same_target(&entry))
+                .cloned()
+                .collect::<Vec<_>>();
+            entries.push(entry.clone());
+            entries
+        });
+    }
+
+    pub(crate) fn remove(&self, target: &str) {
+        let now = Instant::now();
+        let entry = Quarantined::new(target, now);
+        self.0.rcu(|entries| {
+            entries
+                .iter()
+                .filter(|e| now < e.until && !e.same_target(&entry))
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+    }
+
+    pub(crate) fn entries(&self) -> Vec<Quarantined> {
+        let now = Instant::now();
+        let entries = self.0.load();
+        entries.iter().filter(|e| now < e.until).cloned().collect()
+    }
+
+    /// Removes quarantined targets from `candidates`, unless every candidate
+    /// is quarantined, in which case none are removed.
+    pub(crate) fn filter(&self, candidates: &mut Vec<Url>) {
+        let entries = self.0.load();
+        if entries.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let quarantined = |uri: &Url| entries.iter().any(|e| e.matches(uri, now));
+        if candidates.iter().all(quarantined) {
+            tracing::trace!("Every SRV target is quarantined; ignoring the quarantine");
+            return;
+        }
+        candidates.retain(|uri| !quarantined(uri));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(urls: &[&str]) -> Vec<Url> {
+        urls.iter().map(|url| url.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn target_forms() {
+        let until = Instant::now() + Duration::from_secs(60);
+        let a: Url = "https://a.example.com.:8443".parse().unwrap();
+        assert!(Quarantined::new("A.example.com", until).matches(&a, Instant::now()));
+        assert!(Quarantined::new("a.example.com:8443", until).matches(&a, Instant::now()));
+        assert!(!Quarantined::new("a.example.com:443", until).matches(&a, Instant::now()));
+        assert!(Quarantined::new("https://a.example.com:8443/x", until).matches(&a, Instant::now()));
+        assert!(!Quarantined::new("https://a.example.com/", until).matches(&a, Instant::now()));
+
+        // IPv6 addresses match whether or not they're bracketed
+        let v6: Url = "https://[2001:db8::1]:8443".parse().unwrap();
+        for target in [
+            "2001:db8::1",
+            "[2001:db8::1]",
+            "[2001:DB8:0::1]:8443",
+            "https://[2001:db8::1]:8443/",
+        ] {
+            let quarantined = Quarantined::new(target, until);
+            assert_eq!(quarantined.host(), "2001:db8::1");
+            assert!(quarantined.matches(&v6, Instant::now()), "{target}");
+        }
+        assert!(!Quarantined::new("[2001:db8::1]:443", until).matches(&v6, Instant::now()));
+        assert_eq!(
+            Quarantined::new("[2001:db8::1]:443", until).to_string(),
+            "[2001:db8::1]:443"
+        );
+        assert_eq!(
+            Quarantined::new("A.example.com.", until).to_string(),
+            "a.example.com"
+        );
+    }
+
+    #[test]
+    fn unrepresentable_expiry() {
+        let quarantine = Quarantine::default();
+        quarantine.add("a.example.com", Duration::MAX);
+        let mut filtered = urls(&["https://a.example.com", "https://b.example.com"]);
+        quarantine.filter(&mut filtered);
+        assert_eq!(filtered, urls(&["https://b.example.com"]));
+    }
+
+    #[test]
+    fn expiry_and_removal() {
+        let quarantine = Quarantine::default();
+        let candidates = urls(&["https://a.example.com", "https://b.example.com"]);
+
+        quarantine.add("a.example.com", Duration::from_secs(60));
+        quarantine.add("b.example.com", Duration::ZERO);
+        let mut filtered = candidates.clone();
+        quarantine.filter(&mut filtered);
+        assert_eq!(filtered, candidates[1..]);
+        assert_eq!(quarantine.entries().len(), 1);
+
+        quarantine.remove("a.example.com");
+        let mut filtered = candidates.clone();
+        quarantine.filter(&mut filtered);
+        assert_eq!(filtered, candidates);
+        assert!(quarantine.entries().is_empty());
+    }
+
+    #[test]
+    fn all_quarantined_is_ignored() {
+        let quarantine = Quarantine::default();
+        let candidates = urls(&["https://a.example.com", "https://b.example.com"]);
+        quarantine.add("a.example.com", Duration::from_secs(60));
+        quarantine.add("b.example.com", Duration::from_secs(60));
+        let mut filtered = candidates.clone();
+        quarantine.filter(&mut filtered);
+        assert_eq!(filtered, candidates);
+    }
+
+    #[test]
+    fn reported_by_resolve_once() {
+        let resolver = crate::resolver::StaticResolver::new(
+            [("a.example.com", 443, 1, 1)],
+            Duration::from_secs(60),
+        );
+        let client = crate::SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+        client.quarantine("a.example.com:443", Duration::from_secs(60));
+        let report = futures::executor::block_on(client.resolve_once()).unwrap();
+        let quarantined = report
+            .quarantined()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(quarantined, ["a.example.com:443"]);
+    }
+}
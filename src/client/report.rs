@@ -0,0 +1,234 @@
+//! Reports of side-effect-free lookups.
+
+use super::Quarantined;
+use crate::resolver::LookupMeta;
+use std::{fmt, net::IpAddr, time::Instant};
+use url::Url;
+
+/// What a client's SRV name resolves to right now, produced by
+/// [`SrvClient::resolve_once`](super::SrvClient::resolve_once).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveReport {
+    pub(crate) srv_name: String,
+    pub(crate) accepted: Vec<Url>,
+    pub(crate) rejected: Vec<RejectedTarget>,
+    pub(crate) valid_until: Instant,
+    pub(crate) lookup_meta: LookupMeta,
+    pub(crate) min_targets: usize,
+    pub(crate) quarantined: Vec<Quarantined>,
+    pub(crate) srv_name_lookups: Vec<SrvNameLookup>,
+}
+
+impl ResolveReport {
+    /// Name the client's SRV name was resolved as, which differs from it if
+    /// it was expanded with a search domain.
+    pub fn srv_name(&self) -> &str {
+        &self.srv_name
+    }
+
+    /// Lookups of the names the client's SRV name expands to with its
+    /// [search domains](super::SrvClient::search_domains), in the order they
+    /// were tried. The last one is of [`ResolveReport::srv_name`], which won.
+    pub fn srv_name_lookups(&self) -> &[SrvNameLookup] {
+        &self.srv_name_lookups
+    }
+
+    /// Targets the client would use, in the order of their records.
+    pub fn accepted(&self) -> &[Url] {
+        &self.accepted
+    }
+
+    /// Targets the client would reject, with the reasons they'd be rejected.
+    pub fn rejected(&self) -> &[RejectedTarget] {
+        &self.rejected
+    }
+
+    /// Time the records are valid until.
+    pub fn valid_until(&self) -> Instant {
+        self.valid_until
+    }
+
+    /// Where the answer to the lookup came from.
+    pub fn lookup_meta(&self) -> &LookupMeta {
+        &self.lookup_meta
+    }
+
+    /// Number of records rejected for being outside the client's
+    /// [priority bound](super::SrvClient::max_priority).
+    pub fn priority_excluded(&self) -> usize {
+        self.rejected
+            .iter()
+            .filter(|rejected| matches!(rejected.reason, RejectionReason::PriorityExcluded(_)))
+            .count()
+    }
+
+    /// Targets the client currently [quarantines](super::SrvClient::quarantine),
+    /// which executions skip unless every target is quarantined.
+    pub fn quarantined(&self) -> &[Quarantined] {
+        &self.quarantined
+    }
+
+    /// Determines if enough targets were accepted to meet the client's
+    /// [minimum](super::SrvClient::min_targets), so the client would trust
+    /// the answer.
+    pub fn meets_min_targets(&self) -> bool {
+        self.accepted.len() >= self.min_targets
+    }
+}
+
+/// Lookup of one of the names a client's SRV name expands to, in a
+/// [`ResolveReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvNameLookup {
+    pub(crate) name: String,
+    pub(crate) outcome: SrvNameOutcome,
+}
+
+impl SrvNameLookup {
+    /// The name that was looked up.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// What the lookup found.
+    pub fn outcome(&self) -> &SrvNameOutcome {
+        &self.outcome
+    }
+}
+
+/// What looking up one of the names a client's SRV name expands to found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SrvNameOutcome {
+    /// The name has the given number of SRV records.
+    Found(usize),
+    /// The name has no SRV records.
+    NoRecords,
+    /// The lookup failed with the given error.
+    Failed(String),
+}
+
+impl fmt::Display for SrvNameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Found(1) => f.write_str("1 record"),
+            Self::Found(records) => write!(f, "{records} records"),
+            Self::NoRecords => f.write_str("no records"),
+            Self::Failed(error) => write!(f, "failed: {error}"),
+        }
+    }
+}
+
+/// A SRV target rejected by a client, in a [`ResolveReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedTarget {
+    pub(crate) target: String,
+    pub(crate) reason: RejectionReason,
+}
+
+impl RejectedTarget {
+    /// The rejected target, as `host:port`.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Why the target was rejected.
+    pub fn reason(&self) -> &RejectionReason {
+        &self.reason
+    }
+}
+
+/// Reasons a client rejects a SRV target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The record's priority is outside the client's
+    /// [priority bound](super::SrvClient::max_priority).
+    PriorityExcluded(u16),
+    /// The target couldn't be made into a URL the client may use.
+    Invalid(String),
+    /// The target isn't allowed by the client's allowed suffixes.
+    SuffixNotAllowed,
+    /// The target's port isn't one of the client's
+    /// [allowed ports](super::SrvClient::allowed_ports).
+    PortNotAllowed(u16),
+    /// The target is an address of a family other than the one the client
+    /// [requires](super::SrvClient::ip_family).
+    FamilyNotAllowed,
+    /// The addresses the target resolves to couldn't be looked up to check
+    /// them against the client's forbidden addresses.
+    AddrsUnavailable,
+    /// The target resolves to no addresses, so they can't be checked against
+    /// the client's forbidden addresses.
+    NoAddrs,
+    /// The target resolves to a forbidden address.
+    ForbiddenAddr(IpAddr),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{policy::Rfc2782, resolver::FnResolver, OwnedSrvRecord, SrvClient};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_srv_name_lookups() {
+        let resolver = FnResolver::new(|name: String| async move {
+            let valid_until = Instant::now() + Duration::from_secs(60);
+            match name.as_str() {
+                "_api._tcp.payments.corp.example.com" => Err("unreachable".into()),
+                "_api._tcp.payments.example.com" => {
+                    let record = OwnedSrvRecord::new("a.example.com", 443, 1, 1);
+                    Ok((vec![record], valid_until))
+                }
+                _ => Ok((Vec::new(), valid_until)),
+            }
+        });
+        let client = SrvClient::<_, Rfc2782>::new_with_resolver(
+            "_api._tcp.payments",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+        .search_domains(["corp.example.com", "example.com", "example.net"]);
+
+        let report = block_on(client.resolve_once()).unwrap();
+        assert_eq!(report.srv_name(), "_api._tcp.payments.example.com");
+        let lookups = report
+            .srv_name_lookups()
+            .iter()
+            .map(|lookup| (lookup.name(), lookup.outcome().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lookups,
+            [
+                (
+                    "_api._tcp.payments.corp.example.com",
+                    "failed: unreachable".to_owned()
+                ),
+                ("_api._tcp.payments.example.com", "1 record".to_owned()),
+            ]
+        );
+
+        block_on(client.get_fresh_uri_candidates()).unwrap();
+        let snapshot = client.snapshot();
+        assert_eq!(
+            snapshot.resolved_srv_name.as_deref(),
+            Some("_api._tcp.payments.example.com")
+        );
+        assert_eq!(snapshot.srv_name_lookups.len(), 2);
+
+        // The resolution goes with the name it was of
+        client.set_srv_name("_web._tcp.payments");
+        assert_eq!(client.current_srv_name(), "_web._tcp.payments");
+        assert_eq!(client.resolved_srv_name(), None);
+        assert!(client.snapshot().srv_name_lookups.is_empty());
+
+        let report = block_on(client.resolve_once()).unwrap();
+        assert_eq!(report.srv_name(), "_web._tcp.payments");
+        assert_eq!(report.srv_name_lookups().len(), 4);
+        assert_eq!(
+            report.srv_name_lookups()[3].outcome(),
+            &SrvNameOutcome::NoRecords
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Deriving SRV names from service URLs.
+
+use super::ConfigError;
+use std::collections::HashMap;
+use url::Url;
+
+/// Mapping from URL schemes to the service and protocol labels of SRV names,
+/// used by [`SrvClient::for_url`](super::SrvClient::for_url).
+///
+/// Maps `http` to `_http._tcp` and `https` to `_https._tcp` by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceMapping(HashMap<String, (String, String)>);
+
+impl Default for ServiceMapping {
+    fn default() -> Self {
+        Self(HashMap::new())
+            .with("http", "_http", "_tcp")
+            .with("https", "_https", "_tcp")
+    }
+}
+
+impl ServiceMapping {
+    /// Maps URLs of `scheme` to SRV names starting with `service` and `proto`
+    /// (e.g. `_wss` and `_tcp`), replacing any mapping of the scheme.
+    pub fn with(
+        mut self,
+        scheme: impl ToString,
+        service: impl ToString,
+        proto: impl ToString,
+    ) -> Self {
+        self.0.insert(
+            scheme.to_string().to_ascii_lowercase(),
+            (service.to_string(), proto.to_string()),
+        );
+        self
+    }
+
+    /// Derives the SRV name of a URL, e.g. `_https._tcp.example.com.` for
+    /// `https://example.com/`.
+    ///
+    /// Fails if the URL's scheme isn't mapped or its host isn't a domain.
+    pub fn srv_name(&self, url: &Url) -> Result<String, ConfigError> {
+        let (service, proto) =
+            self.0
+                .get(url.scheme())
+                .ok_or_else(|| ConfigError::UnmappedScheme {
+                    scheme: url.scheme().to_owned(),
+                })?;
+        let domain = domain(url)?;
+        Ok(format!(
+            "{service}.{proto}.{}.",
+            domain.trim_end_matches('.')
+        ))
+    }
+}
+
+/// Gets the domain of a URL's host, rejecting IP literals.
+pub(crate) fn domain(url: &Url) -> Result<&str, ConfigError> {
+    match url.host() {
+        Some(url::Host::Domain(domain)) => Ok(domain),
+        Some(host) => Err(ConfigError::IpLiteralHost {
+            host: host.to_string(),
+        }),
+        None => Err(ConfigError::MissingHost),
+    }
+}
+
+/// Gets the registrable domain of a domain, per the public suffix list, as
+/// an allowed suffix of SRV targets (which end with a dot), e.g.
+/// `example.co.uk.` for `api.example.co.uk`.
+///
+/// Domains that have no registrable domain, like public suffixes themselves
+/// or single labels, are their own suffix.
+pub(crate) fn registrable_suffix(domain: &str) -> url::Host {
+    let domain = domain.trim_end_matches('.');
+    let registrable = psl::domain_str(domain).unwrap_or(domain);
+    url::Host::Domain(format!("{registrable}."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::registrable_suffix;
+
+    #[test]
+    fn registrable_suffixes() {
+        let suffix = |domain| match registrable_suffix(domain) {
+            url::Host::Domain(suffix) => suffix,
+            host => panic!("{host:?} isn't a domain"),
+        };
+        assert_eq!(suffix("api.eu.example.com"), "example.com.");
+        assert_eq!(suffix("example.com."), "example.com.");
+        assert_eq!(suffix("api.example.co.uk"), "example.co.uk.");
+        assert_eq!(suffix("shop.example.com.au"), "example.com.au.");
+        assert_eq!(suffix("co.uk"), "co.uk.");
+        assert_eq!(suffix("localhost"), "localhost.");
+    }
+}
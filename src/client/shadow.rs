@@ -0,0 +1,252 @@
+//! Mirroring executions to a secondary target.
+
+use super::{policy, SrvClient};
+use crate::resolver::SrvResolver;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// A boxed mirror of an execution, handed to a [`Shadow`]'s spawner.
+pub type ShadowTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Target that [`SrvClient::execute_shadowed`] mirrors executions to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShadowTarget {
+    /// The highest priority cached target other than the primary, i.e. the
+    /// one the execution tried first.
+    NextPriority,
+    /// A fixed target, e.g. a host in a new region.
+    Fixed(Url),
+}
+
+/// Configuration of a client's shadow traffic, set with
+/// [`SrvClient::shadow`].
+pub struct Shadow {
+    target: ShadowTarget,
+    spawn: Box<dyn Fn(ShadowTask) + Send + Sync>,
+    max_in_flight: usize,
+    in_flight: Arc<AtomicUsize>,
+    rng: Mutex<StdRng>,
+    stats: Arc<ShadowStats>,
+}
+
+impl Shadow {
+    /// Mirrors of executions in flight at once by default.
+    pub const DEFAULT_MAX_IN_FLIGHT: usize = 16;
+
+    /// Creates a configuration mirroring executions to `target`, running each
+    /// mirror as a detached task with `spawn` (e.g.
+    /// `|task| drop(tokio::spawn(task))`).
+    pub fn new(target: ShadowTarget, spawn: impl Fn(ShadowTask) + Send + Sync + 'static) -> Self {
+        Self {
+            target,
+            spawn: Box::new(spawn),
+            max_in_flight: Self::DEFAULT_MAX_IN_FLIGHT,
+            in_flight: Default::default(),
+            rng: Mutex::new(StdRng::from_os_rng()),
+            stats: Default::default(),
+        }
+    }
+
+    /// Sets the number of mirrors in flight at once, beyond which executions
+    /// aren't mirrored.
+    pub fn max_in_flight(self, max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight,
+            ..self
+        }
+    }
+
+    /// Seeds the random number generator sampling executions to mirror, making
+    /// the sample reproducible.
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+
+    /// Gets the target executions are mirrored to.
+    pub fn target(&self) -> &ShadowTarget {
+        &self.target
+    }
+
+    /// Gets the statistics of the mirrors run so far.
+    pub fn stats(&self) -> &ShadowStats {
+        &self.stats
+    }
+
+    fn sample(&self, ratio: f64) -> bool {
+        if ratio.is_nan() || ratio <= 0.0 {
+            return false;
+        }
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        rng.random_bool(ratio.min(1.0))
+    }
+
+    fn acquire(&self) -> Option<InFlight> {
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.max_in_flight).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| InFlight(self.in_flight.clone()))
+    }
+}
+
+impl fmt::Debug for Shadow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shadow")
+            .field("target", &self.target)
+            .field("max_in_flight", &self.max_in_flight)
+            .field("in_flight", &self.in_flight)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A slot of a [`Shadow`]'s concurrency cap, released when dropped.
+struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Outcomes and latencies of the mirrors run by a [`Shadow`].
+#[derive(Debug, Default)]
+pub struct ShadowStats {
+    mirrored: AtomicU64,
+    skipped: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    latency_nanos: AtomicU64,
+}
+
+impl ShadowStats {
+    /// Number of executions mirrored.
+    pub fn mirrored(&self) -> u64 {
+        self.mirrored.load(Ordering::Relaxed)
+    }
+
+    /// Number of executions sampled for mirroring but not mirrored because
+    /// too many mirrors were in flight.
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Number of mirrors that succeeded.
+    pub fn succeeded(&self) -> u64 {
+        self.succeeded.load(Ordering::Relaxed)
+    }
+
+    /// Number of mirrors that failed.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Mean latency of the mirrors that finished, if any have.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        let finished = self.succeeded() + self.failed();
+        let nanos = self.latency_nanos.load(Ordering::Relaxed);
+        (finished > 0).then(|| Duration::from_nanos(nanos / finished))
+    }
+
+    fn record(&self, succeeded: bool, latency: Duration) {
+        let outcome = if succeeded {
+            &self.succeeded
+        } else {
+            &self.failed
+        };
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.latency_nanos.fetch_add(nanos, Ordering::Relaxed);
+        outcome.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<Resolver: SrvResolver, Policy: policy::Policy> SrvClient<Resolver, Policy> {
+    /// Performs an operation on a client's SRV targets like
+    /// [`SrvClient::execute`], also mirroring a `ratio` (between 0 and 1) of
+    /// executions to the target of the client's [`Shadow`], if it has one.
+    ///
+    /// The operation is invoked on the mirror when the execution tries its
+    /// first target, so the operation must be safe to perform twice: requests
+    /// with streaming bodies must be able to replay them. The mirror runs as a
+    /// detached task whose outcome is only recorded in the shadow's
+    /// [statistics](Shadow::stats), never affecting the execution's result or
+    /// the targets the client prefers.
+    pub async fn execute_shadowed<T, E, Fut>(
+        &self,
+        ratio: f64,
+        mut func: impl FnMut(Url) -> Fut,
+    ) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let mut first = true;
+        self.execute(|uri| {
+            if std::mem::take(&mut first) {
+                self.mirror(ratio, &uri, &mut func);
+            }
+            func(uri)
+        })
+        .await
+    }
+
+    fn mirror<T, E, Fut>(&self, ratio: f64, primary: &Url, func: &mut impl FnMut(Url) -> Fut)
+    where
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let Some(shadow) = &self.shadow else {
+            return;
+        };
+        if !shadow.sample(ratio) {
+            return;
+        }
+        let Some(secondary) = self.shadow_target(&shadow.target, primary) else {
+            tracing::trace!("No secondary target to mirror execution to");
+            return;
+        };
+        let Some(in_flight) = shadow.acquire() else {
+            shadow.stats.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        shadow.stats.mirrored.fetch_add(1, Ordering::Relaxed);
+        let stats = shadow.stats.clone();
+        let mirror = func(secondary);
+        (shadow.spawn)(Box::pin(async move {
+            let start = Instant::now();
+            let succeeded = mirror.await.is_ok();
+            stats.record(succeeded, start.elapsed());
+            drop(in_flight);
+        }));
+    }
+
+    fn shadow_target(&self, target: &ShadowTarget, primary: &Url) -> Option<Url> {
+        match target {
+            ShadowTarget::Fixed(uri) => (uri != primary).then(|| uri.clone()),
+            ShadowTarget::NextPriority => {
+                let cache = self.cache.load();
+                let secondary = cache
+                    .items()
+                    .iter()
+                    .filter(|item| Policy::cache_item_to_uri(item) != primary)
+                    .min_by_key(|item| {
+                        Policy::cache_item_to_record(item).map_or(0, |record| record.priority)
+                    })?;
+                Some(Policy::cache_item_to_uri(secondary).clone())
+            }
+        }
+    }
+}
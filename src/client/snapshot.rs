@@ -0,0 +1,194 @@
+//! In-memory snapshots of a client's state, for support bundles.
+
+/// Everything observable about a [`SrvClient`] at one point in time, taken by
+/// [`SrvClient::snapshot`] without performing any lookups.
+///
+/// Fields documented as sensitive may contain operator-sensitive data (e.g.
+/// internal host names) and are blanked by [`ClientSnapshot::redacted`].
+///
+/// [`SrvClient`]: super::SrvClient
+/// [`SrvClient::snapshot`]: super::SrvClient::snapshot
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientSnapshot {
+    /// The client's configuration.
+    pub config: ConfigSnapshot,
+    /// The client's cache of targets.
+    pub cache: CacheSnapshot,
+    /// Cached targets in cache order, with their statistics if the client's
+    /// policy keeps any.
+    pub targets: Vec<TargetSnapshot>,
+    /// Quarantined targets, as `host` or `host:port`. Sensitive.
+    pub quarantined: Vec<String>,
+    /// Targets removed from DNS that are being drained. Sensitive.
+    pub draining: Vec<String>,
+    /// Targets added at runtime with [`SrvClient::add_extra_target`].
+    /// Sensitive.
+    ///
+    /// [`SrvClient::add_extra_target`]: super::SrvClient::add_extra_target
+    pub extra_targets: Vec<String>,
+    /// Name the client's SRV name was last resolved as.
+    pub resolved_srv_name: Option<String>,
+    /// Names the client's SRV name expanded to when it was last resolved, in
+    /// the order they were looked up, with what each lookup found. The last
+    /// one is the name it was resolved as.
+    pub srv_name_lookups: Vec<(String, String)>,
+    /// Error of the last failed refresh, if the last refresh failed.
+    pub last_lookup_error: Option<String>,
+    /// Number of times the fallback has been used.
+    pub fallback_uses: u64,
+    /// Number of targets found by the last refresh, if it found fewer than
+    /// the client's minimum.
+    pub min_targets_shortfall: Option<usize>,
+    /// Number of SRV records the last refresh excluded for being outside the
+    /// client's priority bound.
+    pub priority_excluded: usize,
+    /// Name server that answered the last SRV lookup, if the client's
+    /// resolver can tell. Sensitive.
+    pub lookup_server: Option<String>,
+    /// Protocol the last SRV lookup was answered over, if the client's
+    /// resolver can tell.
+    pub lookup_protocol: Option<String>,
+    /// Whether the last SRV lookup was answered from the resolver's own
+    /// cache, if the resolver can tell.
+    pub lookup_from_cache: Option<bool>,
+    /// Client subnet sent with the last SRV lookup in its EDNS Client Subnet
+    /// option, if any. Sensitive.
+    pub lookup_client_subnet: Option<String>,
+    /// Number of refreshes rejected by the client's refresh guard.
+    pub refresh_rejections: u64,
+}
+
+/// Configuration of a client in a [`ClientSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigSnapshot {
+    /// SRV name looked up.
+    pub srv_name: String,
+    /// Domains relative SRV names are expanded with.
+    pub search_domains: Vec<String>,
+    /// Fallback used when no target succeeds. Sensitive.
+    pub fallback: String,
+    /// Fallbacks tried in order after the first one fails. Sensitive.
+    pub extra_fallbacks: Vec<String>,
+    /// Host suffixes targets are restricted to, if any. Sensitive.
+    pub allowed_suffixes: Option<Vec<String>>,
+    /// Ports targets are restricted to, if any.
+    pub allowed_ports: Option<Vec<u16>>,
+    /// Whether SRV records duplicating another target are dropped.
+    pub dedupe_targets: bool,
+    /// Whether SRV records that can't be parsed fail lookups.
+    pub strict_parsing: bool,
+    /// Scheme of targets' URLs.
+    pub http_scheme: String,
+    /// Whether the client refuses insecure schemes.
+    pub https_only: bool,
+    /// Path of targets' URLs.
+    pub path_prefix: String,
+    /// Whether bursts of executions are spread across targets.
+    pub spread_bursts: bool,
+    /// How the fallback is used.
+    pub fallback_mode: String,
+    /// Multipliers applied to the weights of targets by host suffix.
+    pub weight_bias: Vec<(String, f64)>,
+    /// Address family preference of targets.
+    pub ip_family: String,
+    /// Grace period of targets removed from DNS, in seconds.
+    pub drain_grace_secs: Option<f64>,
+    /// Interval between warmups of a target, in seconds.
+    pub warmup_interval_secs: f64,
+    /// Whether TLSA records of targets are looked up.
+    pub lookup_tlsa: bool,
+    /// Inclusive range of SRV record priorities used.
+    pub priorities: (u16, u16),
+    /// Shortest time records are cached for, in seconds.
+    pub min_ttl_secs: Option<f64>,
+    /// Longest time records are cached for, in seconds.
+    pub max_ttl_secs: Option<f64>,
+    /// Time failed lookups are cached for, in seconds.
+    pub negative_ttl_secs: f64,
+    /// Minimum number of targets a refresh must find to be trusted.
+    pub min_targets: usize,
+    /// What happens when a refresh finds fewer targets than the minimum.
+    pub min_targets_action: String,
+}
+
+/// State of a client's cache in a [`ClientSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheSnapshot {
+    /// Whether the cache holds targets that are still valid.
+    pub fresh: bool,
+    /// Seconds until the cache expires, negative if it has expired, or `None`
+    /// if it holds no targets.
+    pub expires_in_secs: Option<f64>,
+    /// Number of refreshes attempted, which is the cache's generation.
+    pub refreshes: u64,
+}
+
+/// A cached target in a [`ClientSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetSnapshot {
+    /// URL of the target. Sensitive.
+    pub uri: String,
+    /// Priority of the target's SRV record, if known to the client's policy.
+    pub priority: Option<u16>,
+    /// Weight of the target's SRV record, if known to the client's policy.
+    pub weight: Option<u16>,
+    /// Statistics of the target, if the client's policy keeps any.
+    pub stats: Option<TargetStatSnapshot>,
+}
+
+/// Statistics of a target in a [`ClientSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetStatSnapshot {
+    /// Number of successful executions.
+    pub successes: u64,
+    /// Number of failed executions.
+    pub failures: u64,
+    /// Number of executions in progress.
+    pub in_flight: usize,
+    /// Seconds since the last success, if any.
+    pub secs_since_success: Option<f64>,
+    /// Seconds since the last failure, if any.
+    pub secs_since_failure: Option<f64>,
+}
+
+impl ClientSnapshot {
+    const REDACTED: &'static str = "<redacted>";
+
+    /// Blanks the fields that may contain operator-sensitive data.
+    pub fn redacted(mut self) -> Self {
+        let redact = |values: &mut Vec<String>| {
+            values
+                .iter_mut()
+                .for_each(|value| *value = Self::REDACTED.to_owned())
+        };
+        self.config.fallback = Self::REDACTED.to_owned();
+        redact(&mut self.config.extra_fallbacks);
+        if let Some(suffixes) = &mut self.config.allowed_suffixes {
+            redact(suffixes);
+        }
+        self.targets
+            .iter_mut()
+            .for_each(|target| target.uri = Self::REDACTED.to_owned());
+        redact(&mut self.quarantined);
+        redact(&mut self.draining);
+        redact(&mut self.extra_targets);
+        if let Some(server) = &mut self.lookup_server {
+            *server = Self::REDACTED.to_owned();
+        }
+        if let Some(subnet) = &mut self.lookup_client_subnet {
+            *subnet = Self::REDACTED.to_owned();
+        }
+        self
+    }
+
+    /// Serializes the snapshot as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("snapshots are always serializable")
+    }
+}
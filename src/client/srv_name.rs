@@ -0,0 +1,83 @@
+//! Tracking of what a client's SRV name resolves to.
+
+use super::SrvNameLookup;
+
+/// SRV name of a client along with what it was last resolved as, swapped
+/// together so that readers never see the resolution of another name.
+#[derive(Debug)]
+pub(super) struct SrvName {
+    pub name: String,
+    pub resolution: Option<Resolution>,
+}
+
+impl SrvName {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            resolution: None,
+        }
+    }
+}
+
+/// Name a client's SRV name was resolved as, and the lookups of the names it
+/// expands to that led there.
+#[derive(Debug, Clone)]
+pub(super) struct Resolution {
+    /// SRV name that was resolved
+    pub srv: String,
+    /// Name the records were found at
+    pub name: String,
+    pub lookups: Vec<SrvNameLookup>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{policy::Rfc2782, resolver::FnResolver, CachedTargets, OwnedSrvRecord, SrvClient};
+    use futures::{channel::oneshot, executor::block_on, FutureExt};
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn drops_refreshes_of_old_names() {
+        let (release, released) = oneshot::channel::<()>();
+        let released = Mutex::new(Some(released));
+        let resolver = FnResolver::new(move |name: String| {
+            // The lookup of the old name only finishes once it's released
+            let released = match name.as_str() {
+                "_old._tcp.example.com" => released.lock().unwrap().take(),
+                _ => None,
+            };
+            async move {
+                if let Some(released) = released {
+                    released.await.unwrap();
+                }
+                let host = name.split('.').next().unwrap().trim_start_matches('_');
+                let record = OwnedSrvRecord::new(format!("{host}.example.com"), 443, 1, 1);
+                Ok((vec![record], Instant::now() + Duration::from_secs(60)))
+            }
+        });
+        let client = SrvClient::<_, Rfc2782>::new_with_resolver(
+            "_old._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+
+        let mut refresh = Box::pin(client.cached_uris());
+        assert!(refresh.as_mut().now_or_never().is_none());
+        client.set_srv_name("_new._tcp.example.com");
+        release.send(()).unwrap();
+        let uris = block_on(refresh).unwrap();
+        assert_eq!(uris[0].host_str(), Some("new.example.com"));
+        assert_eq!(
+            client.resolved_srv_name().as_deref(),
+            Some("_new._tcp.example.com")
+        );
+        let CachedTargets::Fresh(uris) = client.try_cached_targets() else {
+            panic!("the refresh of the new name is cached");
+        };
+        assert_eq!(uris[0].host_str(), Some("new.example.com"));
+    }
+}
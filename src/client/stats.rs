@@ -0,0 +1,402 @@
+//! Per-target statistics for stateful policies.
+
+use arc_swap::ArcSwap;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// Cheap key identifying a SRV target by a hash of its host and port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetKey(u64);
+
+impl TargetKey {
+    /// Computes the key of the target a [`Url`] points to.
+    pub fn new(url: &Url) -> Self {
+        let mut hasher = DefaultHasher::new();
+        url.host_str().hash(&mut hasher);
+        url.port_or_known_default().hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+impl From<&Url> for TargetKey {
+    fn from(url: &Url) -> Self {
+        Self::new(url)
+    }
+}
+
+/// Clock shared by the targets of a [`TargetStats`], stamping events with
+/// nanoseconds since its epoch, offset by one so that zero can represent
+/// "never".
+#[derive(Debug)]
+struct Clock {
+    epoch: Instant,
+    last: AtomicU64,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets a stamp of the current time, later than every stamp before it so
+    /// that events compare in the order they were recorded, even across
+    /// targets and within the resolution of the system's clock.
+    fn stamp(&self) -> u64 {
+        let now = self.epoch.elapsed().as_nanos().min(u64::MAX as u128 - 1) as u64 + 1;
+        let later = |last: u64| now.max(last.saturating_add(1));
+        let last = self
+            .last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(later(last))
+            })
+            .unwrap_or_else(|last| last);
+        later(last)
+    }
+
+    fn instant(&self, stamp: u64) -> Option<Instant> {
+        match stamp {
+            0 => None,
+            nanos => Some(self.epoch + Duration::from_nanos(nanos - 1)),
+        }
+    }
+}
+
+/// Atomic counters and gauges tracked for a single target.
+#[derive(Debug)]
+pub struct TargetStat {
+    clock: Arc<Clock>,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    in_flight: AtomicUsize,
+    last_attempt: AtomicU64,
+    last_success: AtomicU64,
+    last_failure: AtomicU64,
+    value: AtomicU64,
+}
+
+impl TargetStat {
+    fn new(clock: Arc<Clock>, value: f64) -> Self {
+        Self {
+            clock,
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            in_flight: AtomicUsize::new(0),
+            last_attempt: AtomicU64::new(0),
+            last_success: AtomicU64::new(0),
+            last_failure: AtomicU64::new(0),
+            value: AtomicU64::new(value.to_bits()),
+        }
+    }
+
+    /// Number of successful executions recorded for the target.
+    pub fn successes(&self) -> u64 {
+        self.successes.load(Ordering::Relaxed)
+    }
+
+    /// Number of failed executions recorded for the target.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Number of executions started on the target that have not yet finished.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Time the most recent execution on the target started, if any.
+    pub fn last_attempt(&self) -> Option<Instant> {
+        self.instant(&self.last_attempt)
+    }
+
+    /// Time of the most recent success, if any.
+    pub fn last_success(&self) -> Option<Instant> {
+        self.instant(&self.last_success)
+    }
+
+    /// Time of the most recent failure, if any.
+    pub fn last_failure(&self) -> Option<Instant> {
+        self.instant(&self.last_failure)
+    }
+
+    /// Time of the most recent success or failure, if any.
+    pub fn last_event(&self) -> Option<Instant> {
+        self.last_success().max(self.last_failure())
+    }
+
+    /// Policy-defined value (e.g. an EWMA of latency) associated with the target.
+    pub fn value(&self) -> f64 {
+        f64::from_bits(self.value.load(Ordering::Relaxed))
+    }
+
+    /// Sets the policy-defined value associated with the target.
+    pub fn set_value(&self, value: f64) {
+        self.value.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Atomically updates the policy-defined value associated with the target.
+    pub fn update_value(&self, mut f: impl FnMut(f64) -> f64) {
+        let _ = self
+            .value
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some(f(f64::from_bits(bits)).to_bits())
+            });
+    }
+
+    /// Records the start of an execution on the target.
+    pub fn record_attempt(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.stamp(&self.last_attempt);
+    }
+
+    /// Records a successful execution on the target.
+    pub fn record_success(&self) {
+        self.successes.fetch_add(1, Ordering::Relaxed);
+        self.finish_attempt();
+        self.stamp(&self.last_success);
+    }
+
+    /// Records a failed execution on the target.
+    pub fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.finish_attempt();
+        self.stamp(&self.last_failure);
+    }
+
+    /// Records an execution on the target that was abandoned before it
+    /// finished, e.g. because it was cancelled.
+    pub fn record_cancelled(&self) {
+        self.finish_attempt();
+    }
+
+    fn finish_attempt(&self) {
+        let _ = self
+            .in_flight
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+
+    fn stamp(&self, at: &AtomicU64) {
+        at.store(self.clock.stamp(), Ordering::Relaxed);
+    }
+
+    fn instant(&self, at: &AtomicU64) -> Option<Instant> {
+        self.clock.instant(at.load(Ordering::Relaxed))
+    }
+}
+
+/// Concurrent map of per-target statistics, the supported building block for
+/// writing stateful policies.
+///
+/// Reads never take a lock, so [`TargetStats::get`] is safe to call from
+/// [`Policy::order`]. Entries are populated and evicted by
+/// [`TargetStats::retain_targets`], which policies should call from
+/// [`Policy::note_refresh`] so that the map only ever holds targets present
+/// in the client's cache. Statistics for targets that survive a refresh are
+/// preserved. Times of events recorded in one map are distinct and ordered as
+/// the events were recorded, even across targets.
+///
+/// ```
+/// # use detsys_srv::policy::TargetStats;
+/// let stats = TargetStats::default();
+/// let target: url::Url = "https://example.com:8443".parse().unwrap();
+/// stats.retain_targets([&target]);
+/// stats.record_attempt(&target);
+/// stats.record_success(&target);
+/// assert_eq!(stats.get(&target).unwrap().successes(), 1);
+/// ```
+///
+/// [`Policy::order`]: super::policy::Policy::order
+/// [`Policy::note_refresh`]: super::policy::Policy::note_refresh
+#[derive(Debug)]
+pub struct TargetStats {
+    clock: Arc<Clock>,
+    initial_value: f64,
+    generation: AtomicU64,
+    map: ArcSwap<HashMap<TargetKey, Arc<TargetStat>>>,
+}
+
+impl TargetStats {
+    /// Creates an empty map whose entries start with a policy-defined value of
+    /// `initial_value`.
+    pub fn new(initial_value: f64) -> Self {
+        Self {
+            clock: Arc::new(Clock::new()),
+            initial_value,
+            generation: AtomicU64::new(0),
+            map: Default::default(),
+        }
+    }
+
+    /// Gets the statistics of the target a [`Url`] points to.
+    pub fn get(&self, url: &Url) -> Option<Arc<TargetStat>> {
+        self.get_key(TargetKey::new(url))
+    }
+
+    /// Gets the statistics of the target identified by a [`TargetKey`].
+    pub fn get_key(&self, key: TargetKey) -> Option<Arc<TargetStat>> {
+        self.map.load().get(&key).cloned()
+    }
+
+    /// Number of times the set of tracked targets has been replaced.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Number of tracked targets.
+    pub fn len(&self) -> usize {
+        self.map.load().len()
+    }
+
+    /// Determines if no targets are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.map.load().is_empty()
+    }
+
+    /// Replaces the set of tracked targets, keeping the statistics of targets
+    /// already tracked and evicting those no longer present.
+    pub fn retain_targets<'a>(&self, targets: impl IntoIterator<Item = &'a Url>) {
+        let keys = targets.into_iter().map(TargetKey::new).collect::<Vec<_>>();
+        self.map.rcu(|old| {
+            keys.iter()
+                .map(|&key| {
+                    let stat = old.get(&key).cloned().unwrap_or_else(|| {
+                        Arc::new(TargetStat::new(self.clock.clone(), self.initial_value))
+                    });
+                    (key, stat)
+                })
+                .collect::<HashMap<_, _>>()
+        });
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Records the start of an execution on a tracked target.
+    pub fn record_attempt(&self, url: &Url) {
+        if let Some(stat) = self.get(url) {
+            stat.record_attempt();
+        }
+    }
+
+    /// Records a successful execution on a tracked target.
+    pub fn record_success(&self, url: &Url) {
+        if let Some(stat) = self.get(url) {
+            stat.record_success();
+        }
+    }
+
+    /// Records a failed execution on a tracked target.
+    pub fn record_failure(&self, url: &Url) {
+        if let Some(stat) = self.get(url) {
+            stat.record_failure();
+        }
+    }
+
+    /// Records an abandoned execution on a tracked target.
+    pub fn record_cancelled(&self, url: &Url) {
+        if let Some(stat) = self.get(url) {
+            stat.record_cancelled();
+        }
+    }
+}
+
+impl Default for TargetStats {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn key_ignores_path_and_default_port() {
+        assert_eq!(
+            TargetKey::new(&url("https://example.com/foo")),
+            TargetKey::new(&url("https://example.com:443/bar"))
+        );
+        assert_ne!(
+            TargetKey::new(&url("https://example.com:443")),
+            TargetKey::new(&url("https://example.com:8443"))
+        );
+    }
+
+    #[test]
+    fn untracked_targets_are_ignored() {
+        let stats = TargetStats::default();
+        let target = url("https://example.com");
+        stats.record_success(&target);
+        assert!(stats.get(&target).is_none());
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    fn retain_preserves_and_evicts() {
+        let stats = TargetStats::default();
+        let (a, b, c) = (
+            url("https://a.example.com"),
+            url("https://b.example.com"),
+            url("https://c.example.com"),
+        );
+        stats.retain_targets([&a, &b]);
+        stats.record_success(&a);
+        stats.record_failure(&b);
+        assert_eq!(stats.generation(), 1);
+
+        stats.retain_targets([&a, &c]);
+        assert_eq!(stats.generation(), 2);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.get(&a).unwrap().successes(), 1);
+        assert!(stats.get(&a).unwrap().last_success().is_some());
+        assert!(stats.get(&b).is_none());
+        assert_eq!(stats.get(&c).unwrap().failures(), 0);
+    }
+
+    #[test]
+    fn in_flight_and_value() {
+        let stats = TargetStats::new(1.0);
+        let target = url("https://example.com");
+        stats.retain_targets([&target]);
+        let stat = stats.get(&target).unwrap();
+        stat.record_attempt();
+        stat.record_attempt();
+        assert_eq!(stat.in_flight(), 2);
+        stat.record_failure();
+        stat.record_success();
+        stat.record_success();
+        assert_eq!(stat.in_flight(), 0);
+        assert_eq!(stat.last_event(), stat.last_success());
+
+        assert_eq!(stat.value(), 1.0);
+        stat.update_value(|v| v * 0.5 + 2.0);
+        assert_eq!(stat.value(), 2.5);
+    }
+
+    #[test]
+    fn events_are_ordered() {
+        let stats = TargetStats::default();
+        let (a, b) = (url("https://a.example.com"), url("https://b.example.com"));
+        stats.retain_targets([&a, &b]);
+        let last_success = |target| stats.get(target).unwrap().last_success().unwrap();
+        for _ in 0..100 {
+            stats.record_success(&a);
+            stats.record_success(&b);
+            assert!(last_success(&a) < last_success(&b));
+            stats.record_success(&a);
+            assert!(last_success(&b) < last_success(&a));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! HTTPS records (RFC 9460), an alternative to SRV records.
+
+use std::net::IpAddr;
+
+/// An HTTPS (or SVCB) record, with the parameters a client understands.
+///
+/// A record with priority 0 is in AliasMode, pointing to another name whose
+/// records should be used instead, while others are in ServiceMode,
+/// describing a target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SvcbRecord {
+    priority: u16,
+    target: String,
+    port: Option<u16>,
+    alpn: Vec<String>,
+    hints: Vec<IpAddr>,
+}
+
+impl SvcbRecord {
+    /// Creates a record of a target with a priority and no parameters.
+    pub fn new(priority: u16, target: impl ToString) -> Self {
+        Self {
+            priority,
+            target: target.to_string(),
+            port: None,
+            alpn: Vec::new(),
+            hints: Vec::new(),
+        }
+    }
+
+    /// Sets the `port` parameter of the record.
+    pub fn with_port(self, port: u16) -> Self {
+        Self {
+            port: Some(port),
+            ..self
+        }
+    }
+
+    /// Sets the `alpn` parameter of the record.
+    pub fn with_alpn(self, alpn: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            alpn: alpn.into_iter().map(|id| id.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Sets the `ipv4hint` and `ipv6hint` parameters of the record.
+    pub fn with_hints(self, hints: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self {
+            hints: hints.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// SvcPriority of the record, which is 0 in AliasMode.
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// TargetName of the record, where `.` stands for the name the record
+    /// is at in ServiceMode and for no service at all in AliasMode.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Determines if the record is in AliasMode.
+    pub fn is_alias(&self) -> bool {
+        self.priority == 0
+    }
+
+    /// Port of the target, if the record gives one.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// ALPN protocol identifiers the target supports, e.g. `h2`.
+    pub fn alpn(&self) -> &[String] {
+        &self.alpn
+    }
+
+    /// Addresses the target may have, from the `ipv4hint` and `ipv6hint`
+    /// parameters.
+    pub fn hints(&self) -> &[IpAddr] {
+        &self.hints
+    }
+}
@@ -0,0 +1,87 @@
+//! TLSA records of SRV targets, for DANE verification (RFC 6698).
+
+use std::fmt;
+
+/// A TLSA record associating a certificate or public key with a target.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TlsaRecord {
+    usage: u8,
+    selector: u8,
+    matching_type: u8,
+    data: Vec<u8>,
+}
+
+impl TlsaRecord {
+    /// Creates a TLSA record from its fields.
+    pub fn new(usage: u8, selector: u8, matching_type: u8, data: Vec<u8>) -> Self {
+        Self {
+            usage,
+            selector,
+            matching_type,
+            data,
+        }
+    }
+
+    /// Certificate usage, e.g. `3` for DANE-EE.
+    pub fn usage(&self) -> u8 {
+        self.usage
+    }
+
+    /// Part of the certificate matched, e.g. `1` for the public key.
+    pub fn selector(&self) -> u8 {
+        self.selector
+    }
+
+    /// How the certificate association data is matched, e.g. `1` for SHA-256.
+    pub fn matching_type(&self) -> u8 {
+        self.matching_type
+    }
+
+    /// Certificate association data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl fmt::Debug for TlsaRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data = self
+            .data
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        f.debug_struct("TlsaRecord")
+            .field("usage", &self.usage)
+            .field("selector", &self.selector)
+            .field("matching_type", &self.matching_type)
+            .field("data", &data)
+            .finish()
+    }
+}
+
+/// Result of looking up a target's TLSA records, which never prevents the
+/// target from being used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsaLookup {
+    /// The target has TLSA records.
+    Found(Vec<TlsaRecord>),
+    /// The target has no TLSA records.
+    Absent,
+    /// Looking up the target's TLSA records failed.
+    Failed,
+}
+
+impl TlsaLookup {
+    /// Gets the records found, if any.
+    pub fn records(&self) -> &[TlsaRecord] {
+        match self {
+            Self::Found(records) => records,
+            Self::Absent | Self::Failed => &[],
+        }
+    }
+}
+
+/// Gets the name of the TLSA records of a TCP service on a host.
+pub(crate) fn tlsa_name(host: &str, port: u16) -> String {
+    format!("_{port}._tcp.{host}")
+}
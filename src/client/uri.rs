@@ -0,0 +1,17 @@
+//! Conversion of targets to [`http::Uri`]s.
+
+use url::{Position, Url};
+
+/// Converts a target or fallback into an [`http::Uri`] component by
+/// component, so the URI has exactly the host (already IDNA-encoded and
+/// bracketed if it is an IPv6 literal), port, and path of the URL.
+///
+/// Fails for URLs without a host, like `unix:` or `data:` URLs, which
+/// `http::Uri` can't represent.
+pub(crate) fn to_uri(url: &Url) -> Result<http::Uri, http::Error> {
+    http::Uri::builder()
+        .scheme(url.scheme())
+        .authority(&url[Position::BeforeUsername..Position::AfterPort])
+        .path_and_query(&url[Position::BeforePath..Position::AfterQuery])
+        .build()
+}
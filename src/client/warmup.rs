@@ -0,0 +1,163 @@
+//! Pre-connecting to top-ranked targets ahead of real executions.
+
+use super::TargetKey;
+use arc_swap::ArcSwap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// Outcome of warming up a single target with [`SrvClient::warmup`].
+///
+/// [`SrvClient::warmup`]: super::SrvClient::warmup
+#[derive(Debug)]
+pub enum WarmupOutcome<E> {
+    /// The target was connected to successfully.
+    Connected,
+    /// The target was skipped because it was warmed up recently.
+    Skipped,
+    /// Connecting to the target failed.
+    Failed(E),
+}
+
+/// Results of a call to [`SrvClient::warmup`], in the order the targets would
+/// be tried by an execution.
+///
+/// [`SrvClient::warmup`]: super::SrvClient::warmup
+#[derive(Debug)]
+pub struct WarmupReport<E> {
+    pub(crate) targets: Vec<(Url, WarmupOutcome<E>)>,
+}
+
+impl<E> WarmupReport<E> {
+    /// Gets each warmed up target along with its outcome.
+    pub fn targets(&self) -> &[(Url, WarmupOutcome<E>)] {
+        &self.targets
+    }
+
+    /// Determines if no connection attempted by the warmup failed.
+    pub fn all_connected(&self) -> bool {
+        self.targets
+            .iter()
+            .all(|(_, outcome)| !matches!(outcome, WarmupOutcome::Failed(_)))
+    }
+}
+
+/// Times targets were last warmed up successfully.
+#[derive(Debug, Default)]
+pub(crate) struct Warmed(ArcSwap<HashMap<TargetKey, Instant>>);
+
+impl Warmed {
+    /// Determines if a target was warmed up successfully within `interval`
+    /// before `now`.
+    pub(crate) fn recently(&self, uri: &Url, interval: Duration, now: Instant) -> bool {
+        self.0
+            .load()
+            .get(&TargetKey::new(uri))
+            .is_some_and(|warmed| now.saturating_duration_since(*warmed) < interval)
+    }
+
+    /// Records the targets warmed up successfully at `now`, forgetting those
+    /// whose warmup has expired.
+    pub(crate) fn record<'a>(
+        &self,
+        uris: impl IntoIterator<Item = &'a Url>,
+        interval: Duration,
+        now: Instant,
+    ) {
+        let keys = uris.into_iter().map(TargetKey::new).collect::<Vec<_>>();
+        if keys.is_empty() {
+            return;
+        }
+        self.0.rcu(|warmed| {
+            let mut warmed = warmed
+                .iter()
+                .filter(|(_, warmed)| now.saturating_duration_since(**warmed) < interval)
+                .map(|(&key, &warmed)| (key, warmed))
+                .collect::<HashMap<_, _>>();
+            warmed.extend(keys.iter().map(|&key| (key, now)));
+            warmed
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{policy::Rfc2782, resolver::StaticResolver, SrvClient};
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    #[test]
+    fn warmups_expire() {
+        let warmed = Warmed::default();
+        let a: Url = "https://a.example.com".parse().unwrap();
+        let b: Url = "https://b.example.com".parse().unwrap();
+        let interval = Duration::from_secs(30);
+        let start = Instant::now();
+        warmed.record([&a], interval, start);
+        assert!(warmed.recently(&a, interval, start + Duration::from_secs(29)));
+        assert!(!warmed.recently(&a, interval, start + interval));
+        assert!(!warmed.recently(&b, interval, start));
+
+        // Expired warmups are forgotten once others are recorded
+        warmed.record([&b], interval, start + interval);
+        assert_eq!(warmed.0.load().len(), 1);
+    }
+
+    #[test]
+    fn warmup() {
+        let client = SrvClient::<_, Rfc2782>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            StaticResolver::new(
+                [
+                    ("live.example.com", 443, 1, 100),
+                    ("dead.example.com", 443, 1, 100),
+                ],
+                Duration::from_secs(60),
+            ),
+        );
+        let attempts = Mutex::new(Vec::new());
+        let connect = |uri: Url| {
+            let host = uri.host_str().unwrap().to_owned();
+            attempts.lock().unwrap().push(host.clone());
+            async move {
+                match host.as_str() {
+                    "live.example.com" => Ok(()),
+                    _ => Err(format!("{host} refused the connection")),
+                }
+            }
+        };
+        let outcomes = || {
+            let report = block_on(client.warmup(2, connect)).unwrap();
+            let outcome = |host| {
+                let (_, outcome) = report
+                    .targets()
+                    .iter()
+                    .find(|(uri, _)| uri.host_str() == Some(host))
+                    .unwrap();
+                match outcome {
+                    WarmupOutcome::Connected => "connected",
+                    WarmupOutcome::Skipped => "skipped",
+                    WarmupOutcome::Failed(_) => "failed",
+                }
+            };
+            (outcome("live.example.com"), outcome("dead.example.com"))
+        };
+        assert_eq!(outcomes(), ("connected", "failed"));
+        // Targets warmed up recently are skipped, but failed ones are retried
+        assert_eq!(outcomes(), ("skipped", "failed"));
+        let mut attempted = attempts.lock().unwrap().clone();
+        attempted.sort();
+        assert_eq!(
+            attempted,
+            ["dead.example.com", "dead.example.com", "live.example.com"]
+        );
+
+        let report = block_on(client.warmup(1, connect)).unwrap();
+        assert_eq!(report.targets().len(), 1);
+    }
+}
@@ -0,0 +1,186 @@
+//! Hot-reloading client settings from a config file.
+
+use super::{ConfigError, SrvClient};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use url::Url;
+
+/// Settings locating a client's service, read from a JSON config file by a
+/// [`ConfigWatcher`].
+///
+/// A client's policy is part of its type, so it can't be changed without
+/// building a new client.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SrvClientConfig {
+    /// SRV name of the client
+    pub srv_name: String,
+    /// Fallback of the client
+    pub fallback: String,
+    /// Allowed suffixes of the client, if any
+    #[serde(default)]
+    pub allowed_suffixes: Option<Vec<String>>,
+}
+
+/// Errors encountered when reloading a client's settings with a
+/// [`ConfigWatcher`].
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    /// Produced when the config file can't be read
+    #[error("failed to read config file {path}")]
+    Read {
+        /// Path of the config file
+        path: PathBuf,
+        /// Error reading the file
+        #[source]
+        source: std::io::Error,
+    },
+    /// Produced when the config file isn't valid
+    #[error("failed to parse config file")]
+    Parse(#[from] serde_json::Error),
+    /// Produced when a URL or host in the config file isn't valid
+    #[error("invalid `{setting}` in config file")]
+    Url {
+        /// Name of the invalid setting
+        setting: &'static str,
+        /// Error parsing the setting
+        #[source]
+        source: url::ParseError,
+    },
+    /// Produced when the settings violate the client's configuration
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+/// Outcome of the latest reload by a [`ConfigWatcher`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchStatus {
+    /// No config has been applied yet.
+    NotLoaded,
+    /// The config file was applied.
+    Applied,
+    /// The config file couldn't be applied, so the last config applied, if
+    /// any, remains in effect.
+    Failed(String),
+}
+
+/// Watches a JSON config file of [`SrvClientConfig`] by polling it, applying
+/// its settings to a live client whenever it changes.
+///
+/// Changing the SRV name or allowed suffixes discards the client's cached
+/// targets; changing only the fallback doesn't.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    poll_interval: Duration,
+    applied: ArcSwapOption<SrvClientConfig>,
+    status: ArcSwap<WatchStatus>,
+}
+
+impl ConfigWatcher {
+    /// Creates a watcher of the config file at `path`, polled every five
+    /// seconds by default.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            poll_interval: Duration::from_secs(5),
+            applied: Default::default(),
+            status: ArcSwap::from_pointee(WatchStatus::NotLoaded),
+        }
+    }
+
+    /// Sets how often the config file is polled by [`ConfigWatcher::watch`].
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Gets the outcome of the latest reload.
+    pub fn status(&self) -> WatchStatus {
+        WatchStatus::clone(&self.status.load())
+    }
+
+    /// Gets the config last applied, if any.
+    pub fn applied(&self) -> Option<SrvClientConfig> {
+        self.applied.load().as_deref().cloned()
+    }
+
+    /// Reads the config file once, applying it to `client` if it changed
+    /// since it was last applied. Returns whether it was applied.
+    pub fn check<Resolver, Policy: super::policy::Policy>(
+        &self,
+        client: &SrvClient<Resolver, Policy>,
+    ) -> Result<bool, WatchError> {
+        let checked = self.read().and_then(|config| {
+            if self.applied.load().as_deref() == Some(&config) {
+                return Ok(false);
+            }
+            apply(&config, client)?;
+            self.applied.store(Some(Arc::new(config)));
+            Ok(true)
+        });
+        match &checked {
+            Ok(true) => {
+                tracing::trace!(path = %self.path.display(), "Applied config file");
+                self.status.store(Arc::new(WatchStatus::Applied));
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(path = %self.path.display(), %e, "Failed to apply config file");
+                self.status
+                    .store(Arc::new(WatchStatus::Failed(e.to_string())));
+            }
+        }
+        checked
+    }
+
+    /// Polls the config file forever, applying it to `client` whenever it
+    /// changes. Errors are reported through [`ConfigWatcher::status`].
+    pub async fn watch<Resolver, Policy: super::policy::Policy>(
+        &self,
+        client: &SrvClient<Resolver, Policy>,
+    ) {
+        loop {
+            let _ = self.check(client);
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    fn read(&self) -> Result<SrvClientConfig, WatchError> {
+        let contents = std::fs::read(&self.path).map_err(|source| WatchError::Read {
+            path: self.path.clone(),
+            source,
+        })?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+}
+
+/// Validates a config and applies it to a client, changing nothing if it
+/// isn't valid.
+fn apply<Resolver, Policy: super::policy::Policy>(
+    config: &SrvClientConfig,
+    client: &SrvClient<Resolver, Policy>,
+) -> Result<(), WatchError> {
+    let url_error = |setting| move |source| WatchError::Url { setting, source };
+    let fallback = Url::parse(&config.fallback).map_err(url_error("fallback"))?;
+    let allowed_suffixes = config
+        .allowed_suffixes
+        .as_ref()
+        .map(|suffixes| {
+            suffixes
+                .iter()
+                .map(|suffix| url::Host::parse(suffix))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(url_error("allowed_suffixes"))?;
+    client.set_fallback(fallback)?;
+    client.set_allowed_suffixes(allowed_suffixes);
+    client.set_srv_name(&config.srv_name);
+    Ok(())
+}
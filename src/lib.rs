@@ -42,15 +42,63 @@ a target selection policy that maintains affinity for the last target it has
 used successfully. Both of these behaviors can be changed by implementing the
 [`SrvResolver`] and [`Policy`] traits, respectively.
 
+The provided backends are built on
+[`hickory-resolver`](https://docs.rs/hickory-resolver) (formerly
+`trust-dns-resolver`): its `Resolver` implements [`SrvResolver`] directly,
+`resolver::ClientSubnetResolver` builds on it, and [`DnsSdResolver`] can wrap it.
+`DefaultSrvClient` uses a `resolver::DefaultResolver`, which reads the
+system's DNS configuration:
+
+```
+# #[cfg(feature = "hickory")] {
+# use detsys_srv::DefaultSrvClient;
+let client = DefaultSrvClient::new("_http._tcp.example.com", "https://example.com".parse().unwrap(), None);
+# }
+```
+
+These backends are behind the default `hickory` feature. Without it, e.g.
+with `default-features = false`, hickory isn't compiled at all, and the client
+is used with resolvers of one's own.
+
+A resolver can also be made from a closure with [`FnResolver`], e.g. to serve
+fixed records in tests:
+
+```
+# use detsys_srv::{resolver::FnResolver, OwnedSrvRecord, SrvClient};
+# use std::time::{Duration, Instant};
+let resolver = FnResolver::new(|_| async {
+    Ok((vec![OwnedSrvRecord::new("test1.example.com", 443, 1, 100)], Instant::now() + Duration::from_secs(60)))
+});
+let client = SrvClient::<_>::new_with_resolver("_http._tcp.example.com", "https://example.com".parse().unwrap(), None, resolver);
+```
 
 [`SrvResolver`]: resolver::SrvResolver
+[`FnResolver`]: resolver::FnResolver
+[`DnsSdResolver`]: resolver::DnsSdResolver
 [`Policy`]: policy::Policy
 */
 
 mod client;
-pub use client::{policy, Error, SrvClient};
+#[cfg(feature = "tokio")]
+pub use client::AttemptError;
+pub use client::{
+    policy, shrink_guard, AttemptGuard, BuilderError, CacheSnapshot, CachedTargets, CandidateMeta,
+    ClientSnapshot, ConfigError, ConfigSnapshot, Error, ExecuteError, ExecutionRecord, ExtraTarget,
+    FallbackMode, ForbiddenAddrs, IpFamily, IpFamilyPreference, IpRange, MinTargetsAction,
+    Quarantined, RefreshDecision, RefreshSummary, RejectedTarget, RejectionReason, ResolveReport,
+    ResolvedTarget, ServiceInstance, ServiceMapping, ServicePresence, Shadow, ShadowStats,
+    ShadowTarget, ShadowTask, SrvClient, SrvClientBuilder, SrvNameLookup, SrvNameOutcome,
+    SvcbRecord, TargetSnapshot, TargetStatSnapshot, TlsaLookup, TlsaRecord, UriAttemptError,
+    Userinfo, WarmupOutcome, WarmupReport,
+};
+#[cfg(feature = "watch")]
+pub use client::{ConfigWatcher, SrvClientConfig, WatchError, WatchStatus};
 
 mod record;
-pub use record::SrvRecord;
+pub use record::{OwnedSrvRecord, SrvRecord};
 
 pub mod resolver;
+
+/// Client using the system's DNS configuration and the default policy.
+#[cfg(feature = "hickory")]
+pub type DefaultSrvClient = SrvClient<resolver::DefaultResolver>;
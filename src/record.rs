@@ -2,7 +2,6 @@
 
 use std::{cmp::Reverse, fmt::Display};
 
-use http::uri::Scheme;
 use rand::Rng;
 use url::Url;
 
@@ -24,8 +23,23 @@ pub trait SrvRecord {
     /// Gets a SRV record's weight.
     fn weight(&self) -> u16;
 
-    /// Parses a SRV record into a URI with a given scheme (e.g. https)
-    fn parse(&self, scheme: Scheme) -> Result<Url, url::ParseError> {
+    /// Determines if a SRV record's target is the root name `.`, which
+    /// RFC 2782 uses to say the service is decidedly not available.
+    fn is_root_target(&self) -> bool {
+        matches!(self.target().to_string().as_str(), "." | "")
+    }
+
+    /// Parses a SRV record into a URI with a given scheme (e.g. https, or
+    /// redis), which may be an [`http::uri::Scheme`] or any scheme string.
+    ///
+    /// Fails with [`url::ParseError::RelativeUrlWithoutBase`], as `url` does,
+    /// if the scheme isn't valid (RFC 3986). Targets that aren't host names
+    /// or IP addresses fail: with [`url::ParseError::InvalidDomainCharacter`]
+    /// if they contain `@` or `:`, which `url` would take as delimiters, and
+    /// with [`url::ParseError::IdnaError`] if they contain other characters
+    /// hosts can't, or have empty labels (e.g. `..`) or labels longer than
+    /// DNS allows.
+    fn parse(&self, scheme: impl AsRef<str>) -> Result<Url, url::ParseError> {
         // We do this funny parsing of a bogus URL and then set the
         // properties to get the benefits of parsing each field, since
         // url::Url doesn't support constructing a URL from parts.
@@ -35,10 +49,23 @@ pub trait SrvRecord {
         // `foo.com:123/bar`.
         // Then the port would be appended to the end of that, which would
         // not set the port.
-        let mut url = url::Url::parse("http://example.com")?;
-        url.set_scheme(scheme.as_str())
-            .expect("...Scheme supports HTTP and HTTPS, and that is it.");
-        url.set_host(Some(&self.target().to_string()))?;
+        //
+        // The scheme is checked before it's formatted into the bogus URL for
+        // the same reason. Parsing rather than setting it lets schemes other
+        // than HTTP's (e.g. `redis`) be used, since `url` refuses to switch
+        // between special and non-special schemes.
+        let scheme = scheme.as_ref();
+        let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid {
+            return Err(url::ParseError::RelativeUrlWithoutBase);
+        }
+        let mut url = url::Url::parse(&format!("{scheme}://example.com"))?;
+        let target = self.target().to_string();
+        check_host(&target)?;
+        url.set_host(Some(&target))?;
         url.set_port(Some(self.port()))
             .map_err(|_| url::ParseError::SetHostOnCannotBeABaseUrl)?;
 
@@ -51,9 +78,89 @@ pub trait SrvRecord {
     }
 }
 
+/// Checks what `url` doesn't about a SRV target before it's made a URL's
+/// host: `url` would take `user@host` to mean `user`, and lets through labels
+/// DNS wouldn't.
+fn check_host(target: &str) -> Result<(), url::ParseError> {
+    // Bracketed IPv6 addresses are left to `url`
+    if target.starts_with('[') {
+        return Ok(());
+    }
+    if target.contains(['@', ':']) {
+        return Err(url::ParseError::InvalidDomainCharacter);
+    }
+    let name = target.strip_suffix('.').unwrap_or(target);
+    if name.len() > 253
+        || name
+            .split('.')
+            .any(|label| label.is_empty() || label.len() > 63)
+    {
+        return Err(url::ParseError::IdnaError);
+    }
+    Ok(())
+}
+
 /// Generates a key to sort a SRV record by priority and weight per RFC 2782.
 pub(crate) fn sort_key(priority: u16, weight: u16, mut rng: impl Rng) -> (u16, Reverse<u32>) {
     // Sort ascending by priority, then descending (hence `Reverse`) by randomized weight
     let rand = rng.random::<u16>() as u32;
     (priority, Reverse(weight as u32 * rand))
 }
+
+/// Generates a key to sort by priority and a weight wider than a SRV record's.
+pub(crate) fn wide_sort_key(priority: u16, weight: u32, mut rng: impl Rng) -> (u16, Reverse<u64>) {
+    let rand = rng.random::<u16>() as u64;
+    (priority, Reverse(weight as u64 * rand))
+}
+
+/// SRV record owning its fields, for resolvers that don't have a record type
+/// of their own, like [`FnResolver`](crate::resolver::FnResolver).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OwnedSrvRecord {
+    target: String,
+    port: u16,
+    priority: u16,
+    weight: u16,
+}
+
+impl OwnedSrvRecord {
+    /// Creates a SRV record from its fields.
+    pub fn new(target: impl ToString, port: u16, priority: u16, weight: u16) -> Self {
+        Self {
+            target: target.to_string(),
+            port,
+            priority,
+            weight,
+        }
+    }
+
+    /// Copies the fields of another SRV record.
+    pub fn from_record(record: &impl SrvRecord) -> Self {
+        Self::new(
+            record.target(),
+            record.port(),
+            record.priority(),
+            record.weight(),
+        )
+    }
+}
+
+impl SrvRecord for OwnedSrvRecord {
+    type Target = str;
+
+    fn target(&self) -> &Self::Target {
+        &self.target
+    }
+
+    fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    fn weight(&self) -> u16 {
+        self.weight
+    }
+}
@@ -0,0 +1,389 @@
+//! SRV resolver caching lookups of another, for sharing across clients.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// SRV resolver caching the SRV lookups of an inner resolver until they
+/// expire, so that clients sharing it (e.g. short-lived clients created per
+/// request) don't each look the same names up.
+///
+/// Clones share the cache. Names are cached case-insensitively and without
+/// any trailing dot. Once the cache holds [`CachingResolver::max_entries`]
+/// names, caching another evicts the least recently used one. Failed lookups
+/// aren't cached, and lookups of other record types aren't cached either.
+/// Cached records are reordered for each lookup, so that weighted selection
+/// still varies. Any [`Glue`] is cached along with them.
+///
+/// ```
+/// # use detsys_srv::{resolver::{CachingResolver, FnResolver}, OwnedSrvRecord, SrvClient};
+/// # use std::{sync::atomic::{AtomicUsize, Ordering}, time::{Duration, Instant}};
+/// static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+/// let resolver = FnResolver::new(|_| async {
+///     LOOKUPS.fetch_add(1, Ordering::Relaxed);
+///     let record = OwnedSrvRecord::new("a.example.com", 443, 1, 100);
+///     Ok((vec![record], Instant::now() + Duration::from_secs(60)))
+/// });
+/// let shared = CachingResolver::new(resolver);
+/// let client = || {
+///     SrvClient::<_>::new_with_resolver(
+///         "_http._tcp.example.com",
+///         "https://fallback.example.com".parse().unwrap(),
+///         None,
+///         shared.clone(),
+///     )
+/// };
+/// futures::executor::block_on(client().get_fresh_uri_candidates()).unwrap();
+/// futures::executor::block_on(client().get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(LOOKUPS.load(Ordering::Relaxed), 1);
+/// ```
+pub struct CachingResolver<R: SrvResolver> {
+    shared: Arc<Shared<R>>,
+}
+
+struct Shared<R: SrvResolver> {
+    inner: R,
+    max_entries: usize,
+    entries: Mutex<Entries<R::Record>>,
+}
+
+struct Entries<Record> {
+    map: HashMap<String, Entry<Record>>,
+    /// Counter stamping entries as they're used, for finding the least
+    /// recently used one.
+    clock: u64,
+}
+
+/// SRV records along with the time they're valid until, where the answer
+/// came from, and its glue.
+type Lookup<Record> = (Vec<Record>, Instant, LookupMeta, Glue);
+
+struct Entry<Record> {
+    records: Vec<Record>,
+    valid_until: Instant,
+    meta: LookupMeta,
+    glue: Glue,
+    last_used: u64,
+}
+
+impl<R: SrvResolver> Clone for CachingResolver<R> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<R: SrvResolver + std::fmt::Debug> std::fmt::Debug for CachingResolver<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachingResolver")
+            .field("inner", &self.shared.inner)
+            .field("max_entries", &self.shared.max_entries)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<R: SrvResolver> CachingResolver<R> {
+    /// Default bound on the number of cached names.
+    pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+    /// Creates a resolver caching the lookups of `inner`, holding at most
+    /// [`CachingResolver::DEFAULT_MAX_ENTRIES`] names.
+    pub fn new(inner: R) -> Self {
+        Self::with_max_entries(inner, Self::DEFAULT_MAX_ENTRIES)
+    }
+
+    /// Creates a resolver caching the lookups of `inner`, holding at most
+    /// `max_entries` names (at least one).
+    pub fn with_max_entries(inner: R, max_entries: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                inner,
+                max_entries: max_entries.max(1),
+                entries: Mutex::new(Entries {
+                    map: HashMap::new(),
+                    clock: 0,
+                }),
+            }),
+        }
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.shared.inner
+    }
+
+    /// Gets the most names the cache holds.
+    pub fn max_entries(&self) -> usize {
+        self.shared.max_entries
+    }
+
+    /// Gets the number of names cached, including any that expired but
+    /// haven't been looked up again.
+    pub fn len(&self) -> usize {
+        self.entries().map.len()
+    }
+
+    /// Determines if no names are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the cached lookup of `name`, if any, so that the next lookup
+    /// of it uses the inner resolver.
+    pub fn invalidate(&self, name: &str) {
+        self.entries().map.remove(&key(name));
+    }
+
+    /// Removes every cached lookup.
+    pub fn clear(&self) {
+        self.entries().map.clear();
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries<R::Record>> {
+        self.shared
+            .entries
+            .lock()
+            .expect("cache lock is never poisoned")
+    }
+}
+
+impl<R: SrvResolver> CachingResolver<R>
+where
+    R::Record: Clone,
+{
+    /// Gets the cached lookup of `srv` if it hasn't expired.
+    fn cached(&self, srv: &str) -> Option<Lookup<R::Record>> {
+        let mut entries = self.entries();
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.map.get_mut(&key(srv))?;
+        if entry.valid_until <= Instant::now() {
+            return None;
+        }
+        entry.last_used = clock;
+        Some((
+            entry.records.clone(),
+            entry.valid_until,
+            entry.meta.clone(),
+            entry.glue.clone(),
+        ))
+    }
+
+    /// Caches a lookup of `srv`, evicting the least recently used name if the
+    /// cache is full.
+    fn store(&self, srv: &str, lookup: &Lookup<R::Record>) {
+        let (records, valid_until, meta, glue) = lookup;
+        let mut entries = self.entries();
+        entries.clock += 1;
+        let key = key(srv);
+        if !entries.map.contains_key(&key) && entries.map.len() >= self.shared.max_entries {
+            let lru = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(name, _)| name.clone());
+            if let Some(lru) = lru {
+                tracing::trace!(srv = lru, "Evicting least recently used SRV lookup");
+                entries.map.remove(&lru);
+            }
+        }
+        let entry = Entry {
+            records: records.to_vec(),
+            valid_until: *valid_until,
+            meta: meta.clone(),
+            glue: glue.clone(),
+            last_used: entries.clock,
+        };
+        entries.map.insert(key, entry);
+    }
+
+    /// Looks `srv` up, from the cache if possible.
+    async fn lookup(&self, srv: &str) -> Result<Lookup<R::Record>, R::Error> {
+        if let Some(cached) = self.cached(srv) {
+            tracing::trace!(srv, "Using cached SRV lookup");
+            return Ok(cached);
+        }
+        let lookup = self.shared.inner.get_srv_records_with_glue(srv).await?;
+        self.store(srv, &lookup);
+        Ok(lookup)
+    }
+}
+
+/// Gets the key a name is cached under.
+fn key(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+#[async_trait]
+impl<R> SrvResolver for CachingResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Clone + Send,
+{
+    type Record = R::Record;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, valid_until, _, _) = self.lookup(srv).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let (mut records, valid_until, meta, glue) = self.lookup(srv).await?;
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, valid_until, meta, glue))
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.shared.inner.get_ip_addrs(host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.shared.inner.get_tlsa_records(name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.shared.inner.get_https_records(name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.shared.inner.get_https_records_with_meta(name).await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.shared.inner.get_ptr_records(name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.shared.inner.get_txt_records(name).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resolver::{MockResolver, MockResponse},
+        SrvClient, SrvRecord,
+    };
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    fn records(target: &str, ttl: Duration) -> MockResponse {
+        MockResponse::records([(target, 443, 1, 100)], ttl)
+    }
+
+    fn target(resolver: &CachingResolver<MockResolver>, srv: &str) -> String {
+        let (records, _) = block_on(resolver.get_srv_records(srv)).unwrap();
+        records[0].target().to_owned()
+    }
+
+    #[test]
+    fn clients_share_lookups() {
+        let mock = MockResolver::new([records("a.example.com", Duration::from_secs(60))]);
+        let shared = CachingResolver::new(mock.clone());
+        let client = || {
+            SrvClient::<_>::new_with_resolver(
+                "_http._tcp.example.com",
+                "https://fallback.example.com".parse().unwrap(),
+                None,
+                shared.clone(),
+            )
+        };
+        let host = |client: SrvClient<_>| {
+            block_on(client.execute(|uri| async move {
+                Ok::<_, std::io::Error>(uri.host_str().unwrap().to_owned())
+            }))
+            .unwrap()
+        };
+        assert_eq!(host(client()), "a.example.com");
+        assert_eq!(host(client()), "a.example.com");
+        assert_eq!(mock.lookups(), 1);
+    }
+
+    #[test]
+    fn expires_and_invalidates() {
+        let mock = MockResolver::new([
+            records("a.example.com", Duration::ZERO),
+            records("b.example.com", Duration::from_secs(60)),
+            records("c.example.com", Duration::from_secs(60)),
+        ]);
+        let resolver = CachingResolver::new(mock.clone());
+        assert_eq!(target(&resolver, "_http._tcp.example.com"), "a.example.com");
+        assert_eq!(target(&resolver, "_http._tcp.example.com"), "b.example.com");
+        assert_eq!(
+            target(&resolver, "_HTTP._tcp.example.com."),
+            "b.example.com"
+        );
+        assert_eq!(mock.lookups(), 2);
+
+        resolver.invalidate("_http._tcp.example.com.");
+        assert_eq!(target(&resolver, "_http._tcp.example.com"), "c.example.com");
+        assert_eq!(mock.lookups(), 3);
+
+        // Failures aren't cached
+        block_on(resolver.get_srv_records("_http._tcp.example.org")).unwrap_err();
+        assert_eq!(resolver.len(), 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let ttl = Duration::from_secs(60);
+        let mock = MockResolver::new([
+            records("a.example.com", ttl),
+            records("b.example.com", ttl),
+            records("c.example.com", ttl),
+            records("a2.example.com", ttl),
+        ]);
+        let resolver = CachingResolver::with_max_entries(mock.clone(), 2);
+        assert_eq!(target(&resolver, "_a._tcp.example.com"), "a.example.com");
+        assert_eq!(target(&resolver, "_b._tcp.example.com"), "b.example.com");
+        assert_eq!(target(&resolver, "_a._tcp.example.com"), "a.example.com");
+        // `_b` is least recently used, so it's evicted
+        assert_eq!(target(&resolver, "_c._tcp.example.com"), "c.example.com");
+        assert_eq!(resolver.len(), 2);
+        assert_eq!(target(&resolver, "_a._tcp.example.com"), "a.example.com");
+        assert_eq!(target(&resolver, "_c._tcp.example.com"), "c.example.com");
+        assert_eq!(mock.lookups(), 3);
+
+        resolver.clear();
+        assert!(resolver.is_empty());
+        assert_eq!(target(&resolver, "_a._tcp.example.com"), "a2.example.com");
+    }
+}
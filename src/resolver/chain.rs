@@ -0,0 +1,262 @@
+//! SRV resolver trying several resolvers in order.
+
+use super::{BoxError, Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::{fmt, net::IpAddr, time::Instant};
+
+/// Error of one resolver of a [`ChainedResolver`].
+#[derive(Debug)]
+struct LinkError {
+    error: BoxError,
+    no_records: bool,
+    kind: LookupErrorKind,
+}
+
+/// Errors produced by a [`ChainedResolver`] when every resolver failed,
+/// holding each resolver's error in order.
+#[derive(Debug, thiserror::Error)]
+pub struct ChainedResolverError(Vec<LinkError>);
+
+impl ChainedResolverError {
+    /// Gets the error of each resolver, in the order they were tried.
+    pub fn errors(&self) -> impl Iterator<Item = &(dyn std::error::Error + Send + Sync + 'static)> {
+        self.0.iter().map(|link| &*link.error)
+    }
+}
+
+impl fmt::Display for ChainedResolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("no resolvers to try");
+        }
+        write!(f, "all {} resolvers failed", self.0.len())?;
+        for (idx, link) in self.0.iter().enumerate() {
+            write!(f, "; resolver {idx}: {}", link.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// SRV records found by one resolver of a [`ChainedResolver`], along with
+/// the time they're valid until, where the answer came from, and its glue.
+type SrvAnswer = (Vec<OwnedSrvRecord>, Instant, LookupMeta, Glue);
+
+/// Resolver of a [`ChainedResolver`], with its records and errors converted
+/// to common types.
+#[async_trait]
+trait Link: Send + Sync {
+    async fn srv(&self, srv: &str) -> Result<SrvAnswer, LinkError>;
+
+    async fn ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), LinkError>;
+}
+
+impl LinkError {
+    fn of<R: SrvResolver>(error: R::Error) -> Self
+    where
+        R::Error: Send + Sync,
+    {
+        Self {
+            no_records: R::is_no_records_found(&error),
+            kind: R::classify_error(&error),
+            error: Box::new(error),
+        }
+    }
+}
+
+#[async_trait]
+impl<R> Link for R
+where
+    R: SrvResolver,
+    R::Error: Send + Sync,
+{
+    async fn srv(&self, srv: &str) -> Result<SrvAnswer, LinkError> {
+        let (records, valid_until, meta, glue) = self
+            .get_srv_records_with_glue(srv)
+            .await
+            .map_err(LinkError::of::<R>)?;
+        let records = records.iter().map(OwnedSrvRecord::from_record).collect();
+        Ok((records, valid_until, meta, glue))
+    }
+
+    async fn ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), LinkError> {
+        self.get_ip_addrs(host).await.map_err(LinkError::of::<R>)
+    }
+}
+
+/// SRV resolver trying several resolvers in order until one succeeds, e.g.
+/// an internal resolver and then a public one for when the internal one is
+/// unreachable.
+///
+/// Records are converted to [`OwnedSrvRecord`]s so that resolvers with
+/// different record types can be chained. If every resolver fails, the
+/// errors of all of them are produced, and the lookup is considered to have
+/// found no records only if every resolver found none.
+///
+/// ```
+/// # use detsys_srv::resolver::{ChainedResolver, StaticResolver};
+/// # use std::time::Duration;
+/// let resolver = ChainedResolver::new()
+///     .push(StaticResolver::failing("unreachable"))
+///     .push(StaticResolver::new([("a.example.com", 443, 1, 100)], Duration::from_secs(60)));
+/// ```
+#[derive(Default)]
+pub struct ChainedResolver {
+    links: Vec<Box<dyn Link>>,
+}
+
+impl ChainedResolver {
+    /// Creates a chain without any resolvers, whose lookups fail until one
+    /// is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resolver to try after the resolvers already in the chain.
+    pub fn push<R>(mut self, resolver: R) -> Self
+    where
+        R: SrvResolver + 'static,
+        R::Error: Send + Sync,
+    {
+        self.links.push(Box::new(resolver));
+        self
+    }
+
+    /// Gets the number of resolvers in the chain.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Determines if the chain has no resolvers.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+impl fmt::Debug for ChainedResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainedResolver")
+            .field("len", &self.links.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SrvResolver for ChainedResolver {
+    type Record = OwnedSrvRecord;
+    type Error = ChainedResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, valid_until, _) = self.get_srv_records_with_meta(srv).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let mut errors = Vec::new();
+        for (idx, link) in self.links.iter().enumerate() {
+            match link.srv(srv).await {
+                Ok(found) => return Ok(found),
+                Err(e) => {
+                    tracing::trace!(idx, error = %e.error, "Chained resolver failed, trying the next");
+                    errors.push(e);
+                }
+            }
+        }
+        Err(ChainedResolverError(errors))
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        let mut errors = Vec::new();
+        for link in &self.links {
+            match link.ip_addrs(host).await {
+                Ok(found) => return Ok(found),
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(ChainedResolverError(errors))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        !error.0.is_empty() && error.0.iter().all(|link| link.no_records)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        // Resolvers failing in different ways can't be summed up
+        let mut kinds = error.0.iter().map(|link| link.kind);
+        let first = kinds.next().unwrap_or(LookupErrorKind::Unknown);
+        match kinds.all(|kind| kind == first) {
+            true => first,
+            false => LookupErrorKind::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resolver::{MockResolver, MockResponse, StaticResolver},
+        SrvRecord,
+    };
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    fn targets(resolver: &ChainedResolver) -> Result<Vec<String>, ChainedResolverError> {
+        let (records, _) = block_on(resolver.get_srv_records_unordered("_http._tcp.example.com"))?;
+        Ok(records.iter().map(|r| r.target().to_owned()).collect())
+    }
+
+    #[test]
+    fn first_success() {
+        let ttl = Duration::from_secs(60);
+        let resolver = ChainedResolver::new()
+            .push(StaticResolver::failing("unreachable"))
+            .push(MockResolver::new([MockResponse::records(
+                [("a.example.com.", 443, 1, 100)],
+                ttl,
+            )]))
+            .push(StaticResolver::new([("b.example.com", 443, 1, 100)], ttl));
+        assert_eq!(resolver.len(), 3);
+        assert_eq!(targets(&resolver).unwrap(), ["a.example.com."]);
+        assert_eq!(targets(&resolver).unwrap(), ["b.example.com"]);
+    }
+
+    #[test]
+    fn all_fail() {
+        let resolver = ChainedResolver::new()
+            .push(StaticResolver::failing("unreachable"))
+            .push(MockResolver::new([MockResponse::NoRecords]));
+        let err = targets(&resolver).unwrap_err();
+        assert_eq!(err.errors().count(), 2);
+        assert_eq!(
+            err.to_string(),
+            "all 2 resolvers failed; resolver 0: unreachable; resolver 1: no SRV records"
+        );
+        assert!(!ChainedResolver::is_no_records_found(&err));
+
+        let resolver = ChainedResolver::new()
+            .push(StaticResolver::empty(Duration::ZERO).for_name("_http._tcp.example.net"))
+            .push(MockResolver::new([MockResponse::NoRecords]));
+        assert!(ChainedResolver::is_no_records_found(
+            &targets(&resolver).unwrap_err()
+        ));
+
+        let err = targets(&ChainedResolver::new()).unwrap_err();
+        assert_eq!(err.to_string(), "no resolvers to try");
+        assert!(!ChainedResolver::is_no_records_found(&err));
+    }
+}
@@ -0,0 +1,199 @@
+//! SRV resolver flattening the instances of a DNS-SD service.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use std::{net::IpAddr, time::Instant};
+
+/// SRV resolver treating SRV names as DNS-SD (RFC 6763) service names,
+/// resolving them to the SRV records of every instance of the service so
+/// that a client can execute operations on all of them as one pool.
+///
+/// The records are valid until the earliest expiry of the service's PTR
+/// records and its instances' SRV records. Instances whose SRV records can't
+/// be looked up are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct DnsSdResolver<R>(pub R);
+
+impl<R: SrvResolver> DnsSdResolver<R> {
+    /// Looks up the SRV records and glue of every instance of `srv`.
+    async fn lookup(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<R::Record>, Instant, LookupMeta, Glue), R::Error> {
+        let (instances, valid_until) = self.0.get_ptr_records(srv).await?;
+        let lookups = join_all(instances.iter().map(|instance| async move {
+            let lookup = self.0.get_srv_records_with_glue(instance).await;
+            if let Err(e) = &lookup {
+                tracing::trace!(%instance, %e, "Failed to look up DNS-SD instance");
+            }
+            lookup.ok()
+        }))
+        .await;
+
+        let mut merged = (Vec::new(), valid_until, None, Glue::default());
+        for (records, instance_valid_until, meta, glue) in lookups.into_iter().flatten() {
+            let (all, min_valid_until, first_meta, all_glue) = &mut merged;
+            all.extend(records);
+            *min_valid_until = instance_valid_until.min(*min_valid_until);
+            first_meta.get_or_insert(meta);
+            all_glue.merge(glue);
+        }
+        let (records, valid_until, meta, glue) = merged;
+        Ok((records, valid_until, meta.unwrap_or_default(), glue))
+    }
+}
+
+#[async_trait]
+impl<R> SrvResolver for DnsSdResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send,
+    R::Error: Send,
+{
+    type Record = R::Record;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, valid_until, _, _) = self.lookup(srv).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    /// Reports where the answer for the first instance that was looked up
+    /// came from, with the glue of every instance.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let (mut records, valid_until, meta, glue) = self.lookup(srv).await?;
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, valid_until, meta, glue))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.0.get_ip_addrs(host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.0.get_tlsa_records(name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.0.get_https_records(name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.0.get_https_records_with_meta(name).await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.0.get_ptr_records(name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.0.get_txt_records(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OwnedSrvRecord;
+    use futures::executor::block_on;
+    use std::{io, net::SocketAddr, time::Duration};
+
+    /// Resolver announcing three printers, the last of which fails to be
+    /// looked up, each answered by its own name server with glue.
+    struct Printers;
+
+    fn name_server(instance: &str) -> SocketAddr {
+        match instance {
+            "a._ipp._tcp.local" => "192.0.2.1:53".parse().unwrap(),
+            _ => "192.0.2.2:53".parse().unwrap(),
+        }
+    }
+
+    #[async_trait]
+    impl SrvResolver for Printers {
+        type Record = OwnedSrvRecord;
+        type Error = io::Error;
+
+        async fn get_srv_records_unordered(
+            &self,
+            srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+            let (records, valid_until, _, _) = self.get_srv_records_with_glue(srv).await?;
+            Ok((records, valid_until))
+        }
+
+        async fn get_srv_records_with_glue(
+            &self,
+            srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+            if srv == "c._ipp._tcp.local" {
+                return Err(io::Error::other("SERVFAIL"));
+            }
+            let target = format!("{}.local", &srv[..1]);
+            let glue = [(target.as_str(), IpAddr::from([192, 0, 2, 10]))]
+                .into_iter()
+                .collect();
+            let meta = LookupMeta::default().with_server(name_server(srv));
+            let record = OwnedSrvRecord::new(target, 631, 1, 1);
+            Ok((
+                vec![record],
+                Instant::now() + Duration::from_secs(60),
+                meta,
+                glue,
+            ))
+        }
+
+        async fn get_ptr_records(
+            &self,
+            _name: &str,
+        ) -> Result<(Vec<String>, Instant), Self::Error> {
+            let instances = ["a", "b", "c"].map(|n| format!("{n}._ipp._tcp.local"));
+            Ok((instances.into(), Instant::now() + Duration::from_secs(60)))
+        }
+    }
+
+    #[test]
+    fn forwards_meta_and_glue() {
+        let resolver = DnsSdResolver(Printers);
+        let (records, _, meta, glue) =
+            block_on(resolver.get_srv_records_with_glue("_ipp._tcp.local")).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(meta.server(), Some(name_server("a._ipp._tcp.local")));
+        for target in ["a.local", "b.local"] {
+            assert_eq!(glue.addrs(target), [IpAddr::from([192, 0, 2, 10])]);
+        }
+
+        let (_, _, meta) = block_on(resolver.get_srv_records_with_meta("_ipp._tcp.local")).unwrap();
+        assert_eq!(meta.server(), Some(name_server("a._ipp._tcp.local")));
+    }
+}
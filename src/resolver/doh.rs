@@ -0,0 +1,582 @@
+//! SRV resolver sending DNS queries over HTTPS.
+
+use super::{hickory, BoxError, DnsProtocol, Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use hickory_resolver::{
+    proto::{
+        op::{Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{rdata::SRV, RData, Record, RecordType},
+        ProtoError,
+    },
+    IntoName,
+};
+use http::{header, Method, StatusCode};
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// Media type of DNS messages in RFC 8484 requests and responses.
+const DNS_MESSAGE: &str = "application/dns-message";
+
+/// HTTP client sending the requests of a [`DohResolver`], implemented on top
+/// of whichever HTTP client an application already uses.
+#[async_trait]
+pub trait DohClient: Send + Sync {
+    /// Sends a request, producing its response.
+    async fn send(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, BoxError>;
+}
+
+/// Errors produced by a [`DohResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum DohError {
+    /// Produced when the HTTP request fails
+    #[error("DNS-over-HTTPS request failed")]
+    Request(#[source] BoxError),
+    /// Produced when the endpoint responds with a status other than 200
+    #[error("DNS-over-HTTPS endpoint responded with {status}")]
+    Status {
+        /// Status of the response
+        status: StatusCode,
+    },
+    /// Produced when a query can't be encoded or a response decoded
+    #[error("invalid DNS message")]
+    Message(#[from] ProtoError),
+    /// Produced when the response to a query isn't successful
+    #[error("{code} response to {record_type} query for {name}")]
+    Response {
+        /// Name that was queried
+        name: String,
+        /// Type of the query
+        record_type: RecordType,
+        /// Response code of the response
+        code: ResponseCode,
+    },
+}
+
+/// SRV resolver sending RFC 8484 wire-format queries to a DNS-over-HTTPS
+/// endpoint, for networks where plain DNS to arbitrary resolvers is blocked
+/// but HTTPS isn't.
+///
+/// Answers are valid for the smallest TTL of their records.
+#[derive(Debug, Clone)]
+pub struct DohResolver<C> {
+    client: C,
+    endpoint: http::Uri,
+}
+
+impl<C: DohClient> DohResolver<C> {
+    /// Creates a resolver sending queries with `client` to Cloudflare's
+    /// endpoint, `https://cloudflare-dns.com/dns-query`.
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            endpoint: http::Uri::from_static("https://cloudflare-dns.com/dns-query"),
+        }
+    }
+
+    /// Sets the endpoint queries are sent to, e.g.
+    /// `https://dns.google/dns-query`.
+    pub fn endpoint(self, endpoint: http::Uri) -> Self {
+        Self { endpoint, ..self }
+    }
+
+    /// Gets the endpoint queries are sent to.
+    pub fn current_endpoint(&self) -> &http::Uri {
+        &self.endpoint
+    }
+
+    /// Looks up the records of a name.
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Result<Answer, DohError> {
+        let mut name = name.into_name()?;
+        name.set_fqdn(true);
+        tracing::trace!(%name, ?record_type, endpoint = %self.endpoint, "Sending DNS-over-HTTPS query");
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri(self.endpoint.clone())
+            .header(header::CONTENT_TYPE, DNS_MESSAGE)
+            .header(header::ACCEPT, DNS_MESSAGE)
+            .body(query_message(name.clone(), record_type).to_vec()?)
+            .map_err(|e| DohError::Request(e.into()))?;
+        let response = self.client.send(request).await.map_err(DohError::Request)?;
+        if response.status() != StatusCode::OK {
+            return Err(DohError::Status {
+                status: response.status(),
+            });
+        }
+
+        let now = Instant::now();
+        let response = Message::from_vec(response.body())?;
+        if response.response_code() != ResponseCode::NoError {
+            return Err(DohError::Response {
+                name: name.to_string(),
+                record_type,
+                code: response.response_code(),
+            });
+        }
+        let records = response
+            .answers()
+            .iter()
+            .filter(|record| record.record_type() == record_type)
+            .cloned()
+            .collect::<Vec<_>>();
+        let ttl = records.iter().map(Record::ttl).min().unwrap_or(0);
+        Ok(Answer {
+            records,
+            additionals: response.additionals().to_vec(),
+            valid_until: now + Duration::from_secs(ttl.into()),
+            authentic_data: response.authentic_data(),
+        })
+    }
+
+    async fn srv_lookup(&self, srv: &str) -> Result<(Vec<SRV>, Answer), DohError> {
+        let mut answer = self.lookup(srv, RecordType::SRV).await?;
+        let records = std::mem::take(&mut answer.records)
+            .into_iter()
+            .filter_map(|record| match record.into_data() {
+                RData::SRV(srv) => Some(srv),
+                _ => None,
+            })
+            .collect();
+        Ok((records, answer))
+    }
+}
+
+/// Answer to a query: the records of the queried type, valid until the
+/// smallest of their TTLs, along with its additional section and whether the
+/// endpoint authenticated it with DNSSEC.
+struct Answer {
+    records: Vec<Record>,
+    additionals: Vec<Record>,
+    valid_until: Instant,
+    authentic_data: bool,
+}
+
+impl Answer {
+    fn meta(&self) -> LookupMeta {
+        LookupMeta::default()
+            .with_protocol(DnsProtocol::Https)
+            .with_authenticated(self.authentic_data)
+    }
+}
+
+/// Builds a recursive query with an ID of zero, as RFC 8484 recommends for
+/// cache friendliness, asking for the AD bit to be set on authenticated
+/// answers (RFC 6840).
+fn query_message(name: hickory_resolver::Name, record_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+        .set_id(0)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .set_authentic_data(true)
+        .add_query(Query::query(name, record_type));
+    message
+}
+
+#[async_trait]
+impl<C: DohClient> SrvResolver for DohResolver<C> {
+    type Record = SRV;
+    type Error = DohError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, answer) = self.srv_lookup(srv).await?;
+        Ok((records, answer.valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    /// Reports the A/AAAA records in the answer's additional section as glue.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let (mut records, answer) = self.srv_lookup(srv).await?;
+        Self::order_srv_records(&mut records, rand::rng());
+        let glue = answer
+            .additionals
+            .iter()
+            .filter_map(|record| Some((record.name().to_string(), record.data().ip_addr()?)))
+            .collect();
+        Ok((records, answer.valid_until, answer.meta(), glue))
+    }
+
+    /// Looks up A and AAAA records, only failing if neither lookup succeeds.
+    /// The addresses are valid until the earliest expiry of the families
+    /// that have any.
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        let (v4, v6) = futures_util::join!(
+            self.lookup(host, RecordType::A),
+            self.lookup(host, RecordType::AAAA)
+        );
+        let answers = match (v4, v6) {
+            (Err(e), _) | (_, Err(e)) if !Self::is_no_records_found(&e) => return Err(e),
+            (Err(e), Err(_)) => return Err(e),
+            (v4, v6) => v4.into_iter().chain(v6).collect::<Vec<_>>(),
+        };
+        let valid_until = answers
+            .iter()
+            .filter(|answer| !answer.records.is_empty())
+            .map(|answer| answer.valid_until)
+            .min();
+        let addrs = answers
+            .into_iter()
+            .flat_map(|answer| answer.records)
+            .filter_map(|record| record.data().ip_addr())
+            .collect();
+        Ok((addrs, valid_until.unwrap_or_else(Instant::now)))
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        let answer = match self.lookup(name, RecordType::TLSA).await {
+            Ok(answer) => answer,
+            Err(e) if Self::is_no_records_found(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::TLSA(tlsa) => Some(TlsaRecord::new(
+                    tlsa.cert_usage().into(),
+                    tlsa.selector().into(),
+                    tlsa.matching().into(),
+                    tlsa.cert_data().to_vec(),
+                )),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        let (records, valid_until, _) = self.get_https_records_with_meta(name).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        let answer = self.lookup(name, RecordType::HTTPS).await?;
+        let records = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::HTTPS(https) => Some(hickory::svcb_record(&https.0)),
+                _ => None,
+            })
+            .collect();
+        Ok((records, answer.valid_until, answer.meta()))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        Self::classify_error(error).is_no_records()
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        match error {
+            DohError::Response { code, .. } => match *code {
+                ResponseCode::NXDomain => LookupErrorKind::NxDomain,
+                _ => LookupErrorKind::ServerFailure,
+            },
+            DohError::Status { .. } => LookupErrorKind::ServerFailure,
+            DohError::Request(_) | DohError::Message(_) => LookupErrorKind::Unknown,
+        }
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let answer = self.lookup(name, RecordType::PTR).await?;
+        let names = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::PTR(ptr) => Some(ptr.0.to_string()),
+                _ => None,
+            })
+            .collect();
+        Ok((names, answer.valid_until))
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let answer = match self.lookup(name, RecordType::TXT).await {
+            Ok(answer) => answer,
+            Err(e) if Self::is_no_records_found(&e) => return Ok((Vec::new(), Instant::now())),
+            Err(e) => return Err(e),
+        };
+        let strings = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::TXT(txt) => Some(txt),
+                _ => None,
+            })
+            .flat_map(|txt| txt.iter())
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+            .collect();
+        Ok((strings, answer.valid_until))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    /// Response of a DNS-over-HTTPS endpoint to a SRV query for
+    /// `_http._tcp.example.com`, with records for `test1.example.com` (TTL
+    /// 300) and `test2.example.com` (TTL 60).
+    const RECORDED_RESPONSE: &[u8] = &[
+        0x00, 0x00, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, //
+        0x05, 0x5f, 0x68, 0x74, 0x74, 0x70, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x07, //
+        0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, //
+        0x00, 0x21, 0x00, 0x01, 0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, //
+        0x01, 0x2c, 0x00, 0x19, 0x00, 0x01, 0x00, 0x64, 0x01, 0xbb, 0x05, 0x74, //
+        0x65, 0x73, 0x74, 0x31, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, //
+        0x03, 0x63, 0x6f, 0x6d, 0x00, 0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, //
+        0x00, 0x00, 0x3c, 0x00, 0x19, 0x00, 0x02, 0x00, 0x32, 0x01, 0xbb, 0x05, //
+        0x74, 0x65, 0x73, 0x74, 0x32, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, //
+        0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00,
+    ];
+
+    /// Client answering every request with a fixed response, keeping the
+    /// requests it was sent.
+    struct Recorded {
+        status: StatusCode,
+        body: Vec<u8>,
+        requests: Mutex<Vec<http::Request<Vec<u8>>>>,
+    }
+
+    impl Recorded {
+        fn new(status: StatusCode, body: &[u8]) -> Self {
+            Self {
+                status,
+                body: body.to_vec(),
+                requests: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DohClient for Recorded {
+        async fn send(
+            &self,
+            request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, BoxError> {
+            self.requests.lock().unwrap().push(request);
+            Ok(http::Response::builder()
+                .status(self.status)
+                .body(self.body.clone())?)
+        }
+    }
+
+    #[test]
+    fn recorded_response() {
+        let resolver = DohResolver::new(Recorded::new(StatusCode::OK, RECORDED_RESPONSE))
+            .endpoint(http::Uri::from_static("https://dns.google/dns-query"));
+        let (records, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        let records = records
+            .iter()
+            .map(|r| (r.target().to_string(), r.port(), r.priority(), r.weight()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            [
+                ("test1.example.com.".to_owned(), 443, 1, 100),
+                ("test2.example.com.".to_owned(), 443, 2, 50)
+            ]
+        );
+        assert!(valid_until <= Instant::now() + Duration::from_secs(60));
+        assert!(valid_until > Instant::now() + Duration::from_secs(30));
+
+        let requests = resolver.client.requests.lock().unwrap();
+        let request = &requests[0];
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri(), "https://dns.google/dns-query");
+        assert_eq!(request.headers()[header::CONTENT_TYPE], DNS_MESSAGE);
+        let query = Message::from_vec(request.body()).unwrap();
+        assert_eq!(query.id(), 0);
+        assert!(query.authentic_data());
+        assert_eq!(query.queries()[0].query_type(), RecordType::SRV);
+        assert_eq!(
+            query.queries()[0].name().to_string(),
+            "_http._tcp.example.com."
+        );
+    }
+
+    #[test]
+    fn authenticated_data() {
+        let resolver = DohResolver::new(Recorded::new(StatusCode::OK, RECORDED_RESPONSE));
+        let (_, _, meta) =
+            block_on(resolver.get_srv_records_with_meta("_http._tcp.example.com")).unwrap();
+        assert_eq!(meta.authenticated(), Some(false));
+
+        let mut authenticated = RECORDED_RESPONSE.to_vec();
+        authenticated[3] |= 0x20;
+        let resolver = DohResolver::new(Recorded::new(StatusCode::OK, &authenticated));
+        let (records, _, meta) =
+            block_on(resolver.get_srv_records_with_meta("_http._tcp.example.com")).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(meta.authenticated(), Some(true));
+        assert_eq!(meta.protocol(), Some(&DnsProtocol::Https));
+    }
+
+    #[test]
+    fn unsuccessful_responses() {
+        let resolver = DohResolver::new(Recorded::new(StatusCode::BAD_GATEWAY, b"oops"));
+        assert!(matches!(
+            block_on(resolver.get_srv_records("_http._tcp.example.com")),
+            Err(DohError::Status {
+                status: StatusCode::BAD_GATEWAY
+            })
+        ));
+
+        let mut nxdomain = RECORDED_RESPONSE.to_vec();
+        nxdomain[3] = 0x83;
+        let resolver = DohResolver::new(Recorded::new(StatusCode::OK, &nxdomain));
+        let err = block_on(resolver.get_srv_records("_http._tcp.example.com")).unwrap_err();
+        assert!(DohResolver::<Recorded>::is_no_records_found(&err));
+
+        let resolver = DohResolver::new(Recorded::new(StatusCode::OK, b"not DNS"));
+        assert!(matches!(
+            block_on(resolver.get_srv_records("_http._tcp.example.com")),
+            Err(DohError::Message(_))
+        ));
+    }
+
+    /// Client answering each query with the response a function builds for
+    /// it.
+    struct Answering<F>(F);
+
+    #[async_trait]
+    impl<F: Fn(&Query, &mut Message) + Send + Sync> DohClient for Answering<F> {
+        async fn send(
+            &self,
+            request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, BoxError> {
+            let query = Message::from_vec(request.body())?.queries()[0].clone();
+            let mut response = Message::new();
+            response
+                .set_message_type(MessageType::Response)
+                .add_query(query.clone());
+            (self.0)(&query, &mut response);
+            Ok(http::Response::builder()
+                .status(StatusCode::OK)
+                .body(response.to_vec()?)?)
+        }
+    }
+
+    fn record(query: &Query, ttl: u32, rdata: RData) -> Record {
+        Record::from_rdata(query.name().clone(), ttl, rdata)
+    }
+
+    #[test]
+    fn ip_addrs() {
+        use hickory_resolver::proto::rr::rdata::{A, AAAA};
+
+        // An empty or missing family doesn't fail the lookup nor expire it
+        for v4_code in [ResponseCode::NoError, ResponseCode::NXDomain] {
+            let resolver = DohResolver::new(Answering(
+                move |query: &Query, response: &mut Message| match query.query_type() {
+                    RecordType::AAAA => {
+                        let aaaa = AAAA("2001:db8::1".parse().unwrap());
+                        response.add_answer(record(query, 300, RData::AAAA(aaaa)));
+                    }
+                    _ => {
+                        response.set_response_code(v4_code);
+                    }
+                },
+            ));
+            let (addrs, valid_until) = block_on(resolver.get_ip_addrs("a.example.com")).unwrap();
+            assert_eq!(addrs, ["2001:db8::1".parse::<IpAddr>().unwrap()]);
+            assert!(valid_until > Instant::now() + Duration::from_secs(200));
+        }
+
+        let resolver =
+            DohResolver::new(Answering(
+                |query: &Query, response: &mut Message| match query.query_type() {
+                    RecordType::A => {
+                        let a = A("192.0.2.1".parse().unwrap());
+                        response.add_answer(record(query, 60, RData::A(a)));
+                    }
+                    _ => {
+                        response.set_response_code(ResponseCode::ServFail);
+                    }
+                },
+            ));
+        let err = block_on(resolver.get_ip_addrs("a.example.com")).unwrap_err();
+        assert_eq!(
+            DohResolver::<Recorded>::classify_error(&err),
+            LookupErrorKind::ServerFailure
+        );
+
+        let resolver = DohResolver::new(Answering(|_: &Query, response: &mut Message| {
+            response.set_response_code(ResponseCode::NXDomain);
+        }));
+        let err = block_on(resolver.get_ip_addrs("a.example.com")).unwrap_err();
+        assert!(DohResolver::<Recorded>::is_no_records_found(&err));
+    }
+
+    #[test]
+    fn other_lookups() {
+        use hickory_resolver::proto::rr::rdata::{A, TXT};
+
+        let resolver =
+            DohResolver::new(Answering(
+                |query: &Query, response: &mut Message| match query.query_type() {
+                    RecordType::SRV => {
+                        let target = hickory_resolver::Name::from_ascii("a.example.com.").unwrap();
+                        let srv = SRV::new(1, 100, 443, target.clone());
+                        response.add_answer(record(query, 60, RData::SRV(srv)));
+                        let a = A("192.0.2.1".parse().unwrap());
+                        response.add_additional(Record::from_rdata(target, 60, RData::A(a)));
+                    }
+                    RecordType::TXT => {
+                        let txt = TXT::new(vec!["v=1".to_owned()]);
+                        response.add_answer(record(query, 60, RData::TXT(txt)));
+                    }
+                    RecordType::TLSA => {}
+                    _ => {
+                        response.set_response_code(ResponseCode::NXDomain);
+                    }
+                },
+            ));
+        let (records, _, meta, glue) =
+            block_on(resolver.get_srv_records_with_glue("_http._tcp.example.com")).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(meta.protocol(), Some(&DnsProtocol::Https));
+        assert_eq!(
+            glue.addrs("a.example.com"),
+            ["192.0.2.1".parse::<IpAddr>().unwrap()]
+        );
+        let (txt, _) = block_on(resolver.get_txt_records("example.com")).unwrap();
+        assert_eq!(txt, ["v=1"]);
+        assert!(
+            block_on(resolver.get_tlsa_records("_443._tcp.a.example.com"))
+                .unwrap()
+                .is_empty()
+        );
+        let err = block_on(resolver.get_https_records("example.com")).unwrap_err();
+        assert!(DohResolver::<Recorded>::is_no_records_found(&err));
+        let err = block_on(resolver.get_ptr_records("1.2.0.192.in-addr.arpa")).unwrap_err();
+        assert!(DohResolver::<Recorded>::is_no_records_found(&err));
+    }
+}
@@ -0,0 +1,521 @@
+//! SRV resolver sending the EDNS Client Subnet option with its queries.
+
+use super::{hickory, Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{IpRange, SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use hickory_resolver::{
+    config::{ResolverConfig, ResolverOpts},
+    name_server::{ConnectionProvider, NameServerPool},
+    proto::{
+        op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{
+            rdata::{
+                opt::{ClientSubnet, EdnsOption},
+                SRV,
+            },
+            RData, Record, RecordType,
+        },
+        xfer::{DnsHandle, DnsRequest, DnsRequestOptions},
+        ProtoError,
+    },
+    IntoName, ResolveError,
+};
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// SRV resolver that sends the EDNS Client Subnet option (RFC 7871) with its
+/// queries, so that authoritative servers tailoring answers to a client's
+/// location see the client's subnet rather than its recursive resolver's.
+///
+/// Queries are sent directly to the configured name servers without the
+/// caching or search domains of a [`hickory_resolver::Resolver`], since
+/// answers may differ by subnet.
+#[derive(Clone)]
+pub struct ClientSubnetResolver<P: ConnectionProvider> {
+    pool: NameServerPool<P>,
+    subnet: IpRange,
+    meta: LookupMeta,
+}
+
+impl<P: ConnectionProvider> ClientSubnetResolver<P> {
+    /// Creates a resolver sending `subnet` as the client subnet of its queries
+    /// to the name servers in `config`. Only the prefix of the subnet's
+    /// address is sent.
+    pub fn new(
+        config: &ResolverConfig,
+        options: ResolverOpts,
+        provider: P,
+        subnet: IpRange,
+    ) -> Self {
+        let name_servers = config.name_servers().to_vec().into();
+        let subnet = subnet.masked();
+        Self {
+            pool: NameServerPool::from_config(name_servers, options, provider),
+            subnet,
+            meta: hickory::config_meta(config)
+                .with_from_cache(false)
+                .with_client_subnet(subnet),
+        }
+    }
+
+    /// Gets the client subnet sent with the resolver's queries, with the bits
+    /// of its address past its prefix cleared.
+    pub fn client_subnet(&self) -> IpRange {
+        self.subnet
+    }
+
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Result<Answer, ResolveError> {
+        let mut name = name.into_name()?;
+        name.set_fqdn(true);
+        tracing::trace!(%name, ?record_type, subnet = ?self.subnet, "Sending query with client subnet");
+        let query = Query::query(name.clone(), record_type);
+        let message = query_message(query.clone(), self.subnet);
+        let response = self
+            .pool
+            .send(DnsRequest::new(message, DnsRequestOptions::default()))
+            .next()
+            .await
+            .ok_or_else(|| {
+                ResolveError::from(format!("no response to {record_type} query for {name}"))
+            })??;
+        let now = Instant::now();
+        let records = response
+            .answers()
+            .iter()
+            .filter(|record| record.record_type() == record_type)
+            .cloned()
+            .collect::<Vec<_>>();
+        // Report failures and empty answers like hickory's own lookups do, so
+        // that they're classified the same
+        if response.response_code() != ResponseCode::NoError || records.is_empty() {
+            let error = ProtoError::nx_error(
+                Box::new(query),
+                None,
+                None,
+                None,
+                response.response_code(),
+                false,
+                None,
+            );
+            return Err(error.into());
+        }
+        let ttl = records.iter().map(Record::ttl).min().unwrap_or(0);
+        Ok(Answer {
+            records,
+            additionals: response.additionals().to_vec(),
+            valid_until: now + Duration::from_secs(ttl.into()),
+        })
+    }
+}
+
+/// Records of the queried type answering a query, along with its additional
+/// section.
+struct Answer {
+    records: Vec<Record>,
+    additionals: Vec<Record>,
+    valid_until: Instant,
+}
+
+/// Builds a recursive query carrying `subnet` in its EDNS Client Subnet
+/// option, with the address bits past its prefix cleared as RFC 7871
+/// requires.
+fn query_message(query: Query, subnet: IpRange) -> Message {
+    let subnet = subnet.masked();
+    let mut edns = Edns::new();
+    edns.set_max_payload(1232);
+    edns.options_mut()
+        .insert(EdnsOption::Subnet(ClientSubnet::new(
+            subnet.addr(),
+            subnet.prefix_len(),
+            0,
+        )));
+    let mut message = Message::new();
+    message
+        .set_id(rand::random())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(query)
+        .set_edns(edns);
+    message
+}
+
+impl<P: ConnectionProvider> std::fmt::Debug for ClientSubnetResolver<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientSubnetResolver")
+            .field("subnet", &self.subnet)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<P: ConnectionProvider> SrvResolver for ClientSubnetResolver<P> {
+    type Record = SRV;
+    type Error = ResolveError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let answer = self.lookup(srv, RecordType::SRV).await?;
+        let records = answer
+            .records
+            .into_iter()
+            .filter_map(|record| match record.into_data() {
+                RData::SRV(srv) => Some(srv),
+                _ => None,
+            })
+            .collect();
+        Ok((records, answer.valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    /// Reports the A/AAAA records in the answer's additional section as glue.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let answer = self.lookup(srv, RecordType::SRV).await?;
+        let glue = answer
+            .additionals
+            .iter()
+            .filter_map(|record| {
+                let addr = record.data().ip_addr()?;
+                Some((record.name().to_string(), addr))
+            })
+            .collect();
+        let mut records = answer
+            .records
+            .into_iter()
+            .filter_map(|record| match record.into_data() {
+                RData::SRV(srv) => Some(srv),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, answer.valid_until, self.meta.clone(), glue))
+    }
+
+    /// Looks up A and AAAA records, only failing if neither family has any.
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        let (v4, v6) = futures_util::join!(
+            self.lookup(host, RecordType::A),
+            self.lookup(host, RecordType::AAAA)
+        );
+        let answers = match (v4, v6) {
+            (Err(e), _) | (_, Err(e)) if !Self::is_no_records_found(&e) => return Err(e),
+            (Err(e), Err(_)) => return Err(e),
+            (v4, v6) => v4.into_iter().chain(v6).collect::<Vec<_>>(),
+        };
+        let valid_until = answers.iter().map(|answer| answer.valid_until).min();
+        let addrs = answers
+            .into_iter()
+            .flat_map(|answer| answer.records)
+            .filter_map(|record| record.data().ip_addr())
+            .collect();
+        Ok((addrs, valid_until.unwrap_or_else(Instant::now)))
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        let answer = match self.lookup(name, RecordType::TLSA).await {
+            Ok(answer) => answer,
+            Err(e) if Self::is_no_records_found(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::TLSA(tlsa) => Some(TlsaRecord::new(
+                    tlsa.cert_usage().into(),
+                    tlsa.selector().into(),
+                    tlsa.matching().into(),
+                    tlsa.cert_data().to_vec(),
+                )),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        let (records, valid_until, _) = self.get_https_records_with_meta(name).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        let answer = self.lookup(name, RecordType::HTTPS).await?;
+        let records = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::HTTPS(https) => Some(hickory::svcb_record(&https.0)),
+                _ => None,
+            })
+            .collect();
+        Ok((records, answer.valid_until, self.meta.clone()))
+    }
+
+    /// Considers NXDOMAIN and NODATA answers as no records found, like the
+    /// hickory resolver.
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        Self::classify_error(error).is_no_records()
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        hickory::classify(error)
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let answer = self.lookup(name, RecordType::PTR).await?;
+        let names = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::PTR(ptr) => Some(ptr.0.to_string()),
+                _ => None,
+            })
+            .collect();
+        Ok((names, answer.valid_until))
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let answer = match self.lookup(name, RecordType::TXT).await {
+            Ok(answer) => answer,
+            Err(e) if Self::is_no_records_found(&e) => return Ok((Vec::new(), Instant::now())),
+            Err(e) => return Err(e),
+        };
+        let strings = answer
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::TXT(txt) => Some(txt),
+                _ => None,
+            })
+            .flat_map(|txt| txt.iter())
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+            .collect();
+        Ok((strings, answer.valid_until))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resolver::DefaultResolver, SrvClient};
+    use hickory_resolver::{
+        config::{NameServerConfig, NameServerConfigGroup},
+        name_server::TokioConnectionProvider,
+        proto::{
+            rr::rdata::{opt::EdnsCode, A, TXT},
+            xfer::Protocol,
+        },
+        Name,
+    };
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::{Arc, Mutex},
+    };
+
+    fn client_subnet(message: &Message) -> Option<ClientSubnet> {
+        let edns = message.extensions().as_ref()?;
+        match edns.option(EdnsCode::Subnet)? {
+            EdnsOption::Subnet(subnet) => Some(*subnet),
+            _ => None,
+        }
+    }
+
+    /// Spawns a name server on a local UDP socket, noting the client subnet
+    /// of each query in `subnets`. It answers for `_http._tcp.example.com`
+    /// with a SRV record and its glue, `a.example.com` with only an A record,
+    /// and `example.com` with a TXT record, and with NXDOMAIN otherwise.
+    async fn name_server(subnets: Arc<Mutex<Vec<Option<ClientSubnet>>>>) -> SocketAddr {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0; 1232];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                subnets.lock().unwrap().push(client_subnet(&request));
+                let query = request.query().unwrap().clone();
+                let name = query.name().clone();
+                let target = Name::from_ascii("a.example.com.").unwrap();
+                let glue = Record::from_rdata(
+                    target.clone(),
+                    60,
+                    RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+                );
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(OpCode::Query)
+                    .add_query(query.clone());
+                match (name.to_ascii().as_str(), query.query_type()) {
+                    ("_http._tcp.example.com.", RecordType::SRV) => {
+                        let srv = SRV::new(1, 100, 443, target);
+                        response.add_answer(Record::from_rdata(name, 60, RData::SRV(srv)));
+                        response.add_additional(glue);
+                    }
+                    ("a.example.com.", RecordType::A) => {
+                        response.add_answer(glue);
+                    }
+                    ("a.example.com.", _) => {}
+                    ("example.com.", RecordType::TXT) => {
+                        let txt = TXT::new(vec!["v=1".to_owned()]);
+                        response.add_answer(Record::from_rdata(name, 60, RData::TXT(txt)));
+                    }
+                    _ => {
+                        response.set_response_code(ResponseCode::NXDomain);
+                    }
+                }
+                socket
+                    .send_to(&response.to_vec().unwrap(), from)
+                    .await
+                    .unwrap();
+            }
+        });
+        addr
+    }
+
+    fn resolver_config(addr: SocketAddr) -> ResolverConfig {
+        let name_servers = vec![NameServerConfig::new(addr, Protocol::Udp)];
+        ResolverConfig::from_parts(None, Vec::new(), NameServerConfigGroup::from(name_servers))
+    }
+
+    #[test]
+    fn queries_carry_client_subnet() {
+        let query = Query::query(
+            Name::from_ascii("_http._tcp.example.com.").unwrap(),
+            RecordType::SRV,
+        );
+        let subnet = |addr: &str, prefix_len| {
+            IpRange::new(addr.parse::<IpAddr>().unwrap(), prefix_len).unwrap()
+        };
+        for (sent, expected) in [
+            (subnet("192.0.2.0", 24), ("192.0.2.0", 24)),
+            // Address bits past the prefix are cleared
+            (subnet("192.0.2.77", 24), ("192.0.2.0", 24)),
+            (subnet("192.0.2.77", 0), ("0.0.0.0", 0)),
+            (subnet("2001:db8:1234:5678::1", 48), ("2001:db8:1234::", 48)),
+        ] {
+            let message = query_message(query.clone(), sent);
+            assert!(message.recursion_desired());
+            assert_eq!(message.query(), Some(&query));
+            let (addr, prefix_len) = expected;
+            assert_eq!(
+                client_subnet(&message),
+                Some(ClientSubnet::new(addr.parse().unwrap(), prefix_len, 0))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn lookups() {
+        let subnets = Arc::new(Mutex::new(Vec::new()));
+        let addr = name_server(subnets.clone()).await;
+        let subnet = IpRange::new(Ipv4Addr::new(192, 0, 2, 77), 24).unwrap();
+        let resolver = ClientSubnetResolver::new(
+            &resolver_config(addr),
+            ResolverOpts::default(),
+            TokioConnectionProvider::default(),
+            subnet,
+        );
+        let masked = IpRange::new(Ipv4Addr::new(192, 0, 2, 0), 24).unwrap();
+        assert_eq!(resolver.client_subnet(), masked);
+
+        let (records, _, meta, glue) = resolver
+            .get_srv_records_with_glue("_http._tcp.example.com")
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(meta.client_subnet(), Some(masked));
+        assert_eq!(
+            glue.addrs("a.example.com"),
+            ["192.0.2.1".parse::<IpAddr>().unwrap()]
+        );
+
+        // An empty AAAA answer doesn't fail the lookup of addresses
+        let (addrs, _) = resolver.get_ip_addrs("a.example.com").await.unwrap();
+        assert_eq!(addrs, ["192.0.2.1".parse::<IpAddr>().unwrap()]);
+
+        let (txt, _) = resolver.get_txt_records("example.com").await.unwrap();
+        assert_eq!(txt, ["v=1"]);
+
+        // Missing names are classified like hickory's own lookups
+        let err = resolver
+            .get_srv_records("_http._tcp.missing.example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            ClientSubnetResolver::<TokioConnectionProvider>::classify_error(&err),
+            LookupErrorKind::NxDomain
+        );
+        let err = resolver
+            .get_https_records("a.example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            ClientSubnetResolver::<TokioConnectionProvider>::classify_error(&err),
+            LookupErrorKind::NoData
+        );
+        assert!(resolver
+            .get_tlsa_records("_443._tcp.a.example.com")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let sent = IpRange::new(Ipv4Addr::new(192, 0, 2, 0), 24).unwrap();
+        let expected = Some(ClientSubnet::new(sent.addr(), 24, 0));
+        assert!(subnets.lock().unwrap().iter().all(|sent| *sent == expected));
+    }
+
+    #[tokio::test]
+    async fn client_sends_subnet() {
+        let subnets = Arc::new(Mutex::new(Vec::new()));
+        let addr = name_server(subnets.clone()).await;
+        let resolver = hickory_resolver::Resolver::builder_with_config(
+            resolver_config(addr),
+            TokioConnectionProvider::default(),
+        )
+        .build();
+        let client = SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            DefaultResolver::from(resolver),
+        )
+        .edns_client_subnet(IpRange::new(Ipv4Addr::new(192, 0, 2, 77), 24).unwrap());
+
+        let (uris, _) = client.get_fresh_uri_candidates().await.unwrap();
+        assert_eq!(uris[0].host_str(), Some("a.example.com."));
+        let snapshot = client.snapshot();
+        assert_eq!(
+            snapshot.lookup_client_subnet.as_deref(),
+            Some("192.0.2.0/24")
+        );
+        assert_eq!(
+            snapshot.redacted().lookup_client_subnet.as_deref(),
+            Some("<redacted>")
+        );
+        assert_eq!(
+            *subnets.lock().unwrap(),
+            [Some(ClientSubnet::new("192.0.2.0".parse().unwrap(), 24, 0))]
+        );
+    }
+}
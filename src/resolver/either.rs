@@ -0,0 +1,350 @@
+//! Statically dispatched choice between two SRV resolvers.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SrvRecord, SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use std::{fmt, net::IpAddr, time::Instant};
+
+/// One of two values, e.g. SRV resolvers chosen between at runtime, like
+/// [`futures::future::Either`](https://docs.rs/futures/latest/futures/future/enum.Either.html).
+///
+/// A choice of resolvers is itself a resolver whose records and errors are
+/// choices of the two resolvers' records and errors, delegating to whichever
+/// resolver is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<A, B> {
+    /// The first value
+    A(A),
+    /// The second value
+    B(B),
+}
+
+impl<A, B> Either<A, B> {
+    /// Chooses the first value.
+    pub fn map_a(a: A) -> Self {
+        Self::A(a)
+    }
+
+    /// Chooses the second value.
+    pub fn map_b(b: B) -> Self {
+        Self::B(b)
+    }
+}
+
+impl<A, B> SrvRecord for Either<A, B>
+where
+    A: SrvRecord,
+    A::Target: Sized + 'static,
+    B: SrvRecord,
+    B::Target: Sized + 'static,
+{
+    type Target = dyn fmt::Display;
+
+    fn target(&self) -> &Self::Target {
+        match self {
+            Self::A(a) => a.target(),
+            Self::B(b) => b.target(),
+        }
+    }
+
+    fn port(&self) -> u16 {
+        match self {
+            Self::A(a) => a.port(),
+            Self::B(b) => b.port(),
+        }
+    }
+
+    fn priority(&self) -> u16 {
+        match self {
+            Self::A(a) => a.priority(),
+            Self::B(b) => b.priority(),
+        }
+    }
+
+    fn weight(&self) -> u16 {
+        match self {
+            Self::A(a) => a.weight(),
+            Self::B(b) => b.weight(),
+        }
+    }
+}
+
+impl<A: fmt::Display, B: fmt::Display> fmt::Display for Either<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A(a) => write!(f, "resolver A: {a}"),
+            Self::B(b) => write!(f, "resolver B: {b}"),
+        }
+    }
+}
+
+impl<A, B> std::error::Error for Either<A, B>
+where
+    A: std::error::Error + 'static,
+    B: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::A(a) => Some(a),
+            Self::B(b) => Some(b),
+        }
+    }
+}
+
+#[async_trait]
+impl<A, B> SrvResolver for Either<A, B>
+where
+    A: SrvResolver,
+    <A::Record as SrvRecord>::Target: Sized + 'static,
+    B: SrvResolver,
+    <B::Record as SrvRecord>::Target: Sized + 'static,
+{
+    type Record = Either<A::Record, B::Record>;
+    type Error = Either<A::Error, B::Error>;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => match a.get_srv_records_unordered(srv).await {
+                Ok((records, valid_until)) => {
+                    Ok((records.into_iter().map(Either::A).collect(), valid_until))
+                }
+                Err(e) => Err(Either::A(e)),
+            },
+            Self::B(b) => match b.get_srv_records_unordered(srv).await {
+                Ok((records, valid_until)) => {
+                    Ok((records.into_iter().map(Either::B).collect(), valid_until))
+                }
+                Err(e) => Err(Either::B(e)),
+            },
+        }
+    }
+
+    async fn get_srv_records(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => match a.get_srv_records(srv).await {
+                Ok((records, valid_until)) => {
+                    Ok((records.into_iter().map(Either::A).collect(), valid_until))
+                }
+                Err(e) => Err(Either::A(e)),
+            },
+            Self::B(b) => match b.get_srv_records(srv).await {
+                Ok((records, valid_until)) => {
+                    Ok((records.into_iter().map(Either::B).collect(), valid_until))
+                }
+                Err(e) => Err(Either::B(e)),
+            },
+        }
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        match self {
+            Self::A(a) => match a.get_srv_records_with_meta(srv).await {
+                Ok((records, valid_until, meta)) => Ok((
+                    records.into_iter().map(Either::A).collect(),
+                    valid_until,
+                    meta,
+                )),
+                Err(e) => Err(Either::A(e)),
+            },
+            Self::B(b) => match b.get_srv_records_with_meta(srv).await {
+                Ok((records, valid_until, meta)) => Ok((
+                    records.into_iter().map(Either::B).collect(),
+                    valid_until,
+                    meta,
+                )),
+                Err(e) => Err(Either::B(e)),
+            },
+        }
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        match self {
+            Self::A(a) => match a.get_srv_records_with_glue(srv).await {
+                Ok((records, valid_until, meta, glue)) => Ok((
+                    records.into_iter().map(Either::A).collect(),
+                    valid_until,
+                    meta,
+                    glue,
+                )),
+                Err(e) => Err(Either::A(e)),
+            },
+            Self::B(b) => match b.get_srv_records_with_glue(srv).await {
+                Ok((records, valid_until, meta, glue)) => Ok((
+                    records.into_iter().map(Either::B).collect(),
+                    valid_until,
+                    meta,
+                    glue,
+                )),
+                Err(e) => Err(Either::B(e)),
+            },
+        }
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => a.get_ip_addrs(host).await.map_err(Either::A),
+            Self::B(b) => b.get_ip_addrs(host).await.map_err(Either::B),
+        }
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        match self {
+            Self::A(a) => a.get_tlsa_records(name).await.map_err(Either::A),
+            Self::B(b) => b.get_tlsa_records(name).await.map_err(Either::B),
+        }
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => a.get_https_records(name).await.map_err(Either::A),
+            Self::B(b) => b.get_https_records(name).await.map_err(Either::B),
+        }
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        match self {
+            Self::A(a) => a.get_https_records_with_meta(name).await.map_err(Either::A),
+            Self::B(b) => b.get_https_records_with_meta(name).await.map_err(Either::B),
+        }
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => a.get_ptr_records(name).await.map_err(Either::A),
+            Self::B(b) => b.get_ptr_records(name).await.map_err(Either::B),
+        }
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        match self {
+            Self::A(a) => a.get_txt_records(name).await.map_err(Either::A),
+            Self::B(b) => b.get_txt_records(name).await.map_err(Either::B),
+        }
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        match error {
+            Either::A(a) => A::is_no_records_found(a),
+            Either::B(b) => B::is_no_records_found(b),
+        }
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        match error {
+            Either::A(a) => A::classify_error(a),
+            Either::B(b) => B::classify_error(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SrvClient;
+    use std::time::Duration;
+
+    /// Resolver producing a record for `a.example.com`.
+    #[derive(Debug)]
+    struct ResolverA;
+
+    #[async_trait]
+    impl SrvResolver for ResolverA {
+        type Record = (String, u16);
+        type Error = std::io::Error;
+
+        async fn get_srv_records_unordered(
+            &self,
+            _srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+            let record = ("a.example.com.".to_owned(), 443);
+            Ok((vec![record], Instant::now() + Duration::from_secs(60)))
+        }
+    }
+
+    /// Resolver that always fails.
+    #[derive(Debug)]
+    struct ResolverB;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("no records")]
+    struct NoRecords;
+
+    #[async_trait]
+    impl SrvResolver for ResolverB {
+        type Record = (String, u16);
+        type Error = NoRecords;
+
+        async fn get_srv_records_unordered(
+            &self,
+            _srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+            Err(NoRecords)
+        }
+    }
+
+    impl SrvRecord for (String, u16) {
+        type Target = String;
+
+        fn target(&self) -> &Self::Target {
+            &self.0
+        }
+
+        fn port(&self) -> u16 {
+            self.1
+        }
+
+        fn priority(&self) -> u16 {
+            0
+        }
+
+        fn weight(&self) -> u16 {
+            0
+        }
+    }
+
+    fn client(resolver: Either<ResolverA, ResolverB>) -> SrvClient<Either<ResolverA, ResolverB>> {
+        SrvClient::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+    }
+
+    #[test]
+    fn either_arm() {
+        let (uris, _) = futures::executor::block_on(
+            client(Either::map_a(ResolverA)).get_fresh_uri_candidates(),
+        )
+        .unwrap();
+        assert_eq!(uris, ["https://a.example.com.:443/".parse().unwrap()]);
+
+        let err = futures::executor::block_on(
+            client(Either::map_b(ResolverB)).get_fresh_uri_candidates(),
+        )
+        .unwrap_err();
+        let crate::Error::Lookup(err) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert!(matches!(err, Either::B(NoRecords)));
+        assert_eq!(err.to_string(), "resolver B: no records");
+    }
+}
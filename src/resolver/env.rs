@@ -0,0 +1,441 @@
+//! SRV resolver overridden by an environment variable, for local development.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{OwnedSrvRecord, SrvRecord, SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use std::{
+    env, fmt,
+    net::{IpAddr, Ipv6Addr},
+    time::{Duration, Instant},
+};
+
+/// How long records from the environment variable are valid for.
+const OVERRIDE_TTL: Duration = Duration::from_secs(60);
+
+/// Errors encountered when reading the environment variable of an
+/// [`EnvOverrideResolver`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EnvOverrideError {
+    /// Produced when the variable isn't valid Unicode
+    #[error("environment variable {var} isn't valid Unicode")]
+    NotUnicode {
+        /// Name of the variable
+        var: String,
+    },
+    /// Produced when an entry of the variable isn't of the form
+    /// `host:port[:priority[:weight]]`
+    #[error("malformed entry {entry:?} in environment variable {var}: {reason}")]
+    Malformed {
+        /// Name of the variable
+        var: String,
+        /// The malformed entry
+        entry: String,
+        /// What is wrong with the entry
+        reason: &'static str,
+    },
+}
+
+/// SRV resolver serving targets listed in an environment variable instead of
+/// looking them up with an inner resolver, so that developers can point a
+/// client at a local stack without editing DNS.
+///
+/// The variable ([`EnvOverrideResolver::DEFAULT_VAR`] unless given) holds a
+/// comma-separated list of `host:port[:priority[:weight]]` entries, where
+/// IPv6 hosts are enclosed in brackets (e.g. `[::1]:8443`) and stay so in
+/// the targets of the records, priorities
+/// default to 0, and weights to 1. It is read once, when the resolver is
+/// created, and answers SRV lookups of every name while it's set. Lookups of
+/// other record types are always passed through.
+///
+/// If the variable is unset or empty, SRV lookups are passed straight
+/// through to the inner resolver, whose records are produced unchanged as
+/// [`EnvOverrideRecord::Inner`].
+///
+/// ```
+/// # use detsys_srv::{resolver::{EnvOverrideResolver, StaticResolver}, SrvClient};
+/// # let var = "DETSYS_SRV_TARGETS_EXAMPLE";
+/// # std::env::set_var(var, "localhost:8443, [::1]:8444:1");
+/// let resolver = EnvOverrideResolver::with_var(StaticResolver::failing("unused"), var).unwrap();
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(uris[0].as_str(), "https://localhost:8443/");
+/// assert_eq!(uris[1].as_str(), "https://[::1]:8444/");
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvOverrideResolver<R> {
+    inner: R,
+    targets: Option<Vec<OwnedSrvRecord>>,
+}
+
+impl<R> EnvOverrideResolver<R> {
+    /// Default name of the environment variable listing targets.
+    pub const DEFAULT_VAR: &'static str = "DETSYS_SRV_TARGETS";
+
+    /// Creates a resolver serving the targets listed in
+    /// [`EnvOverrideResolver::DEFAULT_VAR`] if it's set, and looking them up
+    /// with `inner` otherwise.
+    ///
+    /// Fails if the variable is set but malformed, rather than ignoring it.
+    pub fn new(inner: R) -> Result<Self, EnvOverrideError> {
+        Self::with_var(inner, Self::DEFAULT_VAR)
+    }
+
+    /// Creates a resolver serving the targets listed in the environment
+    /// variable `var` if it's set, and looking them up with `inner`
+    /// otherwise.
+    ///
+    /// Fails if the variable is set but malformed, rather than ignoring it.
+    pub fn with_var(inner: R, var: &str) -> Result<Self, EnvOverrideError> {
+        let targets = match env::var(var) {
+            Ok(value) if value.trim().is_empty() => None,
+            Ok(value) => Some(parse_targets(var, &value)?),
+            Err(env::VarError::NotPresent) => None,
+            Err(env::VarError::NotUnicode(_)) => {
+                return Err(EnvOverrideError::NotUnicode {
+                    var: var.to_owned(),
+                })
+            }
+        };
+        if let Some(targets) = &targets {
+            tracing::trace!(
+                var,
+                targets = targets.len(),
+                "Overriding SRV lookups with targets from the environment"
+            );
+        }
+        Ok(Self { inner, targets })
+    }
+
+    /// Gets the targets listed in the environment variable, if it was set.
+    pub fn targets(&self) -> Option<&[OwnedSrvRecord]> {
+        self.targets.as_deref()
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner resolver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Parses the comma-separated `host:port[:priority[:weight]]` entries of
+/// the variable `var`.
+fn parse_targets(var: &str, value: &str) -> Result<Vec<OwnedSrvRecord>, EnvOverrideError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(|entry| {
+            parse_target(entry).map_err(|reason| EnvOverrideError::Malformed {
+                var: var.to_owned(),
+                entry: entry.to_owned(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+fn parse_target(entry: &str) -> Result<OwnedSrvRecord, &'static str> {
+    let (host, rest) = match entry.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed.split_once(']').ok_or("unclosed bracket")?;
+            let rest = rest.strip_prefix(':').ok_or("missing port")?;
+            if host.parse::<Ipv6Addr>().is_err() {
+                return Err("invalid IPv6 address");
+            }
+            // Kept bracketed, so that the target can be the host of a URL
+            (&entry[..host.len() + 2], rest)
+        }
+        None => entry.split_once(':').ok_or("missing port")?,
+    };
+    if host.is_empty() {
+        return Err("missing host");
+    }
+    let mut fields = rest.split(':');
+    let mut field = |default: Option<u16>, reason| match fields.next() {
+        Some(field) => field.parse::<u16>().map_err(|_| reason),
+        None => default.ok_or(reason),
+    };
+    let port = field(None, "invalid port")?;
+    let priority = field(Some(0), "invalid priority")?;
+    let weight = field(Some(1), "invalid weight")?;
+    if fields.next().is_some() {
+        return Err("too many fields");
+    }
+    Ok(OwnedSrvRecord::new(host, port, priority, weight))
+}
+
+/// SRV record produced by an [`EnvOverrideResolver`]: either one looked up by
+/// its inner resolver, or one listed in its environment variable.
+///
+/// Displays as its target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvOverrideRecord<T> {
+    /// Record looked up by the inner resolver, unchanged
+    Inner(T),
+    /// Record listed in the environment variable
+    Override(OwnedSrvRecord),
+}
+
+impl<T: SrvRecord> fmt::Display for EnvOverrideRecord<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inner(record) => write!(f, "{}", record.target()),
+            Self::Override(record) => write!(f, "{}", record.target()),
+        }
+    }
+}
+
+impl<T: SrvRecord + 'static> SrvRecord for EnvOverrideRecord<T> {
+    type Target = dyn fmt::Display;
+
+    fn target(&self) -> &Self::Target {
+        // The inner record's target may not be sized, so the record itself
+        // stands in for it
+        self
+    }
+
+    fn port(&self) -> u16 {
+        match self {
+            Self::Inner(record) => record.port(),
+            Self::Override(record) => record.port(),
+        }
+    }
+
+    fn priority(&self) -> u16 {
+        match self {
+            Self::Inner(record) => record.priority(),
+            Self::Override(record) => record.priority(),
+        }
+    }
+
+    fn weight(&self) -> u16 {
+        match self {
+            Self::Inner(record) => record.weight(),
+            Self::Override(record) => record.weight(),
+        }
+    }
+}
+
+fn inner_records<T>(records: Vec<T>) -> Vec<EnvOverrideRecord<T>> {
+    records.into_iter().map(EnvOverrideRecord::Inner).collect()
+}
+
+/// Copies the targets listed in the environment variable into records.
+fn override_records<T>(targets: &[OwnedSrvRecord]) -> Vec<EnvOverrideRecord<T>> {
+    targets
+        .iter()
+        .cloned()
+        .map(EnvOverrideRecord::Override)
+        .collect()
+}
+
+#[async_trait]
+impl<R> SrvResolver for EnvOverrideResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send + 'static,
+    R::Error: Send,
+{
+    type Record = EnvOverrideRecord<R::Record>;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        if let Some(targets) = &self.targets {
+            tracing::trace!(srv, "Serving SRV targets from the environment");
+            return Ok((override_records(targets), Instant::now() + OVERRIDE_TTL));
+        }
+        let (records, valid_until) = self.inner.get_srv_records_unordered(srv).await?;
+        Ok((inner_records(records), valid_until))
+    }
+
+    async fn get_srv_records(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        if self.targets.is_some() {
+            let (mut records, valid_until) = self.get_srv_records_unordered(srv).await?;
+            Self::order_srv_records(&mut records, rand::rng());
+            return Ok((records, valid_until));
+        }
+        let (records, valid_until) = self.inner.get_srv_records(srv).await?;
+        Ok((inner_records(records), valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        if self.targets.is_some() {
+            let (records, valid_until) = self.get_srv_records(srv).await?;
+            let meta = LookupMeta::default().with_from_cache(false);
+            return Ok((records, valid_until, meta, Glue::new()));
+        }
+        let (records, valid_until, meta, glue) = self.inner.get_srv_records_with_glue(srv).await?;
+        Ok((inner_records(records), valid_until, meta, glue))
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.inner.get_ip_addrs(host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.inner.get_tlsa_records(name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.inner.get_https_records(name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.inner.get_https_records_with_meta(name).await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_ptr_records(name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_txt_records(name).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::StaticResolver;
+    use futures::executor::block_on;
+
+    fn inner() -> StaticResolver {
+        StaticResolver::new([("dns.example.com", 443, 1, 100)], Duration::from_secs(60))
+    }
+
+    fn targets(resolver: &EnvOverrideResolver<StaticResolver>) -> Vec<(String, u16, u16, u16)> {
+        let (records, _) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        records
+            .iter()
+            .map(|r| (r.target().to_string(), r.port(), r.priority(), r.weight()))
+            .collect()
+    }
+
+    #[test]
+    fn overrides_lookups() {
+        let var = "DETSYS_SRV_TEST_OVERRIDES_LOOKUPS";
+        env::set_var(var, "localhost:8443:2:5, 127.0.0.1:8444:1 ,[::1]:8445");
+        let resolver = EnvOverrideResolver::with_var(inner(), var).unwrap();
+        assert_eq!(
+            targets(&resolver),
+            [
+                ("localhost".to_owned(), 8443, 2, 5),
+                ("127.0.0.1".to_owned(), 8444, 1, 1),
+                ("[::1]".to_owned(), 8445, 0, 1),
+            ]
+        );
+        let client = crate::SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+        let (uris, _) = block_on(client.get_fresh_uri_candidates()).unwrap();
+        assert_eq!(uris[0].as_str(), "https://[::1]:8445/");
+    }
+
+    #[test]
+    fn passes_through_when_unset() {
+        let var = "DETSYS_SRV_TEST_PASSES_THROUGH_WHEN_UNSET";
+        env::remove_var(var);
+        let resolver = EnvOverrideResolver::with_var(inner(), var).unwrap();
+        assert!(resolver.targets().is_none());
+        let (records, _) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        assert_eq!(
+            records,
+            [EnvOverrideRecord::Inner(OwnedSrvRecord::new(
+                "dns.example.com",
+                443,
+                1,
+                100
+            ))]
+        );
+
+        env::set_var(var, " ");
+        let resolver = EnvOverrideResolver::with_var(inner(), var).unwrap();
+        assert!(resolver.targets().is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        let var = "DETSYS_SRV_TEST_REJECTS_MALFORMED_ENTRIES";
+        for (value, entry, reason) in [
+            ("localhost", "localhost", "missing port"),
+            ("a:1,,b:2", "", "missing port"),
+            (":8443", ":8443", "missing host"),
+            ("localhost:http", "localhost:http", "invalid port"),
+            ("localhost:1:high", "localhost:1:high", "invalid priority"),
+            ("localhost:1:2:3:4", "localhost:1:2:3:4", "too many fields"),
+            ("[::1:8443", "[::1:8443", "unclosed bracket"),
+            ("[::1]8443", "[::1]8443", "missing port"),
+            (
+                "[localhost]:8443",
+                "[localhost]:8443",
+                "invalid IPv6 address",
+            ),
+        ] {
+            env::set_var(var, value);
+            let err = EnvOverrideResolver::with_var(inner(), var).unwrap_err();
+            assert_eq!(
+                err,
+                EnvOverrideError::Malformed {
+                    var: var.to_owned(),
+                    entry: entry.to_owned(),
+                    reason,
+                },
+                "{value}"
+            );
+        }
+        assert_eq!(
+            EnvOverrideResolver::with_var(inner(), var)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "malformed entry \"[localhost]:8443\" in environment variable {var}: \
+                 invalid IPv6 address"
+            )
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! SRV resolver reading records from a JSON or TOML file.
+
+use super::SrvResolver;
+use crate::OwnedSrvRecord;
+use arc_swap::ArcSwapOption;
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Errors produced by a [`FileResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum FileResolverError {
+    /// Produced when the records file doesn't exist
+    #[error("records file {path} is missing")]
+    Missing {
+        /// Path of the records file
+        path: PathBuf,
+    },
+    /// Produced when the records file can't be read
+    #[error("failed to read records file {path}")]
+    Read {
+        /// Path of the records file
+        path: PathBuf,
+        /// Error reading the file
+        #[source]
+        source: std::io::Error,
+    },
+    /// Produced when the records file isn't valid
+    #[error("failed to parse records file {path}")]
+    Parse {
+        /// Path of the records file
+        path: PathBuf,
+        /// Error parsing the file
+        #[source]
+        source: serde_json::Error,
+    },
+    /// Produced when a TOML records file isn't valid
+    #[cfg(feature = "toml")]
+    #[error("failed to parse records file {path}")]
+    ParseToml {
+        /// Path of the records file
+        path: PathBuf,
+        /// Error parsing the file
+        #[source]
+        source: toml::de::Error,
+    },
+    /// Produced when the records file has no records for a name
+    #[error("no SRV records for {name} in records file")]
+    NameNotFound {
+        /// Name that was looked up
+        name: String,
+    },
+}
+
+/// Largest TTL honored from a records file, the maximum DNS allows (RFC 2181
+/// section 8).
+const MAX_TTL: u64 = i32::MAX as u64;
+
+/// Entry of a records file read by a [`FileResolver`].
+#[derive(Debug, Clone, serde::Deserialize)]
+struct FileRecord {
+    target: String,
+    port: u16,
+    priority: u16,
+    weight: u16,
+    /// Seconds the record is valid for
+    ttl: u64,
+}
+
+/// Records file as last read, along with what identified its version.
+#[derive(Debug)]
+struct Loaded {
+    modified: Option<SystemTime>,
+    len: u64,
+    records: HashMap<String, Vec<FileRecord>>,
+}
+
+/// SRV resolver answering lookups from a JSON file mapping SRV names to their
+/// records, for environments without live DNS:
+///
+/// ```json
+/// {
+///     "_http._tcp.example.com": [
+///         { "target": "a.example.com", "port": 443, "priority": 1, "weight": 100, "ttl": 60 }
+///     ]
+/// }
+/// ```
+///
+/// With the `toml` feature, files with a `.toml` extension are read as TOML
+/// instead:
+///
+/// ```toml
+/// [["_http._tcp.example.com"]]
+/// target = "a.example.com"
+/// port = 443
+/// priority = 1
+/// weight = 100
+/// ttl = 60
+/// ```
+///
+/// TTLs are capped at the largest one DNS allows.
+///
+/// The file is read again whenever its modification time or size changes, so
+/// targets can be rotated by editing it. Answers are valid for the smallest
+/// TTL of their records, after which a client looks them up again and sees
+/// the edits.
+#[derive(Debug)]
+pub struct FileResolver {
+    path: PathBuf,
+    loaded: ArcSwapOption<Loaded>,
+}
+
+impl FileResolver {
+    /// Creates a resolver reading records from the file at `path`. The file
+    /// is first read by the first lookup.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            loaded: Default::default(),
+        }
+    }
+
+    /// Gets the path of the records file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the records file, reading it again if it changed since it was
+    /// last read.
+    fn load(&self) -> Result<Arc<Loaded>, FileResolverError> {
+        let metadata = std::fs::metadata(&self.path).map_err(|source| self.io_error(source))?;
+        let modified = metadata.modified().ok();
+        if let Some(loaded) = self.loaded.load_full() {
+            if modified.is_some() && loaded.modified == modified && loaded.len == metadata.len() {
+                return Ok(loaded);
+            }
+        }
+
+        let contents = std::fs::read(&self.path).map_err(|source| self.io_error(source))?;
+        let records = self
+            .parse(&contents)?
+            .into_iter()
+            .map(|(name, records)| (name.trim_end_matches('.').to_owned(), records))
+            .collect();
+        tracing::trace!(path = %self.path.display(), "Read records file");
+        let loaded = Arc::new(Loaded {
+            modified,
+            len: metadata.len(),
+            records,
+        });
+        self.loaded.store(Some(loaded.clone()));
+        Ok(loaded)
+    }
+
+    /// Parses the contents of the records file in the format its extension
+    /// names.
+    fn parse(
+        &self,
+        contents: &[u8],
+    ) -> Result<HashMap<String, Vec<FileRecord>>, FileResolverError> {
+        #[cfg(feature = "toml")]
+        if self.path.extension().is_some_and(|ext| ext == "toml") {
+            return std::str::from_utf8(contents)
+                .map_err(serde::de::Error::custom)
+                .and_then(toml::from_str)
+                .map_err(|source| FileResolverError::ParseToml {
+                    path: self.path.clone(),
+                    source,
+                });
+        }
+        serde_json::from_slice(contents).map_err(|source| FileResolverError::Parse {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    fn io_error(&self, source: std::io::Error) -> FileResolverError {
+        let path = self.path.clone();
+        match source.kind() {
+            std::io::ErrorKind::NotFound => FileResolverError::Missing { path },
+            _ => FileResolverError::Read { path, source },
+        }
+    }
+}
+
+#[async_trait]
+impl SrvResolver for FileResolver {
+    type Record = OwnedSrvRecord;
+    type Error = FileResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let loaded = self.load()?;
+        let records = loaded
+            .records
+            .get(srv.trim_end_matches('.'))
+            .ok_or_else(|| FileResolverError::NameNotFound {
+                name: srv.to_owned(),
+            })?;
+        let ttl = records.iter().map(|record| record.ttl).min().unwrap_or(0);
+        let now = Instant::now();
+        let valid_until = now
+            .checked_add(Duration::from_secs(ttl.min(MAX_TTL)))
+            .unwrap_or(now);
+        let records = records
+            .iter()
+            .map(|record| {
+                OwnedSrvRecord::new(&record.target, record.port, record.priority, record.weight)
+            })
+            .collect();
+        Ok((records, valid_until))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, FileResolverError::NameNotFound { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn targets(resolver: &FileResolver, name: &str) -> Result<Vec<String>, FileResolverError> {
+        let (records, _) = block_on(resolver.get_srv_records_unordered(name))?;
+        Ok(records
+            .iter()
+            .map(|record| crate::SrvRecord::target(record).to_owned())
+            .collect())
+    }
+
+    #[test]
+    fn reloads_records_file() {
+        let path =
+            std::env::temp_dir().join(format!("detsys-srv-records-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let resolver = FileResolver::new(&path);
+        assert!(matches!(
+            targets(&resolver, "_http._tcp.example.com"),
+            Err(FileResolverError::Missing { .. })
+        ));
+
+        std::fs::write(
+            &path,
+            r#"{"_http._tcp.example.com.": [
+                {"target": "a.example.com", "port": 443, "priority": 1, "weight": 100, "ttl": 60},
+                {"target": "b.example.com", "port": 443, "priority": 2, "weight": 100, "ttl": 30}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            targets(&resolver, "_http._tcp.example.com").unwrap(),
+            ["a.example.com", "b.example.com"]
+        );
+        let (_, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        assert!(valid_until <= Instant::now() + Duration::from_secs(30));
+        let err = targets(&resolver, "_http._tcp.example.net").unwrap_err();
+        assert!(FileResolver::is_no_records_found(&err));
+
+        std::fs::write(
+            &path,
+            r#"{"_http._tcp.example.com": [
+                {"target": "c.example.com", "port": 443, "priority": 1, "weight": 100, "ttl": 60}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            targets(&resolver, "_http._tcp.example.com").unwrap(),
+            ["c.example.com"]
+        );
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            targets(&resolver, "_http._tcp.example.com"),
+            Err(FileResolverError::Parse { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn caps_huge_ttl() {
+        let path = std::env::temp_dir().join(format!("detsys-srv-ttl-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"_http._tcp.example.com": [
+                {"target": "a.example.com", "port": 443, "priority": 1, "weight": 100, "ttl": 18446744073709551615}
+            ]}"#,
+        )
+        .unwrap();
+        let resolver = FileResolver::new(&path);
+        let (_, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        assert!(valid_until > Instant::now() + Duration::from_secs(60));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn reads_toml_records_file() {
+        let path =
+            std::env::temp_dir().join(format!("detsys-srv-records-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+            [["_http._tcp.example.com"]]
+            target = "a.example.com"
+            port = 443
+            priority = 1
+            weight = 100
+            ttl = 60
+            "#,
+        )
+        .unwrap();
+        let resolver = FileResolver::new(&path);
+        assert_eq!(
+            targets(&resolver, "_http._tcp.example.com").unwrap(),
+            ["a.example.com"]
+        );
+
+        std::fs::write(&path, "not toml").unwrap();
+        assert!(matches!(
+            targets(&resolver, "_http._tcp.example.com"),
+            Err(FileResolverError::ParseToml { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
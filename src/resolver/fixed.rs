@@ -0,0 +1,191 @@
+//! SRV resolver serving a fixed set of records.
+
+use super::SrvResolver;
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+/// Errors produced by a [`StaticResolver`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StaticResolverError {
+    /// Produced when looking up a name the resolver has no records for
+    #[error("no SRV records for {name}")]
+    NoRecords {
+        /// Name that was looked up
+        name: String,
+    },
+    /// Produced by every lookup of a [failing](StaticResolver::failing)
+    /// resolver
+    #[error("{0}")]
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+enum Answer {
+    Records(Vec<OwnedSrvRecord>),
+    Failure(String),
+}
+
+/// SRV resolver answering lookups with a fixed set of records, for testing
+/// code built on a [`SrvClient`] without real DNS.
+///
+/// ```
+/// # use detsys_srv::{resolver::StaticResolver, SrvClient};
+/// # use std::time::Duration;
+/// let resolver = StaticResolver::new(
+///     [("a.example.com", 443, 1, 100), ("b.example.com", 8443, 2, 100)],
+///     Duration::from_secs(60),
+/// );
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(uris[0].as_str(), "https://a.example.com/");
+/// ```
+///
+/// [`SrvClient`]: crate::SrvClient
+#[derive(Debug, Clone)]
+pub struct StaticResolver {
+    answer: Answer,
+    ttl: Duration,
+    srv_name: Option<String>,
+}
+
+impl StaticResolver {
+    /// Creates a resolver answering every lookup with records of the given
+    /// `(target, port, priority, weight)`, valid for `ttl`.
+    pub fn new<T: ToString>(
+        records: impl IntoIterator<Item = (T, u16, u16, u16)>,
+        ttl: Duration,
+    ) -> Self {
+        let records = records
+            .into_iter()
+            .map(|(target, port, priority, weight)| {
+                OwnedSrvRecord::new(target, port, priority, weight)
+            })
+            .collect();
+        Self::from_records(records, ttl)
+    }
+
+    /// Creates a resolver answering every lookup with `records`, valid for
+    /// `ttl`.
+    pub fn from_records(records: Vec<OwnedSrvRecord>, ttl: Duration) -> Self {
+        Self {
+            answer: Answer::Records(records),
+            ttl,
+            srv_name: None,
+        }
+    }
+
+    /// Creates a resolver answering every lookup with no records, valid for
+    /// `ttl`.
+    pub fn empty(ttl: Duration) -> Self {
+        Self::from_records(Vec::new(), ttl)
+    }
+
+    /// Creates a resolver failing every lookup with `message`.
+    pub fn failing(message: impl ToString) -> Self {
+        Self {
+            answer: Answer::Failure(message.to_string()),
+            ttl: Duration::ZERO,
+            srv_name: None,
+        }
+    }
+
+    /// Only answers lookups of `srv_name`, failing lookups of other names with
+    /// [`StaticResolverError::NoRecords`].
+    pub fn for_name(self, srv_name: impl ToString) -> Self {
+        Self {
+            srv_name: Some(srv_name.to_string()),
+            ..self
+        }
+    }
+}
+
+#[async_trait]
+impl SrvResolver for StaticResolver {
+    type Record = OwnedSrvRecord;
+    type Error = StaticResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        if let Some(name) = &self.srv_name {
+            if name.trim_end_matches('.') != srv.trim_end_matches('.') {
+                return Err(StaticResolverError::NoRecords {
+                    name: srv.to_owned(),
+                });
+            }
+        }
+        match &self.answer {
+            Answer::Records(records) => Ok((records.clone(), Instant::now() + self.ttl)),
+            Answer::Failure(message) => Err(StaticResolverError::Failed(message.clone())),
+        }
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, StaticResolverError::NoRecords { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, SrvClient};
+    use futures::executor::block_on;
+
+    fn client(resolver: StaticResolver) -> SrvClient<StaticResolver> {
+        SrvClient::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        )
+    }
+
+    fn executed_on(client: &SrvClient<StaticResolver>) -> String {
+        block_on(client.execute(|uri| async move {
+            Ok::<_, std::io::Error>(uri.host_str().unwrap().to_owned())
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn records() {
+        let client = client(
+            StaticResolver::new([("a.example.com", 443, 1, 100)], Duration::from_secs(60))
+                .for_name("_http._tcp.example.com."),
+        );
+        assert_eq!(executed_on(&client), "a.example.com");
+
+        let other = client.srv_name("_http._tcp.example.net");
+        assert!(matches!(
+            block_on(other.get_fresh_uri_candidates()),
+            Err(Error::Lookup(StaticResolverError::NoRecords { .. }))
+        ));
+        assert!(StaticResolver::is_no_records_found(
+            &StaticResolverError::NoRecords {
+                name: "_http._tcp.example.net".into()
+            }
+        ));
+    }
+
+    #[test]
+    fn empty_and_failing() {
+        let empty = client(StaticResolver::empty(Duration::from_secs(60)));
+        let (uris, _) = block_on(empty.get_fresh_uri_candidates()).unwrap();
+        assert!(uris.is_empty());
+        assert_eq!(executed_on(&empty), "fallback.example.com");
+
+        let failing = client(StaticResolver::failing("unreachable"));
+        assert!(matches!(
+            block_on(failing.get_fresh_uri_candidates()),
+            Err(Error::Lookup(StaticResolverError::Failed(message))) if message == "unreachable"
+        ));
+        assert_eq!(executed_on(&failing), "fallback.example.com");
+    }
+}
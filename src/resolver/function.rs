@@ -0,0 +1,71 @@
+//! SRV resolver implemented by a closure.
+
+use super::SrvResolver;
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::{fmt, future::Future, time::Instant};
+
+/// Boxed error produced by the closure of a [`FnResolver`].
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors produced by the closure of a [`FnResolver`].
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct FnResolverError(#[from] pub BoxError);
+
+/// SRV resolver calling a closure with each SRV name to look up, for quick
+/// integrations and tests.
+///
+/// ```
+/// # use detsys_srv::{resolver::FnResolver, OwnedSrvRecord, SrvClient};
+/// # use std::time::{Duration, Instant};
+/// let resolver = FnResolver::new(|name| async move {
+///     let record = OwnedSrvRecord::new(format!("host.{name}"), 443, 1, 100);
+///     Ok((vec![record], Instant::now() + Duration::from_secs(60)))
+/// });
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(uris[0].as_str(), "https://host.example.com/");
+/// ```
+#[derive(Clone)]
+pub struct FnResolver<F>(F);
+
+impl<F, Fut> FnResolver<F>
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(Vec<OwnedSrvRecord>, Instant), BoxError>> + Send,
+{
+    /// Creates a resolver calling `lookup` with each SRV name to look up. The
+    /// name is owned so that the future produced by `lookup` can use it.
+    pub fn new(lookup: F) -> Self {
+        Self(lookup)
+    }
+}
+
+impl<F> fmt::Debug for FnResolver<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnResolver").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<F, Fut> SrvResolver for FnResolver<F>
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(Vec<OwnedSrvRecord>, Instant), BoxError>> + Send,
+{
+    type Record = OwnedSrvRecord;
+    type Error = FnResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        Ok((self.0)(srv.to_owned()).await?)
+    }
+}
@@ -0,0 +1,89 @@
+//! Addresses of SRV targets that came along with a lookup.
+
+use std::{collections::HashMap, net::IpAddr};
+
+/// Addresses of SRV targets that came along with the answer to a lookup,
+/// e.g. A/AAAA glue in its additional section, produced by
+/// [`SrvResolver::get_srv_records_with_glue`](super::SrvResolver::get_srv_records_with_glue).
+///
+/// Targets are matched case-insensitively and without any trailing dot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Glue {
+    addrs: HashMap<String, Vec<IpAddr>>,
+}
+
+impl Glue {
+    /// Creates glue with no addresses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an address of a target, unless it's already known.
+    pub fn insert(&mut self, target: &str, addr: IpAddr) {
+        let addrs = self.addrs.entry(key(target)).or_default();
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+
+    /// Gets the addresses of a target, in the order they were added, which
+    /// are empty if none came along.
+    pub fn addrs(&self, target: &str) -> &[IpAddr] {
+        self.addrs.get(&key(target)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds the addresses of another lookup's glue.
+    pub(crate) fn merge(&mut self, other: Glue) {
+        for (target, addrs) in other.addrs {
+            for addr in addrs {
+                self.insert(&target, addr);
+            }
+        }
+    }
+
+    /// Determines if no target has any addresses.
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, IpAddr)> for Glue {
+    fn from_iter<I: IntoIterator<Item = (S, IpAddr)>>(iter: I) -> Self {
+        let mut glue = Self::new();
+        for (target, addr) in iter {
+            glue.insert(target.as_ref(), addr);
+        }
+        glue
+    }
+}
+
+/// Gets the key a target's addresses are stored under.
+fn key(target: &str) -> String {
+    target
+        .strip_suffix('.')
+        .unwrap_or(target)
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_targets_loosely() {
+        let v4 = IpAddr::from([192, 0, 2, 1]);
+        let v6 = IpAddr::from([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+        let glue = [
+            ("A.example.com.", v4),
+            ("a.example.com", v6),
+            ("a.example.com", v4),
+        ]
+        .into_iter()
+        .collect::<Glue>();
+        assert_eq!(glue.addrs("a.Example.com"), [v4, v6]);
+        assert_eq!(glue.addrs("a.example.com."), [v4, v6]);
+        assert!(glue.addrs("b.example.com").is_empty());
+        assert!(!glue.is_empty());
+        assert!(Glue::new().is_empty());
+    }
+}
@@ -1,12 +1,467 @@
-//! SRV resolver backed by [`hickory_resolver`].
+//! SRV resolver backed by [`hickory_resolver`], along with helpers for
+//! configuring hickory resolvers.
 
-use super::SrvResolver;
-use crate::SrvRecord;
+use super::{DnsProtocol, Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SrvRecord, SvcbRecord, TlsaRecord};
 use async_trait::async_trait;
 use hickory_resolver::{
-    name_server::ConnectionProvider, proto::rr::rdata::SRV, Name, ResolveError, Resolver,
+    config::{NameServerConfig, NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    lookup::Lookup,
+    name_server::ConnectionProvider,
+    proto::{
+        op::ResponseCode,
+        rr::{
+            rdata::{
+                svcb::{SvcParamValue, SVCB},
+                SRV,
+            },
+            RData, RecordType,
+        },
+        xfer::Protocol,
+        ProtoErrorKind,
+    },
+    Name, ResolveError, Resolver, TokioResolver,
 };
-use std::time::Instant;
+use std::{
+    net::{IpAddr, SocketAddr, UdpSocket},
+    ops::Deref,
+    time::{Duration, Instant},
+};
+
+/// Errors encountered when binding DNS lookups to a local address with
+/// [`bind_config`].
+#[derive(Debug, thiserror::Error)]
+pub enum BindError {
+    /// Produced when the local address can't be bound
+    #[error("failed to bind DNS lookups to local address {addr}")]
+    Bind {
+        /// Local address that couldn't be bound
+        addr: IpAddr,
+        /// Error binding the address
+        #[source]
+        source: std::io::Error,
+    },
+    /// Produced when no name server can be reached from the local address
+    #[error("no name servers of the same address family as local address {addr}")]
+    NoNameServers {
+        /// Local address lookups were to be bound to
+        addr: IpAddr,
+    },
+}
+
+/// Configures a hickory resolver's name servers to send lookups from
+/// `local_addr`, e.g. to make lookups leave through a particular interface on
+/// a multi-homed machine.
+///
+/// Name servers of the other address family than `local_addr` are removed.
+/// The address is bound once up front so that an address that isn't local to
+/// the machine is reported here rather than on every lookup.
+pub fn bind_config(
+    config: &ResolverConfig,
+    local_addr: IpAddr,
+) -> Result<ResolverConfig, BindError> {
+    UdpSocket::bind(SocketAddr::new(local_addr, 0)).map_err(|source| BindError::Bind {
+        addr: local_addr,
+        source,
+    })?;
+    let mut bound = ResolverConfig::from_parts(
+        config.domain().cloned(),
+        config.search().to_vec(),
+        Vec::new(),
+    );
+    for name_server in config.name_servers() {
+        if name_server.socket_addr.is_ipv4() == local_addr.is_ipv4() {
+            let mut name_server = name_server.clone();
+            name_server.bind_addr = Some(SocketAddr::new(local_addr, 0));
+            bound.add_name_server(name_server);
+        }
+    }
+    if bound.name_servers().is_empty() {
+        return Err(BindError::NoNameServers { addr: local_addr });
+    }
+    Ok(bound)
+}
+
+/// Tokio-based hickory resolver sending lookups to fixed name servers,
+/// produced by [`with_nameservers`], [`with_cloudflare`] or [`with_google`],
+/// or by `with_dot` with the `dot` feature.
+///
+/// Lookups time out after 5 seconds and are attempted twice unless set
+/// otherwise. [`NameServers::build`] produces a resolver to construct
+/// clients with:
+///
+/// ```
+/// use detsys_srv::{resolver::hickory, SrvClient};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let resolver = hickory::with_nameservers(&["10.0.0.2:53".parse().unwrap()])
+///     .timeout(std::time::Duration::from_secs(1))
+///     .attempts(3)
+///     .build();
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NameServers {
+    config: ResolverConfig,
+    options: ResolverOpts,
+}
+
+/// Creates a resolver sending lookups to the name servers at `addrs`, over
+/// UDP and falling back to TCP for truncated answers, without search domains.
+pub fn with_nameservers(addrs: &[SocketAddr]) -> NameServers {
+    let name_servers = addrs
+        .iter()
+        .flat_map(|&addr| {
+            [Protocol::Udp, Protocol::Tcp].map(|protocol| NameServerConfig::new(addr, protocol))
+        })
+        .collect::<Vec<_>>();
+    NameServers::new(ResolverConfig::from_parts(
+        None,
+        Vec::new(),
+        NameServerConfigGroup::from(name_servers),
+    ))
+}
+
+/// Creates a resolver sending lookups to Cloudflare's public name servers
+/// (1.1.1.1 and 1.0.0.1).
+pub fn with_cloudflare() -> NameServers {
+    NameServers::new(ResolverConfig::cloudflare())
+}
+
+/// Creates a resolver sending lookups to Google's public name servers
+/// (8.8.8.8 and 8.8.4.4).
+pub fn with_google() -> NameServers {
+    NameServers::new(ResolverConfig::google())
+}
+
+/// Creates a resolver sending lookups to the name servers at `addrs` over
+/// DNS-over-TLS, without search domains. The name servers' certificates must
+/// be valid for `tls_name` and chain to one of the Mozilla root certificates.
+///
+/// Lookups whose TLS handshake fails produce an error like any other failed
+/// lookup, which an execution reports as [`Error::Lookup`](crate::Error::Lookup)
+/// before trying the client's fallback.
+#[cfg(feature = "dot")]
+pub fn with_dot(addrs: &[SocketAddr], tls_name: &str) -> NameServers {
+    let name_servers = addrs
+        .iter()
+        .map(|&addr| {
+            let mut name_server = NameServerConfig::new(addr, Protocol::Tls);
+            name_server.tls_dns_name = Some(tls_name.to_owned());
+            name_server
+        })
+        .collect::<Vec<_>>();
+    NameServers::new(ResolverConfig::from_parts(
+        None,
+        Vec::new(),
+        NameServerConfigGroup::from(name_servers),
+    ))
+}
+
+/// Creates a resolver sending lookups to the name servers at `servers` over
+/// DNS-over-TLS, as with [`with_dot`] and the default options:
+///
+/// ```
+/// use detsys_srv::{resolver::hickory, SrvClient};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let resolver = hickory::dot_resolver(&["1.1.1.1:853".parse().unwrap()], "cloudflare-dns.com");
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// # }
+/// ```
+#[cfg(feature = "dot")]
+pub fn dot_resolver(servers: &[SocketAddr], tls_name: &str) -> DefaultResolver {
+    with_dot(servers, tls_name).build()
+}
+
+impl NameServers {
+    fn new(config: ResolverConfig) -> Self {
+        Self {
+            config,
+            options: ResolverOpts::default(),
+        }
+    }
+
+    /// Sets how long to wait for an answer to each lookup attempt.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a failed lookup is attempted.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.options.attempts = attempts;
+        self
+    }
+
+    /// Sets whether answers are validated with DNSSEC, rejecting those that
+    /// fail validation.
+    ///
+    /// Validation is only available with the `dnssec` feature, which enables
+    /// hickory's own; without it, hickory warns and doesn't validate. Lookups
+    /// of a validating resolver report whether their answer was
+    /// [authenticated](LookupMeta::authenticated), e.g. for
+    /// [`SrvClient::require_authenticated_data`](crate::SrvClient::require_authenticated_data).
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.options.validate = validate;
+        self
+    }
+
+    /// Gets the configuration of the name servers lookups are sent to.
+    pub fn config(&self) -> &ResolverConfig {
+        &self.config
+    }
+
+    /// Builds the resolver.
+    pub fn build(self) -> DefaultResolver {
+        let builder = Resolver::builder_with_config(self.config, Default::default());
+        DefaultResolver(builder.with_options(self.options).build())
+    }
+}
+
+/// Gets the provenance of lookups sent to the name servers in `config`: the
+/// name server if they all share one address, whatever protocols it's reached
+/// over, and the protocol if they all share one.
+pub(crate) fn config_meta(config: &ResolverConfig) -> LookupMeta {
+    let name_servers = config.name_servers();
+    let mut meta = LookupMeta::default();
+    if let Some((first, rest)) = name_servers.split_first() {
+        if rest
+            .iter()
+            .all(|other| other.socket_addr == first.socket_addr)
+        {
+            meta = meta.with_server(first.socket_addr);
+        }
+        if rest.iter().all(|other| other.protocol == first.protocol) {
+            meta = meta.with_protocol(DnsProtocol::from_name(&first.protocol.to_string()));
+        }
+    }
+    meta
+}
+
+/// Tokio-based hickory resolver configured from the system's DNS
+/// configuration, making clients constructible with [`SrvClient::new`].
+///
+/// If the system configuration can't be read, hickory's default
+/// configuration is used instead.
+///
+/// [`SrvClient::new`]: crate::SrvClient::new
+#[derive(Debug, Clone)]
+pub struct DefaultResolver(TokioResolver);
+
+/// Options of the resolver of clients created with
+/// [`SrvClient::tokio_with`](crate::SrvClient::tokio_with), applied to the
+/// system's DNS configuration.
+#[derive(Debug, Clone, Default)]
+pub struct TokioOptions {
+    local_addr: Option<IpAddr>,
+}
+
+impl TokioOptions {
+    /// Creates options leaving the system's DNS configuration as it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends lookups from `local_addr`, as with [`bind_config`].
+    pub fn local_addr(self, local_addr: IpAddr) -> Self {
+        Self {
+            local_addr: Some(local_addr),
+        }
+    }
+
+    /// Gets the local address lookups are sent from, if set.
+    pub fn current_local_addr(&self) -> Option<IpAddr> {
+        self.local_addr
+    }
+}
+
+impl DefaultResolver {
+    /// Creates a resolver from the system's DNS configuration like the
+    /// default one, with `options` applied.
+    ///
+    /// Fails if `options` can't be applied, e.g. if its local address can't
+    /// be bound.
+    pub fn with_options(options: &TokioOptions) -> Result<Self, BindError> {
+        let (config, resolver_options) =
+            hickory_resolver::system_conf::read_system_conf().unwrap_or_default();
+        let config = match options.local_addr {
+            Some(local_addr) => bind_config(&config, local_addr)?,
+            None => config,
+        };
+        let builder = Resolver::builder_with_config(config, Default::default());
+        Ok(Self(builder.with_options(resolver_options).build()))
+    }
+
+    /// Gets the underlying hickory resolver.
+    pub fn into_inner(self) -> TokioResolver {
+        self.0
+    }
+}
+
+impl Default for DefaultResolver {
+    fn default() -> Self {
+        let resolver = match Resolver::builder_tokio() {
+            Ok(builder) => builder.build(),
+            Err(_) => {
+                Resolver::builder_with_config(ResolverConfig::default(), Default::default()).build()
+            }
+        };
+        Self(resolver)
+    }
+}
+
+impl From<TokioResolver> for DefaultResolver {
+    fn from(resolver: TokioResolver) -> Self {
+        Self(resolver)
+    }
+}
+
+impl Deref for DefaultResolver {
+    type Target = TokioResolver;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl SrvResolver for DefaultResolver {
+    type Record = SRV;
+    type Error = ResolveError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        self.0.get_srv_records_unordered(srv).await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        self.0.get_srv_records_with_meta(srv).await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        self.0.get_srv_records_with_glue(srv).await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.0.get_ip_addrs(host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.0.get_tlsa_records(name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.0.get_https_records(name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.0.get_https_records_with_meta(name).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        TokioResolver::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        TokioResolver::classify_error(error)
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.0.get_ptr_records(name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.0.get_txt_records(name).await
+    }
+}
+
+/// Converts hickory's SVCB rdata into a [`SvcbRecord`], keeping the
+/// parameters a client understands.
+pub(super) fn svcb_record(svcb: &SVCB) -> SvcbRecord {
+    let mut record = SvcbRecord::new(svcb.svc_priority(), svcb.target_name());
+    let mut hints = Vec::new();
+    for (_, value) in svcb.svc_params() {
+        match value {
+            SvcParamValue::Port(port) => record = record.with_port(*port),
+            SvcParamValue::Alpn(alpn) => record = record.with_alpn(&alpn.0),
+            SvcParamValue::Ipv4Hint(hint) => {
+                hints.extend(hint.0.iter().map(|a| IpAddr::V4(a.0)));
+            }
+            SvcParamValue::Ipv6Hint(hint) => {
+                hints.extend(hint.0.iter().map(|aaaa| IpAddr::V6(aaaa.0)));
+            }
+            _ => {}
+        }
+    }
+    record.with_hints(hints)
+}
+
+/// Gets the provenance of a lookup from `resolver`, with whether its answer
+/// was authenticated if the resolver validates answers with DNSSEC: only if
+/// every record of `record_type` in it was proven secure.
+#[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
+fn lookup_meta<P: ConnectionProvider>(
+    resolver: &Resolver<P>,
+    lookup: &Lookup,
+    record_type: RecordType,
+) -> LookupMeta {
+    let meta = config_meta(resolver.config());
+    #[cfg(feature = "dnssec")]
+    if resolver.options().validate {
+        let authenticated = lookup
+            .record_iter()
+            .filter(|record| record.record_type() == record_type)
+            .all(|record| record.proof().is_secure());
+        return meta.with_authenticated(authenticated);
+    }
+    meta
+}
+
+/// Classifies hickory's errors by the response code of the answer, if there
+/// was one.
+pub(super) fn classify(error: &ResolveError) -> LookupErrorKind {
+    let Some(proto) = error.proto() else {
+        return LookupErrorKind::Unknown;
+    };
+    match proto.kind() {
+        ProtoErrorKind::NoRecordsFound { response_code, .. } => match *response_code {
+            ResponseCode::NXDomain => LookupErrorKind::NxDomain,
+            ResponseCode::NoError => LookupErrorKind::NoData,
+            _ => LookupErrorKind::ServerFailure,
+        },
+        ProtoErrorKind::Timeout => LookupErrorKind::Timeout,
+        ProtoErrorKind::NoConnections | ProtoErrorKind::Busy | ProtoErrorKind::Io(_) => {
+            LookupErrorKind::Unreachable
+        }
+        _ => LookupErrorKind::Unknown,
+    }
+}
 
 #[async_trait]
 impl<P> SrvResolver for Resolver<P>
@@ -24,6 +479,130 @@ where
         let valid_until = lookup.as_lookup().valid_until();
         Ok((lookup.into_iter().collect(), valid_until))
     }
+
+    /// Reports the name server and protocol when they're the same for every
+    /// lookup, since hickory doesn't say which name server answered or
+    /// whether its cache did, and whether the answer was authenticated if
+    /// the resolver validates answers with DNSSEC.
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    /// Reports the A/AAAA records hickory kept from the answer's additional
+    /// section as glue. Hickory only keeps those it considers part of the
+    /// answer, so glue may be missing for some or all targets.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let lookup = self.srv_lookup(srv).await?;
+        let valid_until = lookup.as_lookup().valid_until();
+        let meta = lookup_meta(self, lookup.as_lookup(), RecordType::SRV);
+        let glue = lookup
+            .as_lookup()
+            .record_iter()
+            .filter_map(|record| {
+                let addr = match record.data() {
+                    RData::A(a) => IpAddr::V4(a.0),
+                    RData::AAAA(aaaa) => IpAddr::V6(aaaa.0),
+                    _ => return None,
+                };
+                Some((record.name().to_string(), addr))
+            })
+            .collect();
+        let mut records = lookup.into_iter().collect::<Vec<_>>();
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, valid_until, meta, glue))
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        let lookup = self.lookup_ip(host).await?;
+        let valid_until = lookup.valid_until();
+        Ok((lookup.into_iter().collect(), valid_until))
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        match self.tlsa_lookup(name).await {
+            Ok(lookup) => Ok(lookup
+                .into_iter()
+                .map(|tlsa| {
+                    TlsaRecord::new(
+                        tlsa.cert_usage().into(),
+                        tlsa.selector().into(),
+                        tlsa.matching().into(),
+                        tlsa.cert_data().to_vec(),
+                    )
+                })
+                .collect()),
+            Err(e) if e.is_no_records_found() => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        let (records, valid_until, _) = self.get_https_records_with_meta(name).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        let lookup = self.lookup(name, RecordType::HTTPS).await?;
+        let records = lookup
+            .iter()
+            .filter_map(|rdata| match rdata {
+                RData::HTTPS(https) => Some(svcb_record(&https.0)),
+                _ => None,
+            })
+            .collect();
+        let meta = lookup_meta(self, &lookup, RecordType::HTTPS);
+        Ok((records, lookup.valid_until(), meta))
+    }
+
+    /// Considers NXDOMAIN and NODATA answers as no records found, unlike
+    /// hickory itself, which also reports failures like SERVFAIL as such.
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        Self::classify_error(error).is_no_records()
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        classify(error)
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let lookup = self.lookup(name, RecordType::PTR).await?;
+        let names = lookup
+            .iter()
+            .filter_map(|rdata| match rdata {
+                RData::PTR(ptr) => Some(ptr.0.to_string()),
+                _ => None,
+            })
+            .collect();
+        Ok((names, lookup.valid_until()))
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        match self.txt_lookup(name).await {
+            Ok(lookup) => {
+                let strings = lookup
+                    .iter()
+                    .flat_map(|txt| txt.iter())
+                    .map(|data| String::from_utf8_lossy(data).into_owned())
+                    .collect();
+                Ok((strings, lookup.as_lookup().valid_until()))
+            }
+            Err(e) if e.is_no_records_found() => Ok((Vec::new(), Instant::now())),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl SrvRecord for SRV {
@@ -50,13 +629,288 @@ impl SrvRecord for SRV {
 mod tests {
     use super::*;
 
-    #[doc(hidden)]
-    pub const EXAMPLE_SRV: &str = "_detsys_ids._tcp.install.determinate.systems.";
-    #[doc(hidden)]
-    pub fn example_fallback() -> url::Url {
+    const EXAMPLE_SRV: &str = "_detsys_ids._tcp.install.determinate.systems.";
+
+    fn example_fallback() -> url::Url {
         url::Url::parse("https://install.determinate.systems.").unwrap()
     }
 
+    #[test]
+    fn bind_config_propagates() {
+        let local = IpAddr::from([127, 0, 0, 1]);
+        let bound = bind_config(&ResolverConfig::cloudflare(), local).unwrap();
+        assert!(!bound.name_servers().is_empty());
+        for name_server in bound.name_servers() {
+            assert!(name_server.socket_addr.is_ipv4());
+            assert_eq!(name_server.bind_addr, Some(SocketAddr::new(local, 0)));
+        }
+
+        let err =
+            bind_config(&ResolverConfig::cloudflare(), "192.0.2.1".parse().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("192.0.2.1"), "{err}");
+    }
+
+    #[test]
+    fn tokio_with_local_addr() {
+        let options = TokioOptions::new().local_addr("192.0.2.1".parse().unwrap());
+        let Err(err) = crate::SrvClient::<_>::tokio_with(
+            "_http._tcp.example.com",
+            example_fallback(),
+            None,
+            &options,
+        ) else {
+            panic!("binding a non-local address succeeded");
+        };
+        assert!(err.to_string().contains("192.0.2.1"), "{err}");
+
+        // The system's name servers depend on the machine, and may all be of
+        // the other family
+        let local = IpAddr::from([127, 0, 0, 1]);
+        match DefaultResolver::with_options(&TokioOptions::new().local_addr(local)) {
+            Ok(resolver) => {
+                for name_server in resolver.config().name_servers() {
+                    assert_eq!(name_server.bind_addr, Some(SocketAddr::new(local, 0)));
+                }
+            }
+            Err(err) => assert!(matches!(err, BindError::NoNameServers { .. }), "{err}"),
+        }
+    }
+
+    #[test]
+    fn classifies_errors() {
+        use hickory_resolver::proto::{op::Query, ProtoError};
+
+        let no_records = |response_code| {
+            ResolveError::from(ProtoError::nx_error(
+                Box::new(Query::query(Name::root(), RecordType::SRV)),
+                None,
+                None,
+                None,
+                response_code,
+                false,
+                None,
+            ))
+        };
+        let kind = |error: ResolveError| TokioResolver::classify_error(&error);
+
+        assert_eq!(
+            kind(no_records(ResponseCode::NXDomain)),
+            LookupErrorKind::NxDomain
+        );
+        assert_eq!(
+            kind(no_records(ResponseCode::NoError)),
+            LookupErrorKind::NoData
+        );
+        assert_eq!(
+            kind(no_records(ResponseCode::ServFail)),
+            LookupErrorKind::ServerFailure
+        );
+        assert_eq!(
+            kind(no_records(ResponseCode::Refused)),
+            LookupErrorKind::ServerFailure
+        );
+        assert_eq!(
+            kind(ProtoError::from(ProtoErrorKind::Timeout).into()),
+            LookupErrorKind::Timeout
+        );
+        assert_eq!(
+            kind(ProtoError::from(ProtoErrorKind::NoConnections).into()),
+            LookupErrorKind::Unreachable
+        );
+        assert_eq!(kind("oops".into()), LookupErrorKind::Unknown);
+
+        // Servers failing to answer don't say the name has no records
+        assert!(TokioResolver::is_no_records_found(&no_records(
+            ResponseCode::NXDomain
+        )));
+        assert!(!TokioResolver::is_no_records_found(&no_records(
+            ResponseCode::ServFail
+        )));
+    }
+
+    #[test]
+    fn svcb_record_keeps_known_params() {
+        use hickory_resolver::proto::rr::rdata::{
+            svcb::{Alpn, IpHint, Mandatory, SvcParamKey},
+            A, AAAA,
+        };
+        let svcb = SVCB::new(
+            1,
+            "svc.example.com.".parse().unwrap(),
+            vec![
+                (
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Port])),
+                ),
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".into(), "http/1.1".into()])),
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(8443)),
+                (
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![A::new(192, 0, 2, 1)])),
+                ),
+                (
+                    SvcParamKey::Ipv6Hint,
+                    SvcParamValue::Ipv6Hint(IpHint(vec![AAAA::new(
+                        0x2001, 0xdb8, 0, 0, 0, 0, 0, 1,
+                    )])),
+                ),
+            ],
+        );
+        assert_eq!(
+            svcb_record(&svcb),
+            SvcbRecord::new(1, "svc.example.com.")
+                .with_port(8443)
+                .with_alpn(["h2", "http/1.1"])
+                .with_hints(["192.0.2.1".parse().unwrap(), "2001:db8::1".parse().unwrap()])
+        );
+
+        let alias = SVCB::new(0, Name::root(), Vec::new());
+        assert!(svcb_record(&alias).is_alias());
+        assert_eq!(svcb_record(&alias).target(), ".");
+    }
+
+    #[test]
+    fn config_meta_reports_common_provenance() {
+        let name_server: SocketAddr = "192.0.2.53:53".parse().unwrap();
+        let config = ResolverConfig::from_parts(
+            None,
+            Vec::new(),
+            vec![NameServerConfig::new(name_server, Protocol::Tcp)],
+        );
+        let meta = config_meta(&config);
+        assert_eq!(meta.server(), Some(name_server));
+        assert_eq!(meta.protocol(), Some(&DnsProtocol::Tcp));
+        assert_eq!(meta.from_cache(), None);
+
+        // A name server reached over both UDP and TCP is still the one answering
+        let meta = config_meta(with_nameservers(&[name_server]).config());
+        assert_eq!(meta.server(), Some(name_server));
+        assert_eq!(meta.protocol(), None);
+
+        // Cloudflare's configuration has several name servers over UDP and TCP
+        assert_eq!(
+            config_meta(&ResolverConfig::cloudflare()),
+            LookupMeta::default()
+        );
+    }
+
+    #[test]
+    fn default_resolver_is_debug() {
+        let resolver = with_nameservers(&["192.0.2.53:53".parse().unwrap()]).build();
+        assert!(format!("{resolver:?}").starts_with("DefaultResolver("));
+        let client = crate::DefaultSrvClient::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+        assert!(format!("{client:?}").starts_with("SrvClient"));
+    }
+
+    #[test]
+    fn lookup_meta_reports_validation() {
+        use hickory_resolver::proto::{op::Query, rr::rdata::A};
+
+        let name = Name::from_ascii("svc.example.com.").unwrap();
+        let query = Query::query(name, RecordType::A);
+        let lookup = Lookup::from_rdata(query, RData::A(A::new(192, 0, 2, 1)));
+        let name_servers = with_nameservers(&["10.0.0.2:53".parse().unwrap()]);
+        let meta = lookup_meta(&name_servers.clone().build(), &lookup, RecordType::A);
+        assert_eq!(meta.authenticated(), None);
+
+        // Validating resolvers report records that weren't proven secure as
+        // not authenticated
+        let validating = name_servers.validate(true).build();
+        let meta = lookup_meta(&validating, &lookup, RecordType::A);
+        assert_eq!(
+            meta.authenticated(),
+            cfg!(feature = "dnssec").then_some(false)
+        );
+    }
+
+    #[test]
+    fn explicit_name_servers() {
+        let addrs: [SocketAddr; 2] = [
+            "10.0.0.2:53".parse().unwrap(),
+            "[fd00::2]:5353".parse().unwrap(),
+        ];
+        let name_servers = with_nameservers(&addrs)
+            .timeout(Duration::from_secs(1))
+            .attempts(4)
+            .validate(true);
+        let configured = name_servers
+            .config()
+            .name_servers()
+            .iter()
+            .map(|name_server| (name_server.socket_addr, name_server.protocol))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            configured,
+            [
+                (addrs[0], Protocol::Udp),
+                (addrs[0], Protocol::Tcp),
+                (addrs[1], Protocol::Udp),
+                (addrs[1], Protocol::Tcp)
+            ]
+        );
+        assert!(name_servers.config().search().is_empty());
+
+        let resolver = name_servers.build();
+        assert_eq!(resolver.options().timeout, Duration::from_secs(1));
+        assert_eq!(resolver.options().attempts, 4);
+        assert!(resolver.options().validate);
+
+        let cloudflare = with_cloudflare().config().name_servers().to_vec();
+        assert!(cloudflare
+            .iter()
+            .any(|name_server| name_server.socket_addr == "1.1.1.1:53".parse().unwrap()));
+        let google = with_google().config().name_servers().to_vec();
+        assert!(google
+            .iter()
+            .any(|name_server| name_server.socket_addr == "8.8.8.8:53".parse().unwrap()));
+    }
+
+    #[cfg(feature = "dot")]
+    #[tokio::test]
+    async fn dot_handshake_failure() {
+        // A "name server" closing connections without a TLS handshake
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                drop(stream);
+            }
+        });
+
+        let name_servers = with_dot(&[addr], "dns.example.com");
+        let configured = name_servers
+            .config()
+            .name_servers()
+            .iter()
+            .map(|name_server| {
+                let tls_name = name_server.tls_dns_name.as_deref();
+                (name_server.socket_addr, name_server.protocol, tls_name)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(configured, [(addr, Protocol::Tls, Some("dns.example.com"))]);
+
+        let resolver = name_servers
+            .timeout(Duration::from_secs(1))
+            .attempts(1)
+            .build();
+        let client = crate::SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            example_fallback(),
+            None,
+            resolver,
+        );
+        let err = client.get_fresh_uri_candidates().await.unwrap_err();
+        assert!(matches!(err, crate::Error::Lookup(_)), "{err}");
+    }
+
     #[tokio::test]
     async fn srv_lookup() -> Result<(), ResolveError> {
         let (records, _) = Resolver::builder_tokio()?
@@ -131,4 +985,10 @@ mod tests {
             .await
             .unwrap_err();
     }
+
+    #[tokio::test]
+    async fn default_client() {
+        let client = crate::DefaultSrvClient::new("  @#*^[_hsd flt.com", example_fallback(), None);
+        client.get_fresh_uri_candidates().await.unwrap_err();
+    }
 }
@@ -0,0 +1,316 @@
+//! SRV resolver reporting the latency and outcome of each lookup.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use std::{
+    fmt,
+    future::Future,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Type of records a lookup observed by a [`LookupObserver`] was for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LookupType {
+    /// SRV records of a service
+    Srv,
+    /// Addresses (A and AAAA records) of a host
+    IpAddrs,
+    /// TLSA records of a target
+    Tlsa,
+    /// HTTPS records of a name
+    Https,
+    /// PTR records of a name, e.g. to browse DNS-SD instances
+    Ptr,
+    /// TXT records of a name
+    Txt,
+}
+
+impl LookupType {
+    /// Gets the name of the type, e.g. `SRV`, for use as a metric label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Srv => "SRV",
+            Self::IpAddrs => "A/AAAA",
+            Self::Tlsa => "TLSA",
+            Self::Https => "HTTPS",
+            Self::Ptr => "PTR",
+            Self::Txt => "TXT",
+        }
+    }
+}
+
+/// Outcome of one lookup of an [`InstrumentedResolver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupOutcome {
+    lookup_type: LookupType,
+    duration: Duration,
+    result: Result<usize, LookupErrorKind>,
+}
+
+impl LookupOutcome {
+    /// Type of records looked up.
+    pub fn lookup_type(&self) -> LookupType {
+        self.lookup_type
+    }
+
+    /// Time the lookup took.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Determines if the lookup succeeded, even if it found no records.
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Number of records found, if the lookup succeeded.
+    pub fn records(&self) -> Option<usize> {
+        self.result.ok()
+    }
+
+    /// Kind of failure, as [classified](SrvResolver::classify_error) by the
+    /// inner resolver, if the lookup failed.
+    pub fn error_kind(&self) -> Option<LookupErrorKind> {
+        self.result.err()
+    }
+}
+
+/// Observer of the lookups of an [`InstrumentedResolver`], e.g. recording
+/// them as metrics.
+///
+/// Closures taking the looked up name and the outcome are observers.
+pub trait LookupObserver: Send + Sync {
+    /// Observes a finished lookup of `name`.
+    fn on_lookup(&self, name: &str, outcome: &LookupOutcome);
+}
+
+impl<F> LookupObserver for F
+where
+    F: Fn(&str, &LookupOutcome) + Send + Sync,
+{
+    fn on_lookup(&self, name: &str, outcome: &LookupOutcome) {
+        self(name, outcome)
+    }
+}
+
+/// SRV resolver passing lookups to an inner resolver as is, reporting how
+/// long each took and whether it succeeded to a [`LookupObserver`], so that
+/// DNS latency and failures can be told apart from those of requests to
+/// targets.
+///
+/// ```
+/// # use detsys_srv::resolver::{InstrumentedResolver, StaticResolver};
+/// # use std::time::Duration;
+/// # let resolver = StaticResolver::new([("a.example.com", 443, 1, 1)], Duration::from_secs(60));
+/// let resolver = InstrumentedResolver::new(resolver, |name: &str, outcome: &_| {
+///     tracing::info!(name, ?outcome, "SRV lookup");
+/// });
+/// ```
+#[derive(Clone)]
+pub struct InstrumentedResolver<R> {
+    inner: R,
+    observer: Arc<dyn LookupObserver>,
+}
+
+impl<R: fmt::Debug> fmt::Debug for InstrumentedResolver<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentedResolver")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> InstrumentedResolver<R> {
+    /// Creates a resolver reporting the lookups of `inner` to `observer`.
+    pub fn new(inner: R, observer: impl LookupObserver + 'static) -> Self {
+        Self {
+            inner,
+            observer: Arc::new(observer),
+        }
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner resolver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: SrvResolver> InstrumentedResolver<R> {
+    /// Runs a lookup of the inner resolver, reporting its outcome with the
+    /// number of records `count` finds in its answer.
+    async fn observe<T>(
+        &self,
+        name: &str,
+        lookup_type: LookupType,
+        lookup: impl Future<Output = Result<T, R::Error>>,
+        count: impl FnOnce(&T) -> usize,
+    ) -> Result<T, R::Error> {
+        let start = Instant::now();
+        let result = lookup.await;
+        let outcome = LookupOutcome {
+            lookup_type,
+            duration: start.elapsed(),
+            result: match &result {
+                Ok(answer) => Ok(count(answer)),
+                Err(e) => Err(R::classify_error(e)),
+            },
+        };
+        self.observer.on_lookup(name, &outcome);
+        result
+    }
+}
+
+#[async_trait]
+impl<R> SrvResolver for InstrumentedResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send,
+    R::Error: Send,
+{
+    type Record = R::Record;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let lookup = self.inner.get_srv_records_unordered(srv);
+        self.observe(srv, LookupType::Srv, lookup, |(records, _)| records.len())
+            .await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let lookup = self.inner.get_srv_records_with_meta(srv);
+        self.observe(srv, LookupType::Srv, lookup, |(records, ..)| records.len())
+            .await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let lookup = self.inner.get_srv_records_with_glue(srv);
+        self.observe(srv, LookupType::Srv, lookup, |(records, ..)| records.len())
+            .await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        let lookup = self.inner.get_ip_addrs(host);
+        self.observe(host, LookupType::IpAddrs, lookup, |(addrs, _)| addrs.len())
+            .await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        let lookup = self.inner.get_tlsa_records(name);
+        self.observe(name, LookupType::Tlsa, lookup, Vec::len).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        let lookup = self.inner.get_https_records(name);
+        self.observe(name, LookupType::Https, lookup, |(records, _)| {
+            records.len()
+        })
+        .await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        let lookup = self.inner.get_https_records_with_meta(name);
+        self.observe(name, LookupType::Https, lookup, |(records, ..)| {
+            records.len()
+        })
+        .await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let lookup = self.inner.get_ptr_records(name);
+        self.observe(name, LookupType::Ptr, lookup, |(records, _)| records.len())
+            .await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        let lookup = self.inner.get_txt_records(name);
+        self.observe(name, LookupType::Txt, lookup, |(records, _)| records.len())
+            .await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resolver::{MockResolver, MockResponse},
+        SrvClient,
+    };
+    use std::sync::Mutex;
+
+    #[test]
+    fn observes_lookups() {
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+        let observed = outcomes.clone();
+        let mock = MockResolver::new([
+            MockResponse::records(
+                [
+                    ("a.example.com", 443, 1, 100),
+                    ("b.example.com", 443, 2, 100),
+                ],
+                Duration::ZERO,
+            ),
+            MockResponse::error("SERVFAIL"),
+        ]);
+        let resolver =
+            InstrumentedResolver::new(mock, move |name: &str, outcome: &LookupOutcome| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((name.to_owned(), outcome.clone()));
+            });
+        let client = SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+
+        futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+        futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap_err();
+
+        let outcomes = outcomes.lock().unwrap();
+        assert_eq!(outcomes.len(), 2);
+        let (name, success) = &outcomes[0];
+        assert_eq!(name, "_http._tcp.example.com");
+        assert_eq!(success.lookup_type(), LookupType::Srv);
+        assert!(success.is_success());
+        assert_eq!(success.records(), Some(2));
+        assert_eq!(success.error_kind(), None);
+
+        let (_, failure) = &outcomes[1];
+        assert!(!failure.is_success());
+        assert_eq!(failure.records(), None);
+        assert_eq!(failure.error_kind(), Some(LookupErrorKind::Unknown));
+    }
+}
@@ -0,0 +1,44 @@
+//! Classification of failed lookups.
+
+/// Why a lookup failed, as far as the resolver that performed it can tell,
+/// produced by [`SrvResolver::classify_error`](super::SrvResolver::classify_error).
+///
+/// Failures that say something about the name ([`NxDomain`] and [`NoData`])
+/// are answers that may be cached, while the others are failures of DNS
+/// infrastructure that are worth alerting on.
+///
+/// [`NxDomain`]: LookupErrorKind::NxDomain
+/// [`NoData`]: LookupErrorKind::NoData
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LookupErrorKind {
+    /// The name doesn't exist (NXDOMAIN).
+    NxDomain,
+    /// The name exists but has no records of the type looked up (NODATA).
+    NoData,
+    /// The name server failed or refused to answer (e.g. SERVFAIL or
+    /// REFUSED).
+    ServerFailure,
+    /// No name server answered in time.
+    Timeout,
+    /// No name server could be reached.
+    Unreachable,
+    /// The resolver can't tell.
+    Unknown,
+}
+
+impl LookupErrorKind {
+    /// Determines if the failure says the name has no records, rather than
+    /// that the lookup itself failed.
+    pub fn is_no_records(self) -> bool {
+        matches!(self, Self::NxDomain | Self::NoData)
+    }
+
+    /// Determines if the failure is one of DNS infrastructure, i.e. a server
+    /// failure, a timeout, or an unreachable name server.
+    pub fn is_infrastructure(self) -> bool {
+        matches!(
+            self,
+            Self::ServerFailure | Self::Timeout | Self::Unreachable
+        )
+    }
+}
@@ -0,0 +1,365 @@
+//! SRV resolver sending multicast DNS queries on the local link.
+
+use super::{
+    wire::{self, Header, MAX_MESSAGE_LEN},
+    LookupMeta, SrvResolver,
+};
+use crate::{OwnedSrvRecord, SrvRecord};
+use async_trait::async_trait;
+use rand::Rng;
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+/// Address of the IPv4 mDNS group (RFC 6762).
+const MDNS_GROUP: SocketAddr =
+    SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+/// Errors produced by a [`MdnsResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum MdnsResolverError {
+    /// Produced when a name can't be encoded in a query
+    #[error("invalid name {name:?}: {reason}")]
+    InvalidName {
+        /// Name that was looked up
+        name: String,
+        /// Why the name is invalid
+        reason: &'static str,
+    },
+    /// Produced when the query can't be sent or its responses received
+    #[error("failed to query the local link")]
+    Io(#[source] io::Error),
+    /// Produced when no responder on the local link advertises the name
+    #[error("no responders for {name}")]
+    NoResponders {
+        /// Name that was looked up
+        name: String,
+    },
+}
+
+/// SRV resolver discovering services advertised on the local link with
+/// multicast DNS (e.g. by Avahi or Bonjour), for LAN deployments whose
+/// `.local` names unicast DNS can't see.
+///
+/// Each lookup sends a one-shot query (RFC 6762) to the mDNS group and
+/// collects the SRV records of every response that arrives within the
+/// [window](MdnsResolver::window), so lookups take the whole window. Lookups
+/// are valid for the smallest TTL of their records, capped at
+/// [`MdnsResolver::max_ttl`] (10 seconds unless set) since responders come
+/// and go. Lookups no responder answers fail with
+/// [`MdnsResolverError::NoResponders`], which counts as finding no records,
+/// so that clients use their fallback. With the `tokio` feature, lookups
+/// wait on responses with tokio's sockets, so they need a tokio runtime;
+/// otherwise they block the calling thread.
+#[derive(Debug, Clone)]
+pub struct MdnsResolver {
+    group: SocketAddr,
+    window: Duration,
+    max_ttl: Duration,
+}
+
+impl Default for MdnsResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MdnsResolver {
+    /// Creates a resolver querying the IPv4 mDNS group, collecting responses
+    /// for a second.
+    pub fn new() -> Self {
+        Self {
+            group: MDNS_GROUP,
+            window: Duration::from_secs(1),
+            max_ttl: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the address queries are sent to, instead of the IPv4 mDNS group.
+    pub fn group(self, group: SocketAddr) -> Self {
+        Self { group, ..self }
+    }
+
+    /// Sets how long responses to a query are collected for.
+    pub fn window(self, window: Duration) -> Self {
+        Self { window, ..self }
+    }
+
+    /// Sets how long lookups are valid for at most.
+    pub fn max_ttl(self, max_ttl: Duration) -> Self {
+        Self { max_ttl, ..self }
+    }
+
+    /// Queries the local link for the SRV records of `srv`, producing those
+    /// of every responder along with their smallest TTL.
+    async fn lookup(&self, srv: &str) -> Result<(Vec<OwnedSrvRecord>, u32), MdnsResolverError> {
+        let id = rand::rng().random();
+        // Responses to queries from ports other than 5353 echo their ID
+        let query = wire::srv_query_with_flags(id, 0, srv).map_err(|reason| {
+            MdnsResolverError::InvalidName {
+                name: srv.to_owned(),
+                reason,
+            }
+        })?;
+        let socket = Socket::bind().await.map_err(MdnsResolverError::Io)?;
+        socket
+            .send_to(&query, self.group)
+            .await
+            .map_err(MdnsResolverError::Io)?;
+
+        let deadline = Instant::now() + self.window;
+        let mut records = Vec::<OwnedSrvRecord>::new();
+        let mut min_ttl = None::<u32>;
+        let mut responders = 0;
+        let mut response = vec![0; MAX_MESSAGE_LEN];
+        while let Some((len, responder)) = socket
+            .recv_before(&mut response, deadline)
+            .await
+            .map_err(MdnsResolverError::Io)?
+        {
+            let response = &response[..len];
+            match Header::parse(response) {
+                // Responses with errors are to be ignored (RFC 6762)
+                Ok(header)
+                    if header.response && header.id == id && header.rcode == Header::NO_ERROR => {}
+                _ => continue,
+            }
+            let (found, ttl) = match wire::parse_srv_answers(response, srv) {
+                Ok(found) => found,
+                Err(reason) => {
+                    tracing::trace!(%responder, reason, "Skipping malformed mDNS response");
+                    continue;
+                }
+            };
+            if found.is_empty() {
+                continue;
+            }
+            responders += 1;
+            min_ttl = Some(min_ttl.map_or(ttl, |min| min.min(ttl)));
+            for record in found {
+                let duplicate = records.iter().any(|existing| {
+                    existing.target() == record.target() && existing.port() == record.port()
+                });
+                if !duplicate {
+                    records.push(record);
+                }
+            }
+        }
+        tracing::trace!(
+            srv,
+            responders,
+            records = records.len(),
+            "Collected mDNS responses"
+        );
+        match min_ttl {
+            Some(ttl) => Ok((records, ttl)),
+            None => Err(MdnsResolverError::NoResponders {
+                name: srv.to_owned(),
+            }),
+        }
+    }
+
+    /// Gets how long a lookup whose smallest TTL is `ttl` is valid for.
+    fn valid_for(&self, ttl: u32) -> Duration {
+        Duration::from_secs(ttl.into()).min(self.max_ttl)
+    }
+}
+
+#[async_trait]
+impl SrvResolver for MdnsResolver {
+    type Record = OwnedSrvRecord;
+    type Error = MdnsResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, ttl) = self.lookup(srv).await?;
+        Ok((records, Instant::now() + self.valid_for(ttl)))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (mut records, valid_until) = self.get_srv_records_unordered(srv).await?;
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((
+            records,
+            valid_until,
+            LookupMeta::default().with_server(self.group),
+        ))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, MdnsResolverError::NoResponders { .. })
+    }
+}
+
+/// Socket queries are sent from, which waits on responses with tokio's
+/// sockets when the `tokio` feature is enabled, blocking the calling thread
+/// otherwise.
+struct Socket {
+    #[cfg(feature = "tokio")]
+    inner: tokio::net::UdpSocket,
+    #[cfg(not(feature = "tokio"))]
+    inner: std::net::UdpSocket,
+}
+
+#[cfg(feature = "tokio")]
+impl Socket {
+    async fn bind() -> io::Result<Self> {
+        let inner = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+        inner.set_multicast_ttl_v4(255)?;
+        Ok(Self { inner })
+    }
+
+    async fn send_to(&self, message: &[u8], addr: SocketAddr) -> io::Result<()> {
+        self.inner.send_to(message, addr).await.map(drop)
+    }
+
+    /// Receives a message, producing `None` if none arrives before `deadline`.
+    async fn recv_before(
+        &self,
+        buf: &mut [u8],
+        deadline: Instant,
+    ) -> io::Result<Option<(usize, SocketAddr)>> {
+        let deadline = tokio::time::Instant::from_std(deadline);
+        match tokio::time::timeout_at(deadline, self.inner.recv_from(buf)).await {
+            Ok(received) => received.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Socket {
+    async fn bind() -> io::Result<Self> {
+        let inner = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        inner.set_multicast_ttl_v4(255)?;
+        Ok(Self { inner })
+    }
+
+    async fn send_to(&self, message: &[u8], addr: SocketAddr) -> io::Result<()> {
+        self.inner.send_to(message, addr).map(drop)
+    }
+
+    /// Receives a message, producing `None` if none arrives before `deadline`.
+    async fn recv_before(
+        &self,
+        buf: &mut [u8],
+        deadline: Instant,
+    ) -> io::Result<Option<(usize, SocketAddr)>> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        self.inner.set_read_timeout(Some(remaining))?;
+        match self.inner.recv_from(buf) {
+            Ok(received) => Ok(Some(received)),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{future::Future, net::UdpSocket};
+
+    /// Runs a lookup to completion, in a tokio runtime if lookups need one.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        #[cfg(feature = "tokio")]
+        return tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future);
+        #[cfg(not(feature = "tokio"))]
+        futures::executor::block_on(future)
+    }
+
+    /// Responds to a single query with a response for each of the given
+    /// targets and TTLs, after a response that doesn't match the query.
+    fn respond(responses: Vec<(&'static str, u32)>) -> SocketAddr {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut buf = [0; 512];
+            let (len, peer) = socket.recv_from(&mut buf).unwrap();
+            let query = buf[..len].to_vec();
+            assert_eq!(query[2..4], [0, 0]);
+            let mut stray = query.clone();
+            stray[0] ^= 0xff;
+            stray[2] = 0x84;
+            socket.send_to(&stray, peer).unwrap();
+            for (target, ttl) in responses {
+                let mut message = query.clone();
+                // Authoritative answer, with the cache-flush bit set
+                message[2] = 0x84;
+                message[7] = 1;
+                message.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x21, 0x80, 0x01]);
+                message.extend_from_slice(&ttl.to_be_bytes());
+                let len = 6 + target.len() + 8;
+                message.extend_from_slice(&(len as u16).to_be_bytes());
+                message.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x1f, 0x90]);
+                message.push(target.len() as u8);
+                message.extend_from_slice(target.as_bytes());
+                message.extend_from_slice(b"\x05local\x00");
+                socket.send_to(&message, peer).unwrap();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn collects_responses() {
+        let group = respond(vec![("a", 120), ("b", 5), ("a", 120)]);
+        let resolver = MdnsResolver::new()
+            .group(group)
+            .window(Duration::from_millis(200));
+        let before = Instant::now();
+        let (records, valid_until, meta) =
+            block_on(resolver.get_srv_records_with_meta("_http._tcp.myapp.local")).unwrap();
+        let mut targets = records
+            .iter()
+            .map(|record| (record.target(), record.port()))
+            .collect::<Vec<_>>();
+        targets.sort();
+        assert_eq!(targets, [("a.local.", 8080), ("b.local.", 8080)]);
+        assert!(before.elapsed() >= Duration::from_millis(200));
+        assert!(valid_until >= before + Duration::from_secs(5));
+        assert!(valid_until < before + Duration::from_secs(10));
+        assert_eq!(meta.server(), Some(group));
+
+        let group = respond(vec![("a", 120)]);
+        let resolver = resolver.group(group);
+        let (_, valid_until) =
+            block_on(resolver.get_srv_records("_http._tcp.myapp.local")).unwrap();
+        assert!(valid_until <= Instant::now() + Duration::from_secs(10));
+    }
+
+    #[test]
+    fn no_responders() {
+        let group = respond(vec![]);
+        let resolver = MdnsResolver::new()
+            .group(group)
+            .window(Duration::from_millis(50));
+        let err = block_on(resolver.get_srv_records("_http._tcp.myapp.local")).unwrap_err();
+        assert!(
+            matches!(err, MdnsResolverError::NoResponders { .. }),
+            "{err:?}"
+        );
+        assert!(MdnsResolver::is_no_records_found(&err));
+    }
+}
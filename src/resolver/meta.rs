@@ -0,0 +1,136 @@
+//! Provenance of lookups.
+
+use crate::IpRange;
+use std::{fmt, net::SocketAddr};
+
+/// Where the answer to a lookup came from, as far as the resolver that
+/// performed it can tell, produced by
+/// [`SrvResolver::get_srv_records_with_meta`](super::SrvResolver::get_srv_records_with_meta).
+///
+/// Each piece of provenance is `None` if the resolver can't tell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LookupMeta {
+    server: Option<SocketAddr>,
+    protocol: Option<DnsProtocol>,
+    from_cache: Option<bool>,
+    authenticated: Option<bool>,
+    client_subnet: Option<IpRange>,
+}
+
+impl LookupMeta {
+    /// Sets the name server that answered the lookup.
+    pub fn with_server(self, server: SocketAddr) -> Self {
+        Self {
+            server: Some(server),
+            ..self
+        }
+    }
+
+    /// Sets the protocol the lookup was answered over.
+    pub fn with_protocol(self, protocol: DnsProtocol) -> Self {
+        Self {
+            protocol: Some(protocol),
+            ..self
+        }
+    }
+
+    /// Sets whether the answer was served from the resolver's own cache.
+    pub fn with_from_cache(self, from_cache: bool) -> Self {
+        Self {
+            from_cache: Some(from_cache),
+            ..self
+        }
+    }
+
+    /// Sets whether the answer was authenticated with DNSSEC, either by the
+    /// resolver itself or by a validating name server it trusts setting the
+    /// AD bit.
+    pub fn with_authenticated(self, authenticated: bool) -> Self {
+        Self {
+            authenticated: Some(authenticated),
+            ..self
+        }
+    }
+
+    /// Sets the client subnet sent with the lookup in its EDNS Client Subnet
+    /// option.
+    pub fn with_client_subnet(self, subnet: IpRange) -> Self {
+        Self {
+            client_subnet: Some(subnet),
+            ..self
+        }
+    }
+
+    /// Gets the name server that answered the lookup.
+    pub fn server(&self) -> Option<SocketAddr> {
+        self.server
+    }
+
+    /// Gets the protocol the lookup was answered over.
+    pub fn protocol(&self) -> Option<&DnsProtocol> {
+        self.protocol.as_ref()
+    }
+
+    /// Gets whether the answer was served from the resolver's own cache.
+    pub fn from_cache(&self) -> Option<bool> {
+        self.from_cache
+    }
+
+    /// Gets whether the answer was authenticated with DNSSEC.
+    pub fn authenticated(&self) -> Option<bool> {
+        self.authenticated
+    }
+
+    /// Gets the client subnet sent with the lookup, if it carried one.
+    pub fn client_subnet(&self) -> Option<IpRange> {
+        self.client_subnet
+    }
+}
+
+/// Protocols DNS lookups are performed over.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DnsProtocol {
+    /// Plain DNS over UDP.
+    Udp,
+    /// Plain DNS over TCP.
+    Tcp,
+    /// DNS over TLS (DoT).
+    Tls,
+    /// DNS over HTTPS (DoH).
+    Https,
+    /// DNS over QUIC (DoQ).
+    Quic,
+    /// DNS over HTTP/3.
+    H3,
+    /// Any other protocol, by name.
+    Other(String),
+}
+
+impl DnsProtocol {
+    /// Gets the protocol with a name like `udp` or `https`.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "udp" => Self::Udp,
+            "tcp" => Self::Tcp,
+            "tls" => Self::Tls,
+            "https" => Self::Https,
+            "quic" => Self::Quic,
+            "h3" => Self::H3,
+            _ => Self::Other(name.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for DnsProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Udp => "udp",
+            Self::Tcp => "tcp",
+            Self::Tls => "tls",
+            Self::Https => "https",
+            Self::Quic => "quic",
+            Self::H3 => "h3",
+            Self::Other(name) => name,
+        })
+    }
+}
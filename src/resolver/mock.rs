@@ -0,0 +1,265 @@
+//! SRV resolver answering lookups with scripted responses.
+
+use super::{LookupMeta, SrvResolver};
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Errors produced by a [`MockResolver`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MockResolverError {
+    /// Produced by a lookup answered with [`MockResponse::Error`]
+    #[error("{0}")]
+    Failed(String),
+    /// Produced by a lookup answered with [`MockResponse::NoRecords`]
+    #[error("no SRV records")]
+    NoRecords,
+    /// Produced by lookups after every scripted response was used
+    #[error("no scripted responses left for lookup {lookup}")]
+    Exhausted {
+        /// Number of the lookup, counting from one
+        lookup: usize,
+    },
+}
+
+/// Scripted response to one lookup of a [`MockResolver`].
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// Answers with records valid for a duration
+    Records(Vec<OwnedSrvRecord>, Duration),
+    /// Answers with records valid for a duration, reporting where the answer
+    /// came from, e.g. whether it was authenticated with DNSSEC
+    RecordsWithMeta(Vec<OwnedSrvRecord>, Duration, LookupMeta),
+    /// Fails with a message
+    Error(String),
+    /// Fails as if the name doesn't exist or has no SRV records (NXDOMAIN or
+    /// NODATA)
+    NoRecords,
+}
+
+impl MockResponse {
+    /// Creates a response answering with records of the given `(target, port,
+    /// priority, weight)`, valid for `ttl`.
+    pub fn records<T: ToString>(
+        records: impl IntoIterator<Item = (T, u16, u16, u16)>,
+        ttl: Duration,
+    ) -> Self {
+        let records = records
+            .into_iter()
+            .map(|(target, port, priority, weight)| {
+                OwnedSrvRecord::new(target, port, priority, weight)
+            })
+            .collect();
+        Self::Records(records, ttl)
+    }
+
+    /// Creates a response failing with `message`.
+    pub fn error(message: impl ToString) -> Self {
+        Self::Error(message.to_string())
+    }
+
+    /// Makes a response answering with records report `meta` as where the
+    /// answer came from. Responses failing are unchanged.
+    pub fn with_meta(self, meta: LookupMeta) -> Self {
+        match self {
+            Self::Records(records, ttl) | Self::RecordsWithMeta(records, ttl, _) => {
+                Self::RecordsWithMeta(records, ttl, meta)
+            }
+            failing => failing,
+        }
+    }
+}
+
+/// SRV resolver answering each lookup with the next of a queue of scripted
+/// responses, for simulating DNS changing over time in tests.
+///
+/// Lookups after the queue is used up fail with
+/// [`MockResolverError::Exhausted`]. Clones share the queue and the count of
+/// lookups, so a clone kept by a test observes the lookups of a client.
+///
+/// Only available with the `mock` feature, e.g. for a dev-dependency.
+///
+/// ```
+/// # use detsys_srv::{resolver::{MockResolver, MockResponse}, SrvClient};
+/// # use std::time::Duration;
+/// let resolver = MockResolver::new([
+///     MockResponse::records([("a.example.com", 443, 1, 100)], Duration::ZERO),
+///     MockResponse::error("SERVFAIL"),
+/// ]);
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver.clone(),
+/// );
+/// futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap_err();
+/// assert_eq!(resolver.lookups(), 2);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MockResolver(Arc<Script>);
+
+#[derive(Debug, Default)]
+struct Script {
+    responses: Mutex<VecDeque<MockResponse>>,
+    lookups: AtomicUsize,
+}
+
+impl MockResolver {
+    /// Creates a resolver answering lookups with `responses` in order.
+    pub fn new(responses: impl IntoIterator<Item = MockResponse>) -> Self {
+        Self(Arc::new(Script {
+            responses: Mutex::new(responses.into_iter().collect()),
+            lookups: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Adds a response to the end of the queue.
+    pub fn push(&self, response: MockResponse) {
+        self.0.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Gets the number of lookups performed so far.
+    pub fn lookups(&self) -> usize {
+        self.0.lookups.load(Ordering::Acquire)
+    }
+
+    /// Gets the number of responses not yet used.
+    pub fn remaining(&self) -> usize {
+        self.0.responses.lock().unwrap().len()
+    }
+
+    /// Answers a lookup with the next response.
+    fn answer(&self) -> Result<(Vec<OwnedSrvRecord>, Instant, LookupMeta), MockResolverError> {
+        let lookup = self.0.lookups.fetch_add(1, Ordering::AcqRel) + 1;
+        let response = self.0.responses.lock().unwrap().pop_front();
+        match response {
+            Some(MockResponse::Records(records, ttl)) => {
+                Ok((records, Instant::now() + ttl, LookupMeta::default()))
+            }
+            Some(MockResponse::RecordsWithMeta(records, ttl, meta)) => {
+                Ok((records, Instant::now() + ttl, meta))
+            }
+            Some(MockResponse::Error(message)) => Err(MockResolverError::Failed(message)),
+            Some(MockResponse::NoRecords) => Err(MockResolverError::NoRecords),
+            None => Err(MockResolverError::Exhausted { lookup }),
+        }
+    }
+}
+
+#[async_trait]
+impl SrvResolver for MockResolver {
+    type Record = OwnedSrvRecord;
+    type Error = MockResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        _srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, valid_until, _) = self.answer()?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        _srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (mut records, valid_until, meta) = self.answer()?;
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, valid_until, meta))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, MockResolverError::NoRecords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SrvClient;
+    use futures::executor::block_on;
+
+    /// Executes an always-failing operation, returning the hosts it was
+    /// attempted on.
+    fn attempted_hosts(client: &SrvClient<MockResolver>) -> Vec<String> {
+        let mut attempted = Vec::new();
+        let _ = block_on(client.execute(|uri| {
+            attempted.push(uri.host_str().unwrap().to_owned());
+            async { Err::<(), _>(std::io::Error::other("failed")) }
+        }));
+        attempted.sort();
+        attempted
+    }
+
+    #[test]
+    fn scripted_lookups() {
+        let resolver = MockResolver::new([
+            MockResponse::records(
+                [
+                    ("a.example.com", 443, 1, 100),
+                    ("b.example.com", 443, 1, 100),
+                ],
+                Duration::ZERO,
+            ),
+            MockResponse::error("SERVFAIL"),
+        ]);
+        resolver.push(MockResponse::records(
+            [("c.example.com", 443, 1, 100)],
+            Duration::from_secs(60),
+        ));
+        let client = SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver.clone(),
+        );
+
+        assert_eq!(
+            attempted_hosts(&client),
+            ["a.example.com", "b.example.com", "fallback.example.com"]
+        );
+        assert_eq!(attempted_hosts(&client), ["fallback.example.com"]);
+        assert_eq!(
+            attempted_hosts(&client),
+            ["c.example.com", "fallback.example.com"]
+        );
+        // The last answer is still valid, so it isn't looked up again
+        attempted_hosts(&client);
+        assert_eq!(resolver.lookups(), 3);
+        assert_eq!(resolver.remaining(), 0);
+
+        let client = client.srv_name("_http._tcp.example.net");
+        assert!(matches!(
+            block_on(client.get_fresh_uri_candidates()),
+            Err(crate::Error::Lookup(MockResolverError::Exhausted {
+                lookup: 4
+            }))
+        ));
+    }
+
+    #[test]
+    fn scripted_presence() {
+        let resolver =
+            MockResolver::new([MockResponse::NoRecords, MockResponse::error("SERVFAIL")]);
+        let client = SrvClient::<_>::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            resolver,
+        );
+        assert_eq!(
+            block_on(client.exists()).unwrap(),
+            crate::ServicePresence::NotAdvertised
+        );
+        client.set_srv_name("_http._tcp.example.net");
+        assert!(block_on(client.exists()).is_err());
+    }
+}
@@ -1,13 +1,96 @@
 //! SRV resolvers.
+//!
+//! With the `dot` feature, which enables hickory's TLS support,
+//! `hickory::dot_resolver` builds a resolver sending lookups over
+//! DNS-over-TLS to given name servers. TLS handshake failures are produced as
+//! [`Error::Lookup`](crate::Error::Lookup) like any other failed lookup.
 
-use crate::SrvRecord;
+use crate::{SrvRecord, SvcbRecord, TlsaRecord};
 use async_trait::async_trait;
 use rand::Rng;
-use std::time::Instant;
+use std::{net::IpAddr, time::Instant};
 
-mod hickory;
+mod caching;
+mod chain;
+mod dns_sd;
+#[cfg(feature = "doh")]
+mod doh;
+#[cfg(feature = "hickory")]
+mod ecs;
+mod either;
+mod env;
+#[cfg(feature = "serde")]
+mod file;
+mod fixed;
+mod function;
+mod glue;
+#[cfg(feature = "hickory")]
+pub mod hickory;
+mod instrumented;
+mod kind;
+#[cfg(feature = "mdns")]
+mod mdns;
+mod meta;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+mod multi;
+#[cfg(feature = "tokio")]
+mod retry;
+mod shared;
+#[cfg(feature = "std-resolver")]
+mod stdlib;
+#[cfg(feature = "system-resolver")]
+mod system;
+#[cfg(feature = "tokio")]
+mod timeout;
+#[cfg(feature = "hickory")]
+pub mod trust_dns;
+#[cfg(any(
+    feature = "mdns",
+    feature = "std-resolver",
+    feature = "system-resolver"
+))]
+mod wire;
+mod zone;
+
+pub use caching::CachingResolver;
+pub use chain::{ChainedResolver, ChainedResolverError};
+pub use dns_sd::DnsSdResolver;
+#[cfg(feature = "doh")]
+pub use doh::{DohClient, DohError, DohResolver};
+#[cfg(feature = "hickory")]
+pub use ecs::ClientSubnetResolver;
+pub use either::Either;
+pub use env::{EnvOverrideError, EnvOverrideRecord, EnvOverrideResolver};
+#[cfg(feature = "serde")]
+pub use file::{FileResolver, FileResolverError};
+pub use fixed::{StaticResolver, StaticResolverError};
+pub use function::{BoxError, FnResolver, FnResolverError};
+pub use glue::Glue;
+#[cfg(feature = "hickory")]
+pub use hickory::{bind_config, BindError, DefaultResolver};
+pub use instrumented::{InstrumentedResolver, LookupObserver, LookupOutcome, LookupType};
+pub use kind::LookupErrorKind;
+#[cfg(feature = "mdns")]
+pub use mdns::{MdnsResolver, MdnsResolverError};
+pub use meta::{DnsProtocol, LookupMeta};
+#[cfg(any(test, feature = "mock"))]
+pub use mock::{MockResolver, MockResolverError, MockResponse};
+pub use multi::{MultiNameResolver, MultiNameResolverError};
+#[cfg(feature = "tokio")]
+pub use retry::{RetryResolver, RetryResolverError};
+#[cfg(feature = "std-resolver")]
+pub use stdlib::{StdResolver, StdResolverError};
+#[cfg(feature = "system-resolver")]
+pub use system::{SystemResolver, SystemResolverError};
+#[cfg(feature = "tokio")]
+pub use timeout::{TimeoutResolver, TimeoutResolverError};
+pub use zone::{NotInZone, ZoneError, ZoneResolver};
 
 /// Represents the ability to act as a SRV resolver.
+///
+/// Resolvers behind an [`Arc`](std::sync::Arc) or a reference are resolvers
+/// too, so that one resolver can be shared by several clients.
 #[async_trait]
 pub trait SrvResolver: Send + Sync {
     /// SRV record representation produced by the resolver.
@@ -36,6 +119,124 @@ pub trait SrvResolver: Send + Sync {
         Ok((records, valid_until))
     }
 
+    /// Gets the records corresponding to a srv name like
+    /// [`SrvResolver::get_srv_records`], along with where the answer came
+    /// from.
+    ///
+    /// Resolvers that can't tell report an empty [`LookupMeta`], which the
+    /// default implementation does.
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until) = self.get_srv_records(srv).await?;
+        Ok((records, valid_until, LookupMeta::default()))
+    }
+
+    /// Gets the records corresponding to a srv name like
+    /// [`SrvResolver::get_srv_records_with_meta`], along with the addresses
+    /// of their targets that came with the answer (e.g. A/AAAA glue in its
+    /// additional section), which spare looking each target up.
+    ///
+    /// Resolvers that can't tell report no [`Glue`], which the default
+    /// implementation does.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let (records, valid_until, meta) = self.get_srv_records_with_meta(srv).await?;
+        Ok((records, valid_until, meta, Glue::default()))
+    }
+
+    /// Gets the IP addresses a host name resolves to, returning them along
+    /// with the time they're valid until.
+    ///
+    /// Resolvers that can't look up addresses resolve every host to no
+    /// addresses, which the default implementation does.
+    #[allow(unused_variables)]
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        Ok((Vec::new(), Instant::now()))
+    }
+
+    /// Gets the TLSA records of a name (e.g. `_443._tcp.example.com`), which
+    /// are empty if it has none.
+    ///
+    /// Resolvers that can't look up TLSA records resolve every name to no
+    /// records, which the default implementation does.
+    #[allow(unused_variables)]
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Gets the HTTPS records (RFC 9460) of a name, returning them along with
+    /// the time they're valid until.
+    ///
+    /// Resolvers that can't look up HTTPS records resolve every name to no
+    /// records, which the default implementation does.
+    #[allow(unused_variables)]
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        Ok((Vec::new(), Instant::now()))
+    }
+
+    /// Gets the HTTPS records of a name like
+    /// [`SrvResolver::get_https_records`], along with where the answer came
+    /// from.
+    ///
+    /// Resolvers that can't tell report an empty [`LookupMeta`], which the
+    /// default implementation does.
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until) = self.get_https_records(name).await?;
+        Ok((records, valid_until, LookupMeta::default()))
+    }
+
+    /// Gets the names the PTR records of a name point to (e.g. the instances
+    /// of a DNS-SD service), returning them along with the time they're valid
+    /// until.
+    ///
+    /// Resolvers that can't look up PTR records resolve every name to no
+    /// records, which the default implementation does.
+    #[allow(unused_variables)]
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        Ok((Vec::new(), Instant::now()))
+    }
+
+    /// Gets the strings of the TXT records of a name, returning them along
+    /// with the time they're valid until.
+    ///
+    /// Resolvers that can't look up TXT records resolve every name to no
+    /// records, which the default implementation does.
+    #[allow(unused_variables)]
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        Ok((Vec::new(), Instant::now()))
+    }
+
+    /// Determines if an error means the looked up name doesn't exist or has no
+    /// records of the requested type (NXDOMAIN or NODATA), as opposed to the
+    /// lookup itself failing.
+    ///
+    /// Resolvers that can't tell consider every error a failed lookup, which
+    /// the default implementation does.
+    #[allow(unused_variables)]
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        false
+    }
+
+    /// Classifies an error, e.g. to tell a name that doesn't exist from name
+    /// servers that are down.
+    ///
+    /// Resolvers that can't tell classify every error as
+    /// [`LookupErrorKind::Unknown`], which the default implementation does.
+    #[allow(unused_variables)]
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        LookupErrorKind::Unknown
+    }
+
     /// Sorts SRV records by priority and weight per RFC 2782.
     fn order_srv_records(records: &mut [Self::Record], mut rng: impl Rng) {
         records.sort_by_cached_key(|record| record.sort_key(&mut rng));
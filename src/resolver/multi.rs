@@ -0,0 +1,398 @@
+//! SRV resolver merging the records of several SRV names.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use std::{collections::HashMap, fmt, net::IpAddr, time::Instant};
+
+/// Errors produced by a [`MultiNameResolver`] when the lookup of every name
+/// failed, holding the inner resolver's error for each name.
+#[derive(Debug, thiserror::Error)]
+pub struct MultiNameResolverError<E> {
+    errors: Vec<(String, E)>,
+    no_records: bool,
+}
+
+impl<E> MultiNameResolverError<E> {
+    /// Gets each name that was looked up along with the error looking it up,
+    /// in the order the names were given.
+    pub fn errors(&self) -> &[(String, E)] {
+        &self.errors
+    }
+
+    /// Unwraps the names and errors.
+    pub fn into_errors(self) -> Vec<(String, E)> {
+        self.errors
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for MultiNameResolverError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [(_, error)] => write!(f, "{error}"),
+            errors => {
+                write!(f, "all {} SRV names failed", errors.len())?;
+                for (name, error) in errors {
+                    write!(f, "; {name}: {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// SRV resolver merging the records of several SRV names, e.g. a service
+/// published separately in each region, so that one client considers the
+/// targets of all of them.
+///
+/// Lookups of a name [merged](MultiNameResolver::merge) from others look
+/// each of those up concurrently with the inner resolver, merging their
+/// records, which are valid until the earliest of their lookups expires. The
+/// lookups of some names failing is tolerated as long as one succeeds, with
+/// each failure logged. Names are matched case-insensitively and without
+/// any trailing dot, and lookups of other names (or other record types) are
+/// passed through.
+///
+/// ```
+/// # use detsys_srv::{resolver::{FnResolver, MultiNameResolver}, OwnedSrvRecord, SrvClient};
+/// # use std::time::{Duration, Instant};
+/// let resolver = FnResolver::new(|name| async move {
+///     let region = name.split('.').nth(2).unwrap().to_owned();
+///     let record = OwnedSrvRecord::new(format!("api.{region}.example.com"), 443, 1, 100);
+///     Ok((vec![record], Instant::now() + Duration::from_secs(60)))
+/// });
+/// let resolver = MultiNameResolver::new(resolver).merge(
+///     "_api._tcp.example.com",
+///     ["_api._tcp.region-a.example.com", "_api._tcp.region-b.example.com"],
+/// );
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_api._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(uris.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiNameResolver<R> {
+    inner: R,
+    names: HashMap<String, Vec<String>>,
+}
+
+impl<R> MultiNameResolver<R> {
+    /// Creates a resolver merging names looked up with `inner`, which passes
+    /// every lookup through until names are [merged](MultiNameResolver::merge).
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Sets the names whose records lookups of `name` merge, replacing any
+    /// set before. `name` itself is only looked up if it's one of them.
+    pub fn merge<S: Into<String>>(
+        mut self,
+        name: impl AsRef<str>,
+        names: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let names = names.into_iter().map(Into::into).collect();
+        self.names.insert(key(name.as_ref()), names);
+        self
+    }
+
+    /// Gets the names whose records lookups of `name` merge, if any.
+    pub fn merged_names(&self, name: &str) -> Option<&[String]> {
+        self.names.get(&key(name)).map(Vec::as_slice)
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner resolver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Gets the key merged names are stored under.
+fn key(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+/// Wraps an error of a lookup that's passed through.
+fn single<E>(name: &str) -> impl FnOnce(E) -> MultiNameResolverError<E> + '_ {
+    move |error| MultiNameResolverError {
+        errors: vec![(name.to_owned(), error)],
+        no_records: false,
+    }
+}
+
+impl<R: SrvResolver> MultiNameResolver<R> {
+    /// Looks `srv` up, merging the records of the names merged into it.
+    async fn lookup(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<R::Record>, Instant, LookupMeta, Glue), MultiNameResolverError<R::Error>> {
+        let Some(names) = self.merged_names(srv) else {
+            return self
+                .inner
+                .get_srv_records_with_glue(srv)
+                .await
+                .map_err(|error| MultiNameResolverError {
+                    no_records: R::is_no_records_found(&error),
+                    errors: vec![(srv.to_owned(), error)],
+                });
+        };
+        let lookups = join_all(
+            names
+                .iter()
+                .map(|name| self.inner.get_srv_records_with_glue(name)),
+        )
+        .await;
+
+        let mut merged = None::<(Vec<R::Record>, Instant, LookupMeta, Glue)>;
+        let mut errors = Vec::new();
+        for (name, lookup) in names.iter().zip(lookups) {
+            match lookup {
+                Ok((records, valid_until, meta, glue)) => match &mut merged {
+                    Some((all, min_valid_until, _, all_glue)) => {
+                        all.extend(records);
+                        *min_valid_until = valid_until.min(*min_valid_until);
+                        all_glue.merge(glue);
+                    }
+                    None => merged = Some((records, valid_until, meta, glue)),
+                },
+                Err(error) => errors.push((name.clone(), error)),
+            }
+        }
+        match merged {
+            Some(merged) => {
+                for (name, error) in &errors {
+                    if !R::is_no_records_found(error) {
+                        tracing::debug!(srv, name, %error, "Failed to look up merged SRV name");
+                    }
+                }
+                Ok(merged)
+            }
+            None => Err(MultiNameResolverError {
+                no_records: !errors.is_empty()
+                    && errors
+                        .iter()
+                        .all(|(_, error)| R::is_no_records_found(error)),
+                errors,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl<R> SrvResolver for MultiNameResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send,
+    R::Error: Send,
+{
+    type Record = R::Record;
+    type Error = MultiNameResolverError<R::Error>;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let (records, valid_until, _, _) = self.lookup(srv).await?;
+        Ok((records, valid_until))
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let (records, valid_until, meta, _) = self.get_srv_records_with_glue(srv).await?;
+        Ok((records, valid_until, meta))
+    }
+
+    /// Reports where the answer for the first name that succeeded came from.
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        let (mut records, valid_until, meta, glue) = self.lookup(srv).await?;
+        Self::order_srv_records(&mut records, rand::rng());
+        Ok((records, valid_until, meta, glue))
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.inner.get_ip_addrs(host).await.map_err(single(host))
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.inner
+            .get_tlsa_records(name)
+            .await
+            .map_err(single(name))
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.inner
+            .get_https_records(name)
+            .await
+            .map_err(single(name))
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.inner
+            .get_https_records_with_meta(name)
+            .await
+            .map_err(single(name))
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_ptr_records(name).await.map_err(single(name))
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_txt_records(name).await.map_err(single(name))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        error.no_records
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        // Names failing in different ways can't be summed up
+        let mut kinds = error.errors.iter().map(|(_, e)| R::classify_error(e));
+        let first = kinds.next().unwrap_or(LookupErrorKind::Unknown);
+        match kinds.all(|kind| kind == first) {
+            true => first,
+            false => LookupErrorKind::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OwnedSrvRecord, SrvRecord};
+    use futures::executor::block_on;
+    use std::{io, time::Duration};
+
+    /// Resolver answering for two regions, one of them with a shorter TTL,
+    /// failing for a third, and finding no records for any other name.
+    struct Regions;
+
+    #[async_trait]
+    impl SrvResolver for Regions {
+        type Record = OwnedSrvRecord;
+        type Error = io::Error;
+
+        async fn get_srv_records_unordered(
+            &self,
+            srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+            let ttl = match srv {
+                "_api._tcp.region-a.example.com" => 60,
+                "_api._tcp.region-b.example.com" => 10,
+                "_api._tcp.region-c.example.com" => return Err(io::Error::other("SERVFAIL")),
+                _ => return Err(io::ErrorKind::NotFound.into()),
+            };
+            let region = srv.split('.').nth(2).unwrap();
+            let records = (1..=2)
+                .map(|n| OwnedSrvRecord::new(format!("{n}.{region}.example.com"), 443, 1, 100))
+                .collect();
+            Ok((records, Instant::now() + Duration::from_secs(ttl)))
+        }
+
+        fn is_no_records_found(error: &Self::Error) -> bool {
+            error.kind() == io::ErrorKind::NotFound
+        }
+    }
+
+    fn lookup(
+        resolver: &MultiNameResolver<Regions>,
+        srv: &str,
+    ) -> Result<(Vec<String>, Instant), MultiNameResolverError<io::Error>> {
+        let (records, valid_until) = block_on(resolver.get_srv_records(srv))?;
+        let mut targets = records
+            .iter()
+            .map(|record| record.target().to_owned())
+            .collect::<Vec<_>>();
+        targets.sort();
+        Ok((targets, valid_until))
+    }
+
+    fn region(name: &str) -> String {
+        format!("_api._tcp.{name}.example.com")
+    }
+
+    #[test]
+    fn merges_names() {
+        let resolver = MultiNameResolver::new(Regions).merge(
+            "_api._tcp.example.com.",
+            ["region-a", "region-b"].map(region),
+        );
+        let before = Instant::now();
+        let (targets, valid_until) = lookup(&resolver, "_API._tcp.example.com").unwrap();
+        assert_eq!(
+            targets,
+            [
+                "1.region-a.example.com",
+                "1.region-b.example.com",
+                "2.region-a.example.com",
+                "2.region-b.example.com",
+            ]
+        );
+        assert!(valid_until <= Instant::now() + Duration::from_secs(10));
+        assert!(valid_until >= before + Duration::from_secs(10));
+
+        // Other names are passed through
+        let (targets, _) = lookup(&resolver, &region("region-a")).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert!(resolver.merged_names(&region("region-a")).is_none());
+    }
+
+    #[test]
+    fn tolerates_partial_failures() {
+        let resolver = MultiNameResolver::new(Regions).merge(
+            "_api._tcp.example.com",
+            ["region-c", "region-b", "region-d"].map(region),
+        );
+        let (targets, _) = lookup(&resolver, "_api._tcp.example.com").unwrap();
+        assert_eq!(
+            targets,
+            ["1.region-b.example.com", "2.region-b.example.com"]
+        );
+
+        let resolver = MultiNameResolver::new(Regions).merge(
+            "_api._tcp.example.com",
+            ["region-c", "region-d"].map(region),
+        );
+        let err = lookup(&resolver, "_api._tcp.example.com").unwrap_err();
+        assert_eq!(err.errors().len(), 2);
+        assert_eq!(
+            err.to_string(),
+            "all 2 SRV names failed; _api._tcp.region-c.example.com: SERVFAIL; \
+             _api._tcp.region-d.example.com: entity not found"
+        );
+        assert!(!MultiNameResolver::<Regions>::is_no_records_found(&err));
+
+        let resolver = MultiNameResolver::new(Regions).merge(
+            "_api._tcp.example.com",
+            ["region-d", "region-e"].map(region),
+        );
+        let err = lookup(&resolver, "_api._tcp.example.com").unwrap_err();
+        assert!(MultiNameResolver::<Regions>::is_no_records_found(&err));
+        let err = lookup(&resolver, "_api._tcp.example.net").unwrap_err();
+        assert!(MultiNameResolver::<Regions>::is_no_records_found(&err));
+    }
+}
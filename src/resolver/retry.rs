@@ -0,0 +1,328 @@
+//! SRV resolver retrying failed lookups with exponential backoff.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use rand::Rng;
+use std::{
+    future::Future,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// Error produced by a [`RetryResolver`]: the inner resolver's error from
+/// the last attempt, along with how many attempts were made.
+#[derive(Debug, thiserror::Error)]
+#[error("{error} (after {attempts} attempts)")]
+pub struct RetryResolverError<E> {
+    #[source]
+    error: E,
+    attempts: usize,
+}
+
+impl<E> RetryResolverError<E> {
+    /// Gets the inner resolver's error from the last attempt.
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Gets how many attempts were made.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Unwraps the inner resolver's error from the last attempt.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+/// SRV resolver retrying failed SRV lookups of an inner resolver, so that a
+/// transient failure (e.g. SERVFAIL) doesn't leave a client on its fallback
+/// for the rest of the cache window.
+///
+/// Retries wait an exponentially growing delay with jitter: each is
+/// between half and all of the initial delay times the multiplier raised to
+/// the number of retries so far, capped at a maximum delay. Lookups finding that the name doesn't
+/// exist or has no records aren't retried. Lookups of other record types
+/// aren't retried either.
+#[derive(Debug, Clone)]
+pub struct RetryResolver<R> {
+    inner: R,
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    max_attempts: usize,
+}
+
+impl<R> RetryResolver<R> {
+    /// Creates a resolver retrying lookups of `inner`, making at most three
+    /// attempts with an initial delay of 100 milliseconds that doubles for
+    /// each retry, up to 10 seconds.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            initial_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(10),
+            max_attempts: 3,
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_delay(self, initial_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            ..self
+        }
+    }
+
+    /// Sets the factor the delay grows by for each retry. Factors below 1
+    /// are treated as 1.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        Self {
+            multiplier: multiplier.max(1.0),
+            ..self
+        }
+    }
+
+    /// Sets the longest delay before a retry, however many retries came
+    /// before it.
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Sets how many attempts a lookup makes at most, including the first.
+    /// At least one attempt is always made.
+    pub fn max_attempts(self, max_attempts: usize) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..self
+        }
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner resolver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Gets the delay before the retry following `retries` earlier retries,
+    /// without jitter, capped at the maximum delay so that jitter can't
+    /// overflow it.
+    fn backoff(&self, retries: usize) -> Duration {
+        let exponent = i32::try_from(retries).unwrap_or(i32::MAX);
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(delay)
+            .unwrap_or(Duration::MAX)
+            .min(self.max_delay)
+    }
+}
+
+impl<R: SrvResolver> RetryResolver<R> {
+    /// Runs a lookup of the inner resolver until it succeeds, finds no
+    /// records, or runs out of attempts.
+    async fn retry<T, Fut>(
+        &self,
+        srv: &str,
+        lookup: impl Fn() -> Fut,
+    ) -> Result<T, RetryResolverError<R::Error>>
+    where
+        Fut: Future<Output = Result<T, R::Error>>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match lookup().await {
+                Ok(found) => {
+                    if attempts > 1 {
+                        tracing::debug!(srv, attempts, "SRV lookup succeeded after retrying");
+                    }
+                    return Ok(found);
+                }
+                Err(error) => error,
+            };
+            if attempts >= self.max_attempts || R::is_no_records_found(&error) {
+                tracing::debug!(srv, attempts, %error, "SRV lookup failed");
+                return Err(RetryResolverError { error, attempts });
+            }
+            let delay = self
+                .backoff(attempts - 1)
+                .mul_f64(rand::rng().random_range(0.5..=1.0));
+            tracing::trace!(srv, attempts, %error, ?delay, "Retrying SRV lookup");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Wraps an error of a lookup that isn't retried.
+fn once<E>(error: E) -> RetryResolverError<E> {
+    RetryResolverError { error, attempts: 1 }
+}
+
+#[async_trait]
+impl<R> SrvResolver for RetryResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send,
+    R::Error: Send,
+{
+    type Record = R::Record;
+    type Error = RetryResolverError<R::Error>;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        self.retry(srv, || self.inner.get_srv_records_unordered(srv))
+            .await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        self.retry(srv, || self.inner.get_srv_records_with_meta(srv))
+            .await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        self.retry(srv, || self.inner.get_srv_records_with_glue(srv))
+            .await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.inner.get_ip_addrs(host).await.map_err(once)
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.inner.get_tlsa_records(name).await.map_err(once)
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.inner.get_https_records(name).await.map_err(once)
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.inner
+            .get_https_records_with_meta(name)
+            .await
+            .map_err(once)
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_ptr_records(name).await.map_err(once)
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.inner.get_txt_records(name).await.map_err(once)
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(&error.error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(&error.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resolver::{MockResolver, MockResolverError, MockResponse},
+        SrvRecord,
+    };
+
+    fn resolver(responses: impl IntoIterator<Item = MockResponse>) -> RetryResolver<MockResolver> {
+        RetryResolver::new(MockResolver::new(responses))
+            .initial_delay(Duration::from_secs(1))
+            .multiplier(3.0)
+            .max_attempts(3)
+    }
+
+    fn a_record() -> MockResponse {
+        MockResponse::records([("a.example.com", 443, 1, 100)], Duration::from_secs(60))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn succeeds_after_retrying() {
+        let resolver = resolver([
+            MockResponse::error("SERVFAIL"),
+            MockResponse::error("SERVFAIL"),
+            a_record(),
+        ]);
+        let start = tokio::time::Instant::now();
+        let (records, _) = resolver
+            .get_srv_records("_http._tcp.example.com")
+            .await
+            .unwrap();
+        assert_eq!(records[0].target(), "a.example.com");
+        assert_eq!(resolver.inner().lookups(), 3);
+        // Waited between half and all of 1 + 3 seconds
+        let waited = start.elapsed();
+        assert!(waited >= Duration::from_secs(2), "{waited:?}");
+        assert!(waited <= Duration::from_secs(4), "{waited:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exhausts_attempts() {
+        let resolver = resolver([
+            MockResponse::error("SERVFAIL"),
+            MockResponse::error("SERVFAIL"),
+            MockResponse::error("REFUSED"),
+            a_record(),
+        ]);
+        let err = resolver
+            .get_srv_records("_http._tcp.example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(err.attempts(), 3);
+        assert_eq!(err.error(), &MockResolverError::Failed("REFUSED".into()));
+        assert_eq!(err.to_string(), "REFUSED (after 3 attempts)");
+        assert_eq!(resolver.inner().remaining(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_records_not_retried() {
+        let resolver = resolver([MockResponse::NoRecords, a_record()]);
+        let err = resolver
+            .get_srv_records("_http._tcp.example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(err.attempts(), 1);
+        assert!(RetryResolver::<MockResolver>::is_no_records_found(&err));
+        assert_eq!(resolver.inner().remaining(), 1);
+    }
+
+    #[test]
+    fn backoff() {
+        let resolver = resolver([]);
+        assert_eq!(resolver.backoff(0), Duration::from_secs(1));
+        assert_eq!(resolver.backoff(2), Duration::from_secs(9));
+        assert_eq!(resolver.backoff(3), Duration::from_secs(10));
+        assert_eq!(resolver.backoff(usize::MAX), Duration::from_secs(10));
+        assert_eq!(
+            resolver.backoff(usize::MAX).mul_f64(1.0),
+            Duration::from_secs(10)
+        );
+
+        let resolver = resolver.max_delay(Duration::from_secs(5));
+        assert_eq!(resolver.backoff(1), Duration::from_secs(3));
+        assert_eq!(resolver.backoff(2), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,195 @@
+//! SRV resolvers shared between clients, behind an [`Arc`] or a reference.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use rand::Rng;
+use std::{net::IpAddr, sync::Arc, time::Instant};
+
+#[async_trait]
+impl<R: SrvResolver + ?Sized> SrvResolver for Arc<R> {
+    type Record = R::Record;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        R::get_srv_records_unordered(self, srv).await
+    }
+
+    async fn get_srv_records(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        R::get_srv_records(self, srv).await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        R::get_srv_records_with_meta(self, srv).await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        R::get_srv_records_with_glue(self, srv).await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        R::get_ip_addrs(self, host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        R::get_tlsa_records(self, name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        R::get_https_records(self, name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        R::get_https_records_with_meta(self, name).await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        R::get_ptr_records(self, name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        R::get_txt_records(self, name).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+
+    fn order_srv_records(records: &mut [Self::Record], rng: impl Rng) {
+        R::order_srv_records(records, rng)
+    }
+}
+
+#[async_trait]
+impl<R: SrvResolver + ?Sized> SrvResolver for &R {
+    type Record = R::Record;
+    type Error = R::Error;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        R::get_srv_records_unordered(self, srv).await
+    }
+
+    async fn get_srv_records(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        R::get_srv_records(self, srv).await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        R::get_srv_records_with_meta(self, srv).await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        R::get_srv_records_with_glue(self, srv).await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        R::get_ip_addrs(self, host).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        R::get_tlsa_records(self, name).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        R::get_https_records(self, name).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        R::get_https_records_with_meta(self, name).await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        R::get_ptr_records(self, name).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        R::get_txt_records(self, name).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        R::is_no_records_found(error)
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        R::classify_error(error)
+    }
+
+    fn order_srv_records(records: &mut [Self::Record], rng: impl Rng) {
+        R::order_srv_records(records, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resolver::StaticResolver, SrvClient};
+    use std::time::Duration;
+
+    #[test]
+    fn shared_between_clients() {
+        let resolver = Arc::new(StaticResolver::new(
+            [("a.example.com", 443, 1, 1)],
+            Duration::from_secs(60),
+        ));
+        fn first_host<R: SrvResolver>(resolver: R) -> String {
+            let client = SrvClient::<_>::new_with_resolver(
+                "_http._tcp.example.com",
+                "https://fallback.example.com".parse().unwrap(),
+                None,
+                resolver,
+            );
+            let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+            uris[0].host_str().unwrap().to_owned()
+        }
+        assert_eq!(first_host(resolver.clone()), "a.example.com");
+        assert_eq!(first_host(&*resolver), "a.example.com");
+    }
+
+    #[test]
+    fn clients_of_shared_resolvers_are_send_and_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<SrvClient<Arc<StaticResolver>>>();
+        is_send_sync::<SrvClient<&'static StaticResolver>>();
+        #[cfg(feature = "hickory")]
+        is_send_sync::<SrvClient<Arc<crate::resolver::DefaultResolver>>>();
+    }
+}
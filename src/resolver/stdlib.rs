@@ -0,0 +1,603 @@
+//! SRV resolver sending DNS queries with the standard library's sockets.
+
+use super::{
+    wire::{self, Header, MAX_MESSAGE_LEN},
+    LookupMeta, SrvResolver,
+};
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use rand::Rng;
+use std::{
+    io::{self, Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Path of the system's resolver configuration.
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Errors produced by a [`StdResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum StdResolverError {
+    /// Produced when the resolver configuration can't be read
+    #[error("failed to read resolver configuration {path}")]
+    Config {
+        /// Path of the resolver configuration
+        path: PathBuf,
+        /// Error reading the configuration
+        #[source]
+        source: io::Error,
+    },
+    /// Produced when a name can't be encoded in a query
+    #[error("invalid name {name:?}: {reason}")]
+    InvalidName {
+        /// Name that was looked up
+        name: String,
+        /// Why the name is invalid
+        reason: &'static str,
+    },
+    /// Produced when the resolver has no name servers to query
+    #[error("no name servers to query")]
+    NoNameServers,
+    /// Produced when the name doesn't exist or has no SRV records
+    #[error("no SRV records for {name}")]
+    NoRecords {
+        /// Name that was looked up
+        name: String,
+    },
+    /// Produced when a name server can't be reached
+    #[error("failed to query {server}")]
+    Io {
+        /// Name server that was queried
+        server: SocketAddr,
+        /// Error communicating with the name server
+        #[source]
+        source: io::Error,
+    },
+    /// Produced when a name server doesn't answer in time
+    #[error("{server} didn't answer in time")]
+    TimedOut {
+        /// Name server that was queried
+        server: SocketAddr,
+    },
+    /// Produced when a name server answers with an error
+    #[error("{server} failed to look up {name} (rcode {rcode})")]
+    Failed {
+        /// Name that was looked up
+        name: String,
+        /// Name server that was queried
+        server: SocketAddr,
+        /// Response code of the answer
+        rcode: u8,
+    },
+    /// Produced when an answer can't be parsed
+    #[error("malformed DNS answer: {0}")]
+    Malformed(&'static str),
+}
+
+/// SRV resolver sending queries over UDP with nothing but the standard
+/// library, for small tools that don't want an async DNS client.
+///
+/// Names are looked up like the system's stub resolver would, trying the
+/// search domains according to `ndots`, and answers truncated over UDP are
+/// retried over TCP. Priorities, weights and ports are used exactly as they
+/// come from the wire. Lookups are valid for the smallest TTL of their
+/// records, or for [`StdResolver::default_ttl`] (30 seconds unless set) when
+/// the answer doesn't give one. With the `tokio` feature, lookups run on
+/// tokio's blocking threads, so they need a tokio runtime; otherwise they
+/// block the calling thread.
+#[derive(Debug, Clone)]
+pub struct StdResolver {
+    name_servers: Vec<SocketAddr>,
+    search: Vec<String>,
+    ndots: usize,
+    timeout: Duration,
+    attempts: usize,
+    default_ttl: Duration,
+    trust_ad: bool,
+}
+
+impl StdResolver {
+    /// Creates a resolver querying the given name servers, without search
+    /// domains.
+    pub fn new(name_servers: impl IntoIterator<Item = SocketAddr>) -> Self {
+        Self {
+            name_servers: name_servers.into_iter().collect(),
+            search: Vec::new(),
+            ndots: 1,
+            timeout: Duration::from_secs(5),
+            attempts: 2,
+            default_ttl: Duration::from_secs(30),
+            trust_ad: false,
+        }
+    }
+
+    /// Creates a resolver configured like the system's, from
+    /// `/etc/resolv.conf`.
+    pub fn from_system() -> Result<Self, StdResolverError> {
+        Self::from_resolv_conf_file(RESOLV_CONF)
+    }
+
+    /// Creates a resolver from a `resolv.conf(5)` file.
+    pub fn from_resolv_conf_file(path: impl AsRef<Path>) -> Result<Self, StdResolverError> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|source| StdResolverError::Config {
+                path: path.to_owned(),
+                source,
+            })?;
+        Ok(Self::from_resolv_conf(&contents))
+    }
+
+    /// Creates a resolver from the contents of a `resolv.conf(5)` file,
+    /// honoring its `nameserver`, `search`, `domain` and `options` lines,
+    /// including the `trust-ad` option (see [`StdResolver::trust_ad`]).
+    /// Without name servers, the local one is queried.
+    pub fn from_resolv_conf(contents: &str) -> Self {
+        let mut resolver = Self::new([]);
+        for line in contents.lines() {
+            let mut words = line
+                .split(['#', ';'])
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            match words.next() {
+                Some("nameserver") => {
+                    // Scoped IPv6 addresses can't be parsed, and are skipped
+                    if let Some(ip) = words.next().and_then(|ip| ip.parse::<IpAddr>().ok()) {
+                        resolver.name_servers.push(SocketAddr::new(ip, 53));
+                    }
+                }
+                Some("search" | "domain") => {
+                    resolver.search = words.map(|domain| domain.to_owned()).collect();
+                }
+                Some("options") => {
+                    for option in words {
+                        if option == "trust-ad" {
+                            resolver.trust_ad = true;
+                        }
+                        let Some((option, value)) = option.split_once(':') else {
+                            continue;
+                        };
+                        let Ok(value) = value.parse::<u64>() else {
+                            continue;
+                        };
+                        match option {
+                            "ndots" => resolver.ndots = value.min(15) as usize,
+                            "timeout" => resolver.timeout = Duration::from_secs(value.max(1)),
+                            "attempts" => resolver.attempts = value.clamp(1, 5) as usize,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if resolver.name_servers.is_empty() {
+            resolver.name_servers = vec![SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 53)];
+        }
+        resolver
+    }
+
+    /// Sets the domains appended to names with fewer than `ndots` dots.
+    pub fn search_domains(self, search: Vec<String>) -> Self {
+        Self { search, ..self }
+    }
+
+    /// Sets how many dots a name needs to be looked up as is before the
+    /// search domains are tried.
+    pub fn ndots(self, ndots: usize) -> Self {
+        Self { ndots, ..self }
+    }
+
+    /// Sets how long to wait for each name server to answer.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets how many times each name server is tried.
+    pub fn attempts(self, attempts: usize) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            ..self
+        }
+    }
+
+    /// Sets how long lookups are valid for when their answer doesn't give a
+    /// TTL, i.e. when the smallest TTL of its records is zero.
+    pub fn default_ttl(self, default_ttl: Duration) -> Self {
+        Self {
+            default_ttl,
+            ..self
+        }
+    }
+
+    /// Sets whether the AD bit is trusted from every name server, like the
+    /// `trust-ad` option of `resolv.conf(5)`, which it isn't by default.
+    ///
+    /// Answers travel over plain UDP or TCP, so anything on the path to a
+    /// name server can set the AD bit. Unless trusted, it's only honored
+    /// from name servers on a loopback address, and answers from others are
+    /// reported as not [authenticated](LookupMeta::authenticated).
+    pub fn trust_ad(self, trust_ad: bool) -> Self {
+        Self { trust_ad, ..self }
+    }
+
+    /// Gets the name servers queried by the resolver.
+    pub fn name_servers(&self) -> &[SocketAddr] {
+        &self.name_servers
+    }
+
+    /// Determines if the AD bit set by `server` can be trusted.
+    fn trusts_ad_from(&self, server: SocketAddr) -> bool {
+        self.trust_ad || server.ip().is_loopback()
+    }
+
+    /// Gets how long an answer with the given TTL is valid for.
+    fn valid_for(&self, ttl: u32) -> Duration {
+        match ttl {
+            0 => self.default_ttl,
+            ttl => Duration::from_secs(ttl.into()),
+        }
+    }
+
+    /// Gets the names to query for `srv`, in order.
+    fn candidates(&self, srv: &str) -> Vec<String> {
+        if srv.ends_with('.') {
+            return vec![srv.to_owned()];
+        }
+        let searched = self.search.iter().map(|domain| format!("{srv}.{domain}"));
+        if srv.matches('.').count() >= self.ndots {
+            std::iter::once(srv.to_owned()).chain(searched).collect()
+        } else {
+            searched.chain(std::iter::once(srv.to_owned())).collect()
+        }
+    }
+
+    /// Looks up the SRV records of `srv`, on tokio's blocking threads when the
+    /// `tokio` feature is enabled.
+    async fn lookup(&self, srv: &str) -> Result<Answer, StdResolverError> {
+        #[cfg(feature = "tokio")]
+        {
+            let (resolver, srv) = (self.clone(), srv.to_owned());
+            tokio::task::spawn_blocking(move || resolver.lookup_blocking(&srv))
+                .await
+                .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+        }
+        #[cfg(not(feature = "tokio"))]
+        self.lookup_blocking(srv)
+    }
+
+    /// Looks up the SRV records of `srv`, blocking the calling thread.
+    fn lookup_blocking(&self, srv: &str) -> Result<Answer, StdResolverError> {
+        for name in self.candidates(srv) {
+            if let Some(answer) = self.query(&name)? {
+                return Ok(answer);
+            }
+        }
+        Err(StdResolverError::NoRecords {
+            name: srv.to_owned(),
+        })
+    }
+
+    /// Queries the name servers for the SRV records of `name`, producing
+    /// `None` when it doesn't exist or has none.
+    fn query(&self, name: &str) -> Result<Option<Answer>, StdResolverError> {
+        let id = rand::rng().random();
+        let query = wire::srv_query(id, name).map_err(|reason| StdResolverError::InvalidName {
+            name: name.to_owned(),
+            reason,
+        })?;
+        let mut last_err = StdResolverError::NoNameServers;
+        for _ in 0..self.attempts {
+            for &server in &self.name_servers {
+                let (header, answer) = match self.exchange(server, &query, id) {
+                    Ok(answer) => answer,
+                    Err(err) => {
+                        last_err = err;
+                        continue;
+                    }
+                };
+                match header.rcode {
+                    Header::NO_ERROR => {
+                        let (records, ttl) = wire::parse_srv_answers(&answer, name)
+                            .map_err(StdResolverError::Malformed)?;
+                        return Ok((!records.is_empty()).then_some(Answer {
+                            records,
+                            ttl,
+                            server,
+                            authentic_data: header.authentic_data,
+                        }));
+                    }
+                    Header::NX_DOMAIN => return Ok(None),
+                    rcode => {
+                        last_err = StdResolverError::Failed {
+                            name: name.to_owned(),
+                            server,
+                            rcode,
+                        }
+                    }
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Sends `query` to `server` over UDP, retrying over TCP if the answer
+    /// is truncated.
+    fn exchange(
+        &self,
+        server: SocketAddr,
+        query: &[u8],
+        id: u16,
+    ) -> Result<(Header, Vec<u8>), StdResolverError> {
+        let io_err = |source: io::Error| match source.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                StdResolverError::TimedOut { server }
+            }
+            _ => StdResolverError::Io { server, source },
+        };
+        let local: IpAddr = match server {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => std::net::Ipv6Addr::UNSPECIFIED.into(),
+        };
+        let socket = UdpSocket::bind((local, 0)).map_err(io_err)?;
+        socket
+            .set_read_timeout(Some(self.timeout))
+            .map_err(io_err)?;
+        socket.connect(server).map_err(io_err)?;
+        socket.send(query).map_err(io_err)?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut answer = vec![0; MAX_MESSAGE_LEN];
+        loop {
+            let len = socket.recv(&mut answer).map_err(io_err)?;
+            // Skip stray datagrams, such as late answers to other queries
+            match Header::parse(&answer[..len]) {
+                Ok(header) if header.response && header.id == id => {
+                    if header.truncated {
+                        return self.exchange_tcp(server, query, id).map_err(io_err);
+                    }
+                    answer.truncate(len);
+                    return Ok((header, answer));
+                }
+                _ if Instant::now() >= deadline => {
+                    return Err(StdResolverError::TimedOut { server })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Sends `query` to `server` over TCP.
+    fn exchange_tcp(
+        &self,
+        server: SocketAddr,
+        query: &[u8],
+        id: u16,
+    ) -> io::Result<(Header, Vec<u8>)> {
+        let mut stream = TcpStream::connect_timeout(&server, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut message = (query.len() as u16).to_be_bytes().to_vec();
+        message.extend_from_slice(query);
+        stream.write_all(&message)?;
+
+        let mut len = [0; 2];
+        stream.read_exact(&mut len)?;
+        let mut answer = vec![0; u16::from_be_bytes(len).into()];
+        stream.read_exact(&mut answer)?;
+        match Header::parse(&answer) {
+            Ok(header) if header.response && header.id == id => Ok((header, answer)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "answer doesn't match the query",
+            )),
+        }
+    }
+}
+
+/// SRV records found by a [`StdResolver`].
+struct Answer {
+    records: Vec<OwnedSrvRecord>,
+    /// Smallest TTL of the records
+    ttl: u32,
+    /// Name server that answered
+    server: SocketAddr,
+    /// Whether the name server set the AD bit
+    authentic_data: bool,
+}
+
+#[async_trait]
+impl SrvResolver for StdResolver {
+    type Record = OwnedSrvRecord;
+    type Error = StdResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let answer = self.lookup(srv).await?;
+        let valid_until = Instant::now() + self.valid_for(answer.ttl);
+        Ok((answer.records, valid_until))
+    }
+
+    /// Reports the name server that answered, and whether it authenticated
+    /// the answer with DNSSEC by setting the AD bit, if the AD bit is
+    /// [trusted](StdResolver::trust_ad) from it.
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        let mut answer = self.lookup(srv).await?;
+        let valid_until = Instant::now() + self.valid_for(answer.ttl);
+        Self::order_srv_records(&mut answer.records, rand::rng());
+        let meta = LookupMeta::default()
+            .with_server(answer.server)
+            .with_authenticated(answer.authentic_data && self.trusts_ad_from(answer.server));
+        Ok((answer.records, valid_until, meta))
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, StdResolverError::NoRecords { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SrvRecord;
+    use std::{future::Future, sync::mpsc};
+
+    /// Runs a lookup to completion, in a tokio runtime if lookups need one.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        #[cfg(feature = "tokio")]
+        return tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future);
+        #[cfg(not(feature = "tokio"))]
+        futures::executor::block_on(future)
+    }
+
+    /// Serves the given answers (NXDOMAIN, or a TTL and whether the AD bit is
+    /// set) to queries over UDP, one per query, sending back the names that
+    /// were queried.
+    fn serve(answers: Vec<Option<(u32, bool)>>) -> (SocketAddr, mpsc::Receiver<Vec<u8>>) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for answer in answers {
+                let mut buf = [0; 512];
+                let (len, peer) = socket.recv_from(&mut buf).unwrap();
+                let mut message = buf[..len].to_vec();
+                tx.send(message[12..].to_vec()).unwrap();
+                message[2] = 0x81;
+                match answer {
+                    // A single record for `a.example.com:8443` with the given TTL
+                    Some((ttl, authentic_data)) => {
+                        message[3] = if authentic_data { 0xa0 } else { 0x80 };
+                        message[7] = 1;
+                        message.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01]);
+                        message.extend_from_slice(&ttl.to_be_bytes());
+                        message.extend_from_slice(&[0x00, 0x15, 0x00, 0x05, 0x00, 0x0a]);
+                        message.extend_from_slice(&[0x20, 0xfb, 0x01, b'a']);
+                        message.extend_from_slice(b"\x07example\x03com\x00");
+                    }
+                    None => message[3] = 0x80 | Header::NX_DOMAIN,
+                }
+                socket.send_to(&message, peer).unwrap();
+            }
+        });
+        (addr, rx)
+    }
+
+    #[test]
+    fn searches_domains() {
+        let (server, queried) = serve(vec![None, Some((0, false)), Some((120, true))]);
+        let resolver = StdResolver::new([server])
+            .search_domains(vec!["corp.example".into()])
+            .ndots(5)
+            .default_ttl(Duration::from_secs(10));
+
+        let before = Instant::now();
+        let (records, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        let record = &records[0];
+        assert_eq!(
+            (
+                record.target(),
+                record.port(),
+                record.priority(),
+                record.weight()
+            ),
+            ("a.example.com.", 8443, 5, 10)
+        );
+        assert!(valid_until >= before + Duration::from_secs(10));
+        assert!(valid_until < before + Duration::from_secs(60));
+        let expected = |name| wire::srv_query(0, name).unwrap()[12..].to_vec();
+        assert_eq!(
+            queried.recv().unwrap(),
+            expected("_http._tcp.example.com.corp.example")
+        );
+        assert_eq!(queried.recv().unwrap(), expected("_http._tcp.example.com"));
+
+        let (_, valid_until, meta) =
+            block_on(resolver.get_srv_records_with_meta("_http._tcp.example.com.")).unwrap();
+        assert!(valid_until >= before + Duration::from_secs(120));
+        assert_eq!(meta.server(), Some(server));
+        assert_eq!(meta.authenticated(), Some(true));
+        assert_eq!(queried.recv().unwrap(), expected("_http._tcp.example.com"));
+    }
+
+    #[test]
+    fn no_records() {
+        let (server, _queried) = serve(vec![None]);
+        let resolver = StdResolver::new([server]);
+        let err = block_on(resolver.get_srv_records("_http._tcp.example.com.")).unwrap_err();
+        assert!(StdResolver::is_no_records_found(&err));
+    }
+
+    #[test]
+    fn times_out() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let server = socket.local_addr().unwrap();
+        let resolver = StdResolver::new([server])
+            .timeout(Duration::from_millis(50))
+            .attempts(1);
+        let err = block_on(resolver.get_srv_records("_http._tcp.example.com.")).unwrap_err();
+        assert!(matches!(err, StdResolverError::TimedOut { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn parses_resolv_conf() {
+        let resolver = StdResolver::from_resolv_conf(
+            "# generated\n\
+             nameserver 10.0.0.1\n\
+             nameserver fe80::1%eth0\n\
+             nameserver 2001:db8::1 ; secondary\n\
+             domain example.com\n\
+             search corp.example example.com\n\
+             options ndots:2 timeout:1 attempts:3 rotate trust-ad\n",
+        );
+        assert_eq!(
+            resolver.name_servers(),
+            [
+                "10.0.0.1:53".parse().unwrap(),
+                "[2001:db8::1]:53".parse().unwrap()
+            ]
+        );
+        assert_eq!(resolver.search, ["corp.example", "example.com"]);
+        assert_eq!(
+            (resolver.ndots, resolver.timeout, resolver.attempts),
+            (2, Duration::from_secs(1), 3)
+        );
+        assert_eq!(
+            resolver.candidates("_http._tcp.svc"),
+            [
+                "_http._tcp.svc",
+                "_http._tcp.svc.corp.example",
+                "_http._tcp.svc.example.com"
+            ]
+        );
+        assert_eq!(
+            resolver.candidates("_http._tcp"),
+            [
+                "_http._tcp.corp.example",
+                "_http._tcp.example.com",
+                "_http._tcp"
+            ]
+        );
+
+        assert!(resolver.trusts_ad_from("10.0.0.1:53".parse().unwrap()));
+
+        // Without `trust-ad`, only local name servers are trusted to set the
+        // AD bit
+        let local = StdResolver::from_resolv_conf("");
+        assert_eq!(local.name_servers(), ["127.0.0.1:53".parse().unwrap()]);
+        assert!(local.trusts_ad_from("127.0.0.1:53".parse().unwrap()));
+        assert!(!local.trusts_ad_from("10.0.0.1:53".parse().unwrap()));
+    }
+}
@@ -0,0 +1,178 @@
+//! SRV resolver backed by the system's libresolv.
+
+use super::{
+    wire::{self, CLASS_IN, MAX_MESSAGE_LEN, TYPE_SRV},
+    SrvResolver,
+};
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::time::{Duration, Instant};
+
+/// Errors produced by a [`SystemResolver`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SystemResolverError {
+    /// Produced on platforms without libresolv
+    #[error("libresolv isn't available on this platform")]
+    Unsupported,
+    /// Produced when a name can't be passed to libresolv
+    #[error("invalid name {name:?}")]
+    InvalidName {
+        /// Name that was looked up
+        name: String,
+    },
+    /// Produced when the name doesn't exist or has no SRV records
+    #[error("no SRV records for {name}")]
+    NoRecords {
+        /// Name that was looked up
+        name: String,
+    },
+    /// Produced when the lookup fails
+    #[error("SRV lookup of {name} failed (h_errno {h_errno})")]
+    Failed {
+        /// Name that was looked up
+        name: String,
+        /// `h_errno` after the lookup, if the platform reports it
+        h_errno: i32,
+    },
+    /// Produced when the answer can't be parsed
+    #[error("malformed DNS answer: {0}")]
+    Malformed(&'static str),
+}
+
+/// SRV resolver sending queries with the system's libresolv (`res_search`),
+/// for builds that want to avoid a DNS client implementation of their own.
+///
+/// Lookups honor the search domains and name servers of `/etc/resolv.conf`
+/// and are valid for the smallest TTL of their records. They block the
+/// calling thread, so executors sensitive to blocking should run them
+/// elsewhere. libresolv is only linked on glibc-based Linux and on macOS;
+/// elsewhere every lookup fails with [`SystemResolverError::Unsupported`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+#[async_trait]
+impl SrvResolver for SystemResolver {
+    type Record = OwnedSrvRecord;
+    type Error = SystemResolverError;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let message = search(srv)?;
+        parse_answer(&message, Instant::now())
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, SystemResolverError::NoRecords { .. })
+    }
+}
+
+/// Parses the SRV records of a raw answer received at `now`.
+fn parse_answer(
+    message: &[u8],
+    now: Instant,
+) -> Result<(Vec<OwnedSrvRecord>, Instant), SystemResolverError> {
+    // res_search may have appended a search domain to the name looked up
+    let (records, ttl) = wire::question_name(message)
+        .and_then(|name| wire::parse_srv_answers(message, &name))
+        .map_err(SystemResolverError::Malformed)?;
+    Ok((records, now + Duration::from_secs(ttl.into())))
+}
+
+#[cfg(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos"))]
+mod ffi {
+    use std::ffi::{c_char, c_int, c_uchar};
+
+    pub const HOST_NOT_FOUND: c_int = 1;
+    pub const NO_DATA: c_int = 4;
+
+    #[link(name = "resolv")]
+    extern "C" {
+        #[cfg_attr(target_os = "macos", link_name = "res_9_search")]
+        pub fn res_search(
+            dname: *const c_char,
+            class: c_int,
+            r#type: c_int,
+            answer: *mut c_uchar,
+            anslen: c_int,
+        ) -> c_int;
+    }
+
+    #[cfg(target_os = "linux")]
+    extern "C" {
+        fn __h_errno_location() -> *mut c_int;
+    }
+
+    /// Gets the calling thread's `h_errno`, or 0 where it isn't exposed.
+    pub fn h_errno() -> c_int {
+        #[cfg(target_os = "linux")]
+        // SAFETY: glibc returns a valid pointer to the thread's h_errno
+        return unsafe { *__h_errno_location() };
+        #[cfg(not(target_os = "linux"))]
+        0
+    }
+}
+
+/// Looks up the SRV records of `name`, producing the raw answer.
+#[cfg(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos"))]
+fn search(name: &str) -> Result<Vec<u8>, SystemResolverError> {
+    let c_name = std::ffi::CString::new(name).map_err(|_| SystemResolverError::InvalidName {
+        name: name.to_owned(),
+    })?;
+    let mut answer = vec![0; MAX_MESSAGE_LEN];
+    // SAFETY: `c_name` is NUL-terminated and `answer` is writable for the
+    // length passed
+    let len = unsafe {
+        ffi::res_search(
+            c_name.as_ptr(),
+            CLASS_IN.into(),
+            TYPE_SRV.into(),
+            answer.as_mut_ptr(),
+            MAX_MESSAGE_LEN as _,
+        )
+    };
+    if len < 0 {
+        let name = name.to_owned();
+        return Err(match ffi::h_errno() {
+            ffi::HOST_NOT_FOUND | ffi::NO_DATA => SystemResolverError::NoRecords { name },
+            h_errno => SystemResolverError::Failed { name, h_errno },
+        });
+    }
+    answer.truncate((len as usize).min(MAX_MESSAGE_LEN));
+    Ok(answer)
+}
+
+#[cfg(not(any(all(target_os = "linux", target_env = "gnu"), target_os = "macos")))]
+fn search(_name: &str) -> Result<Vec<u8>, SystemResolverError> {
+    Err(SystemResolverError::Unsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SrvRecord;
+
+    #[test]
+    fn parses_canned_answer() {
+        let now = Instant::now();
+        let (records, valid_until) = parse_answer(wire::tests::ANSWER, now).unwrap();
+        let targets = records.iter().map(|r| r.target()).collect::<Vec<_>>();
+        assert_eq!(targets, ["test1.example.com.", "test2.example.com."]);
+        assert_eq!(valid_until, now + Duration::from_secs(60));
+
+        let err = parse_answer(&wire::tests::ANSWER[..20], now).unwrap_err();
+        assert!(matches!(err, SystemResolverError::Malformed(_)));
+    }
+
+    #[test]
+    fn invalid_name() {
+        let err =
+            futures::executor::block_on(SystemResolver.get_srv_records("_http.\0_tcp.foo.com"))
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            SystemResolverError::InvalidName { .. } | SystemResolverError::Unsupported
+        ));
+    }
+}
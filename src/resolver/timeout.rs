@@ -0,0 +1,221 @@
+//! SRV resolver giving up on lookups that take too long.
+
+use super::{Glue, LookupErrorKind, LookupMeta, SrvResolver};
+use crate::{SvcbRecord, TlsaRecord};
+use async_trait::async_trait;
+use std::{
+    future::Future,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// Errors produced by a [`TimeoutResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum TimeoutResolverError<E> {
+    /// Produced when the inner resolver's lookup failed
+    #[error("{0}")]
+    Lookup(E),
+    /// Produced when the inner resolver's lookup didn't finish in time
+    #[error("lookup timed out after {0:?}")]
+    TimedOut(Duration),
+}
+
+/// SRV resolver failing lookups of an inner resolver that don't finish
+/// within a timeout, so that a hung name server makes a client use its
+/// fallback promptly rather than stalling
+/// [`SrvClient::execute`](crate::SrvClient::execute).
+#[derive(Debug, Clone)]
+pub struct TimeoutResolver<R> {
+    inner: R,
+    timeout: Duration,
+}
+
+impl<R> TimeoutResolver<R> {
+    /// Creates a resolver failing lookups of `inner` that take longer than
+    /// `timeout`.
+    pub fn new(inner: R, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Gets the timeout of the resolver's lookups.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Gets the inner resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps the inner resolver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Runs a lookup of the inner resolver, failing it if it doesn't finish
+    /// in time.
+    async fn limit<T, E>(
+        &self,
+        lookup: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, TimeoutResolverError<E>> {
+        match tokio::time::timeout(self.timeout, lookup).await {
+            Ok(result) => result.map_err(TimeoutResolverError::Lookup),
+            Err(_) => {
+                tracing::debug!(timeout = ?self.timeout, "Lookup timed out");
+                Err(TimeoutResolverError::TimedOut(self.timeout))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<R> SrvResolver for TimeoutResolver<R>
+where
+    R: SrvResolver,
+    R::Record: Send,
+    R::Error: Send,
+{
+    type Record = R::Record;
+    type Error = TimeoutResolverError<R::Error>;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        self.limit(self.inner.get_srv_records_unordered(srv)).await
+    }
+
+    async fn get_srv_records_with_meta(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta), Self::Error> {
+        self.limit(self.inner.get_srv_records_with_meta(srv)).await
+    }
+
+    async fn get_srv_records_with_glue(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant, LookupMeta, Glue), Self::Error> {
+        self.limit(self.inner.get_srv_records_with_glue(srv)).await
+    }
+
+    async fn get_ip_addrs(&self, host: &str) -> Result<(Vec<IpAddr>, Instant), Self::Error> {
+        self.limit(self.inner.get_ip_addrs(host)).await
+    }
+
+    async fn get_tlsa_records(&self, name: &str) -> Result<Vec<TlsaRecord>, Self::Error> {
+        self.limit(self.inner.get_tlsa_records(name)).await
+    }
+
+    async fn get_https_records(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant), Self::Error> {
+        self.limit(self.inner.get_https_records(name)).await
+    }
+
+    async fn get_https_records_with_meta(
+        &self,
+        name: &str,
+    ) -> Result<(Vec<SvcbRecord>, Instant, LookupMeta), Self::Error> {
+        self.limit(self.inner.get_https_records_with_meta(name))
+            .await
+    }
+
+    async fn get_ptr_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.limit(self.inner.get_ptr_records(name)).await
+    }
+
+    async fn get_txt_records(&self, name: &str) -> Result<(Vec<String>, Instant), Self::Error> {
+        self.limit(self.inner.get_txt_records(name)).await
+    }
+
+    fn is_no_records_found(error: &Self::Error) -> bool {
+        matches!(error, TimeoutResolverError::Lookup(e) if R::is_no_records_found(e))
+    }
+
+    fn classify_error(error: &Self::Error) -> LookupErrorKind {
+        match error {
+            TimeoutResolverError::Lookup(e) => R::classify_error(e),
+            TimeoutResolverError::TimedOut(_) => LookupErrorKind::Timeout,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resolver::{MockResolver, MockResponse},
+        Error, SrvClient, SrvRecord,
+    };
+
+    /// Resolver whose lookups never finish.
+    struct HungResolver;
+
+    #[async_trait]
+    impl SrvResolver for HungResolver {
+        type Record = crate::OwnedSrvRecord;
+        type Error = std::io::Error;
+
+        async fn get_srv_records_unordered(
+            &self,
+            _srv: &str,
+        ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+            std::future::pending().await
+        }
+    }
+
+    fn client<R: SrvResolver>(resolver: R) -> SrvClient<TimeoutResolver<R>> {
+        SrvClient::new_with_resolver(
+            "_http._tcp.example.com",
+            "https://fallback.example.com".parse().unwrap(),
+            None,
+            TimeoutResolver::new(resolver, Duration::from_secs(2)),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hung_lookups_time_out() {
+        let client = client(HungResolver);
+        let start = tokio::time::Instant::now();
+        assert!(matches!(
+            client.get_fresh_uri_candidates().await,
+            Err(Error::Lookup(TimeoutResolverError::TimedOut(timeout)))
+                if timeout == Duration::from_secs(2)
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+        let host = client
+            .execute(
+                |uri| async move { Ok::<_, std::io::Error>(uri.host_str().unwrap().to_owned()) },
+            )
+            .await
+            .unwrap();
+        assert_eq!(host, "fallback.example.com");
+        assert_eq!(start.elapsed(), Duration::from_secs(4));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn prompt_lookups_pass_through() {
+        let mock = MockResolver::new([
+            MockResponse::records([("a.example.com", 443, 1, 100)], Duration::from_secs(60)),
+            MockResponse::NoRecords,
+        ]);
+        let resolver = TimeoutResolver::new(mock, Duration::from_secs(2));
+        let (records, _) = resolver
+            .get_srv_records("_http._tcp.example.com")
+            .await
+            .unwrap();
+        assert_eq!(records[0].target(), "a.example.com");
+
+        let err = resolver
+            .get_srv_records("_http._tcp.example.com")
+            .await
+            .unwrap_err();
+        assert!(TimeoutResolver::<MockResolver>::is_no_records_found(&err));
+        assert!(!TimeoutResolver::<MockResolver>::is_no_records_found(
+            &TimeoutResolverError::TimedOut(Duration::from_secs(2))
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! Compatibility names for code written against the `trust-dns-resolver`
+//! backend.
+//!
+//! `trust-dns-resolver` was renamed to [`hickory_resolver`], and its
+//! resolvers implement [`SrvResolver`](super::SrvResolver) under their new
+//! names. These aliases ease migrating code that named the old types; a
+//! `trust-dns-resolver` resolver itself isn't supported.
+
+/// Former name of hickory's Tokio-based resolver.
+#[deprecated(
+    note = "trust-dns-resolver was renamed to hickory-resolver; use `hickory_resolver::TokioResolver`"
+)]
+pub type TokioAsyncResolver = hickory_resolver::TokioResolver;
+
+/// Former name of hickory's resolver, generic over its connection provider.
+#[deprecated(
+    note = "trust-dns-resolver was renamed to hickory-resolver; use `hickory_resolver::Resolver`"
+)]
+pub type AsyncResolver<P> = hickory_resolver::Resolver<P>;
+
+/// Former name of the errors of hickory's resolver.
+#[deprecated(
+    note = "trust-dns-resolver was renamed to hickory-resolver; use `hickory_resolver::ResolveError`"
+)]
+pub type ResolveError = hickory_resolver::ResolveError;
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::resolver::SrvResolver;
+
+    fn is_resolver<R: SrvResolver<Error = ResolveError>>() {}
+
+    #[test]
+    fn aliases_are_resolvers() {
+        is_resolver::<TokioAsyncResolver>();
+        is_resolver::<AsyncResolver<hickory_resolver::name_server::TokioConnectionProvider>>();
+    }
+}
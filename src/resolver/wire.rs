@@ -0,0 +1,342 @@
+//! Encoding of DNS queries and parsing of their answers, shared by the
+//! resolvers that don't rely on a DNS client library.
+
+use crate::OwnedSrvRecord;
+
+/// DNS class of Internet records.
+pub(super) const CLASS_IN: u16 = 1;
+/// DNS type of SRV records.
+pub(super) const TYPE_SRV: u16 = 33;
+/// DNS type of CNAME records.
+const TYPE_CNAME: u16 = 5;
+
+/// Reason a DNS message couldn't be encoded or parsed.
+pub(super) type Malformed = &'static str;
+
+/// Largest DNS message, as limited by the length prefix of DNS over TCP.
+pub(super) const MAX_MESSAGE_LEN: usize = u16::MAX as usize;
+
+/// Encodes a recursive SRV query for `name`.
+#[cfg(feature = "std-resolver")]
+pub(super) fn srv_query(id: u16, name: &str) -> Result<Vec<u8>, Malformed> {
+    // Recursion desired, authenticated data wanted (RFC 6840)
+    srv_query_with_flags(id, 0x0120, name)
+}
+
+/// Encodes a SRV query for `name` with the given header flags.
+#[cfg(any(feature = "mdns", feature = "std-resolver"))]
+pub(super) fn srv_query_with_flags(id: u16, flags: u16, name: &str) -> Result<Vec<u8>, Malformed> {
+    let mut message = Vec::with_capacity(18 + name.len());
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&flags.to_be_bytes());
+    // One question
+    message.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if !name.is_empty() {
+        for label in name.split('.') {
+            let len = u8::try_from(label.len())
+                .ok()
+                .filter(|len| (1..=63).contains(len))
+                .ok_or("label must have between 1 and 63 bytes")?;
+            message.push(len);
+            message.extend_from_slice(label.as_bytes());
+        }
+    }
+    message.push(0);
+    if message.len() - 12 > 255 {
+        return Err("name is longer than 255 bytes");
+    }
+    message.extend_from_slice(&TYPE_SRV.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+/// Header fields of a DNS message.
+#[cfg(any(feature = "mdns", feature = "std-resolver"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Header {
+    pub id: u16,
+    pub response: bool,
+    pub truncated: bool,
+    pub authentic_data: bool,
+    pub rcode: u8,
+}
+
+#[cfg(any(feature = "mdns", feature = "std-resolver"))]
+impl Header {
+    /// Response code of a successful query.
+    pub const NO_ERROR: u8 = 0;
+    /// Response code of a query for a name that doesn't exist.
+    #[cfg(feature = "std-resolver")]
+    pub const NX_DOMAIN: u8 = 3;
+
+    /// Parses the header of a DNS message.
+    pub fn parse(message: &[u8]) -> Result<Self, Malformed> {
+        match *message {
+            [id_high, id_low, flags_high, flags_low, ..] if message.len() >= 12 => Ok(Self {
+                id: u16::from_be_bytes([id_high, id_low]),
+                response: flags_high & 0x80 != 0,
+                truncated: flags_high & 0x02 != 0,
+                authentic_data: flags_low & 0x20 != 0,
+                rcode: flags_low & 0x0f,
+            }),
+            _ => Err("truncated header"),
+        }
+    }
+}
+
+/// Reader of a DNS message.
+struct Reader<'a> {
+    message: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Result<&[u8], Malformed> {
+        let bytes = self
+            .message
+            .get(self.pos..self.pos + len)
+            .ok_or("truncated message")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, Malformed> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Malformed> {
+        Ok((u32::from(self.u16()?) << 16) | u32::from(self.u16()?))
+    }
+
+    /// Reads a possibly compressed domain name, producing it with a trailing
+    /// dot.
+    fn name(&mut self) -> Result<String, Malformed> {
+        let mut name = String::new();
+        let mut pos = self.pos;
+        let mut resume = None;
+        // Every pointer must go backwards, so this bounds the labels read
+        for _ in 0..self.message.len() {
+            let len = *self.message.get(pos).ok_or("truncated name")?;
+            match len {
+                0 => {
+                    self.pos = resume.unwrap_or(pos + 1);
+                    if name.is_empty() {
+                        name.push('.');
+                    }
+                    return Ok(name);
+                }
+                len if len & 0xc0 == 0xc0 => {
+                    let low = *self.message.get(pos + 1).ok_or("truncated name")?;
+                    let target = (usize::from(len & 0x3f) << 8) | usize::from(low);
+                    if target >= pos {
+                        return Err("forward name pointer");
+                    }
+                    resume.get_or_insert(pos + 2);
+                    pos = target;
+                }
+                len if len & 0xc0 == 0 => {
+                    let label = self
+                        .message
+                        .get(pos + 1..pos + 1 + usize::from(len))
+                        .ok_or("truncated label")?;
+                    name.push_str(&String::from_utf8_lossy(label));
+                    name.push('.');
+                    pos += 1 + usize::from(len);
+                }
+                _ => return Err("unknown label type"),
+            }
+        }
+        Err("name pointer loop")
+    }
+}
+
+/// Parses the SRV records of a DNS answer to a query for `name`, along with
+/// their smallest TTL.
+///
+/// Answers to questions about other names are rejected, and records owned by
+/// other names (e.g. other services a responder advertises) are skipped,
+/// except for those `name` is an alias of.
+pub(super) fn parse_srv_answers(
+    message: &[u8],
+    name: &str,
+) -> Result<(Vec<OwnedSrvRecord>, u32), Malformed> {
+    let mut reader = Reader { message, pos: 4 };
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.bytes(4)?;
+    for _ in 0..questions {
+        if !same_name(&reader.name()?, name) {
+            return Err("answer to another question");
+        }
+        reader.bytes(4)?;
+    }
+
+    let mut owners = vec![name.to_owned()];
+    let mut records = Vec::new();
+    let mut min_ttl = None::<u32>;
+    for _ in 0..answers {
+        let owner = reader.name()?;
+        let (rtype, class, ttl) = (reader.u16()?, reader.u16()?, reader.u32()?);
+        let len = usize::from(reader.u16()?);
+        let end = reader.pos + len;
+        // The top bit of the class is mDNS's cache-flush bit (RFC 6762)
+        let owned = class & 0x7fff == CLASS_IN && owners.iter().any(|o| same_name(o, &owner));
+        if owned && rtype == TYPE_SRV {
+            let (priority, weight, port) = (reader.u16()?, reader.u16()?, reader.u16()?);
+            let target = reader.name()?;
+            records.push(OwnedSrvRecord::new(target, port, priority, weight));
+            min_ttl = Some(min_ttl.map_or(ttl, |min| min.min(ttl)));
+        } else if owned && rtype == TYPE_CNAME {
+            owners.push(reader.name()?);
+        }
+        if end > message.len() {
+            return Err("truncated record");
+        }
+        reader.pos = end;
+    }
+    Ok((records, min_ttl.unwrap_or(0)))
+}
+
+/// Parses the name asked about by the question of a DNS message.
+#[cfg(feature = "system-resolver")]
+pub(super) fn question_name(message: &[u8]) -> Result<String, Malformed> {
+    let mut reader = Reader { message, pos: 4 };
+    if reader.u16()? == 0 {
+        return Err("missing question");
+    }
+    reader.pos = 12;
+    reader.name()
+}
+
+/// Whether two domain names are the same, ignoring case and trailing dots.
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::SrvRecord;
+
+    /// Answer to a SRV query for `_http._tcp.example.com`, with records for
+    /// `test1.example.com` (TTL 300) and `test2.example.com` (TTL 60), whose
+    /// owner names are compressed.
+    pub(in crate::resolver) const ANSWER: &[u8] = &[
+        0x00, 0x00, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, //
+        0x05, 0x5f, 0x68, 0x74, 0x74, 0x70, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x07, //
+        0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, //
+        0x00, 0x21, 0x00, 0x01, 0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, 0x00, //
+        0x01, 0x2c, 0x00, 0x19, 0x00, 0x01, 0x00, 0x64, 0x01, 0xbb, 0x05, 0x74, //
+        0x65, 0x73, 0x74, 0x31, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, //
+        0x03, 0x63, 0x6f, 0x6d, 0x00, 0xc0, 0x0c, 0x00, 0x21, 0x00, 0x01, 0x00, //
+        0x00, 0x00, 0x3c, 0x00, 0x19, 0x00, 0x02, 0x00, 0x32, 0x01, 0xbb, 0x05, //
+        0x74, 0x65, 0x73, 0x74, 0x32, 0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, //
+        0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00,
+    ];
+
+    /// Name asked about by [`ANSWER`].
+    pub(in crate::resolver) const NAME: &str = "_http._tcp.example.com";
+
+    #[test]
+    fn parses_answers() {
+        let (records, ttl) = parse_srv_answers(ANSWER, NAME).unwrap();
+        let records = records
+            .iter()
+            .map(|r| (r.target(), r.port(), r.priority(), r.weight()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            [
+                ("test1.example.com.", 443, 1, 100),
+                ("test2.example.com.", 443, 2, 50)
+            ]
+        );
+        assert_eq!(ttl, 60);
+    }
+
+    #[test]
+    fn skips_records_of_other_names() {
+        let (records, _) = parse_srv_answers(ANSWER, "_HTTP._tcp.example.com.").unwrap();
+        assert_eq!(records.len(), 2);
+
+        let mut other = ANSWER.to_vec();
+        // Point the second answer's owner name at `_tcp.example.com`
+        other[78] = 18;
+        let (records, ttl) = parse_srv_answers(&other, NAME).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target(), "test1.example.com.");
+        assert_eq!(ttl, 300);
+    }
+
+    #[test]
+    fn rejects_answers_to_other_questions() {
+        assert!(matches!(
+            parse_srv_answers(ANSWER, "_ftp._tcp.example.com"),
+            Err("answer to another question")
+        ));
+    }
+
+    #[test]
+    fn follows_aliases() {
+        // Answer to a query for `_ftp._tcp.example.com`, an alias of
+        // `_http._tcp.example.com` whose records follow
+        let mut aliased = ANSWER[..12].to_vec();
+        aliased[7] = 3;
+        aliased.extend_from_slice(b"\x04_ftp\x04_tcp\x07example\x03com\x00\x00\x21\x00\x01");
+        aliased.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x05, 0x00, 0x01]);
+        aliased.extend_from_slice(&[0x00, 0x00, 0x00, 0x3c, 0x00, 0x08]);
+        aliased.extend_from_slice(b"\x05_http\xc0\x11");
+        let mut records = ANSWER[40..].to_vec();
+        // Point the records' owner names at the alias's target
+        records[1] = 51;
+        records[38] = 51;
+        aliased.extend_from_slice(&records);
+
+        let (records, ttl) = parse_srv_answers(&aliased, "_ftp._tcp.example.com").unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(ttl, 60);
+    }
+
+    #[test]
+    fn rejects_malformed_answers() {
+        for len in [3, 20, 50, ANSWER.len() - 1] {
+            assert!(parse_srv_answers(&ANSWER[..len], NAME).is_err(), "{len}");
+        }
+        let mut looping = ANSWER.to_vec();
+        // Point the first answer's owner name at itself
+        looping[41] = 40;
+        assert!(matches!(
+            parse_srv_answers(&looping, NAME),
+            Err("forward name pointer")
+        ));
+    }
+
+    #[cfg(feature = "std-resolver")]
+    #[test]
+    fn encodes_queries() {
+        let query = srv_query(0x1234, "_http._tcp.example.com.").unwrap();
+        assert_eq!(query, srv_query(0x1234, "_http._tcp.example.com").unwrap());
+        // The question of the recorded answer, which had ID 0
+        assert_eq!(query[12..], ANSWER[12..40]);
+        assert_eq!(
+            Header::parse(&query).unwrap(),
+            Header {
+                id: 0x1234,
+                response: false,
+                truncated: false,
+                authentic_data: true,
+                rcode: Header::NO_ERROR
+            }
+        );
+        assert_eq!(Header::parse(ANSWER).unwrap().id, 0);
+        assert!(Header::parse(ANSWER).unwrap().response);
+
+        assert!(srv_query(0, "_http..example.com").is_err());
+        assert!(srv_query(0, &format!("{}.com", "a".repeat(64))).is_err());
+        assert!(srv_query(0, &vec!["a".repeat(63); 4].join(".")).is_err());
+        assert_eq!(srv_query(0, ".").unwrap().len(), 17);
+    }
+}
@@ -0,0 +1,268 @@
+//! SRV resolver serving records parsed from zone file text.
+
+use super::SrvResolver;
+use crate::OwnedSrvRecord;
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// TTL of records with neither their own TTL nor a `$TTL` directive before
+/// them.
+const DEFAULT_TTL: u32 = 3600;
+
+/// Errors encountered when loading zone file text into a [`ZoneResolver`].
+#[derive(Debug, thiserror::Error)]
+pub enum ZoneError {
+    /// Produced when the zone file can't be read
+    #[error("failed to read zone file {path}")]
+    Read {
+        /// Path of the zone file
+        path: PathBuf,
+        /// Error reading the file
+        #[source]
+        source: std::io::Error,
+    },
+    /// Produced when a line isn't a valid SRV record
+    #[error("malformed zone file line {line}: {reason}")]
+    Malformed {
+        /// Number of the line, counting from one
+        line: usize,
+        /// What is wrong with the line
+        reason: String,
+    },
+}
+
+/// Error produced when looking up a name a [`ZoneResolver`] has no records
+/// for.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("no SRV records for {name} in zone")]
+pub struct NotInZone {
+    /// Name that was looked up
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+struct ZoneRecord {
+    record: OwnedSrvRecord,
+    ttl: u32,
+}
+
+/// SRV resolver answering lookups with SRV records parsed from zone file
+/// text, e.g. to mirror production DNS data in tests.
+///
+/// Lines are of the form `name [ttl] [class] SRV priority weight port target`,
+/// where the TTL and class may come in either order. Comments start with `;`,
+/// a line starting with whitespace uses the name of the line before it, and
+/// a `$TTL` directive sets the TTL of the records after it. Names are
+/// absolute whether or not they end with a dot.
+///
+/// ```
+/// # use detsys_srv::{resolver::ZoneResolver, SrvClient};
+/// let resolver: ZoneResolver = "
+/// _http._tcp.example.com. 60 IN SRV 1 100 443 test1.example.com.
+///                         60 IN SRV 2 50  443 test2.example.com. ; backup
+/// ".parse().unwrap();
+/// let client = SrvClient::<_>::new_with_resolver(
+///     "_http._tcp.example.com",
+///     "https://fallback.example.com".parse().unwrap(),
+///     None,
+///     resolver,
+/// );
+/// let (uris, _) = futures::executor::block_on(client.get_fresh_uri_candidates()).unwrap();
+/// assert_eq!(uris[0].as_str(), "https://test1.example.com./");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ZoneResolver {
+    records: HashMap<String, Vec<ZoneRecord>>,
+}
+
+impl ZoneResolver {
+    /// Creates a resolver from the zone file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ZoneError> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|source| ZoneError::Read {
+                path: path.to_owned(),
+                source,
+            })?
+            .parse()
+    }
+
+    /// Gets the number of records the resolver has.
+    pub fn len(&self) -> usize {
+        self.records.values().map(Vec::len).sum()
+    }
+
+    /// Determines if the resolver has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// Normalizes a domain name for use as a key.
+fn key(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn is_class(field: &str) -> bool {
+    ["IN", "CH", "HS", "CS"]
+        .iter()
+        .any(|class| field.eq_ignore_ascii_case(class))
+}
+
+impl FromStr for ZoneResolver {
+    type Err = ZoneError;
+
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        let mut records = HashMap::<_, Vec<_>>::new();
+        let mut default_ttl = DEFAULT_TTL;
+        let mut previous_name = None;
+        for (idx, line) in zone.lines().enumerate() {
+            let malformed = |reason: &str| ZoneError::Malformed {
+                line: idx + 1,
+                reason: reason.to_owned(),
+            };
+            let content = line.split(';').next().unwrap_or_default();
+            let mut fields = content.split_whitespace().peekable();
+            let Some(&first) = fields.peek() else {
+                continue;
+            };
+
+            if first.eq_ignore_ascii_case("$TTL") {
+                fields.next();
+                default_ttl = fields
+                    .next()
+                    .and_then(|ttl| ttl.parse().ok())
+                    .ok_or_else(|| malformed("`$TTL` needs a number of seconds"))?;
+                continue;
+            }
+            if first.starts_with('$') {
+                return Err(malformed(&format!("unsupported directive `{first}`")));
+            }
+
+            let name = match content.starts_with(char::is_whitespace) {
+                true => previous_name
+                    .clone()
+                    .ok_or_else(|| malformed("no name to continue"))?,
+                false => key(fields.next().unwrap_or_default()),
+            };
+            let mut ttl = None;
+            let mut class = false;
+            let rtype = loop {
+                let field = fields
+                    .next()
+                    .ok_or_else(|| malformed("missing record type"))?;
+                if let (None, Ok(seconds)) = (ttl, field.parse()) {
+                    ttl = Some(seconds);
+                } else if !class && is_class(field) {
+                    class = true;
+                } else {
+                    break field;
+                }
+            };
+            if !rtype.eq_ignore_ascii_case("SRV") {
+                return Err(malformed(&format!("record type `{rtype}` isn't SRV")));
+            }
+
+            let mut number = |field: &str| {
+                fields
+                    .next()
+                    .and_then(|value| value.parse::<u16>().ok())
+                    .ok_or_else(|| malformed(&format!("missing or invalid {field}")))
+            };
+            let priority = number("priority")?;
+            let weight = number("weight")?;
+            let port = number("port")?;
+            let target = fields.next().ok_or_else(|| malformed("missing target"))?;
+            if fields.next().is_some() {
+                return Err(malformed("unexpected fields after target"));
+            }
+
+            records.entry(name.clone()).or_default().push(ZoneRecord {
+                record: OwnedSrvRecord::new(target, port, priority, weight),
+                ttl: ttl.unwrap_or(default_ttl),
+            });
+            previous_name = Some(name);
+        }
+        Ok(Self { records })
+    }
+}
+
+#[async_trait]
+impl SrvResolver for ZoneResolver {
+    type Record = OwnedSrvRecord;
+    type Error = NotInZone;
+
+    async fn get_srv_records_unordered(
+        &self,
+        srv: &str,
+    ) -> Result<(Vec<Self::Record>, Instant), Self::Error> {
+        let records = self.records.get(&key(srv)).ok_or_else(|| NotInZone {
+            name: srv.to_owned(),
+        })?;
+        let ttl = records.iter().map(|zone| zone.ttl).min().unwrap_or(0);
+        let records = records.iter().map(|zone| zone.record.clone()).collect();
+        Ok((records, Instant::now() + Duration::from_secs(ttl.into())))
+    }
+
+    fn is_no_records_found(_error: &Self::Error) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SrvRecord;
+    use futures::executor::block_on;
+
+    #[test]
+    fn parse_zone() {
+        let zone = [
+            "$TTL 300",
+            "; the example service",
+            "_http._tcp.example.com. 60 IN SRV 1 100 443 test1.example.com.",
+            "                        IN 30 SRV 2 50 8443 test2.example.com",
+            "_HTTP._TCP.Example.NET  SRV 0 0 80 test3.example.net. ; no TTL",
+        ];
+        let resolver: ZoneResolver = zone.join("\n").parse().unwrap();
+        assert_eq!(resolver.len(), 3);
+
+        let (records, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.com")).unwrap();
+        let records = records
+            .iter()
+            .map(|r| (r.target(), r.port(), r.priority(), r.weight()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            [
+                ("test1.example.com.", 443, 1, 100),
+                ("test2.example.com", 8443, 2, 50)
+            ]
+        );
+        assert!(valid_until <= Instant::now() + Duration::from_secs(30));
+
+        let (_, valid_until) =
+            block_on(resolver.get_srv_records_unordered("_http._tcp.example.net.")).unwrap();
+        assert!(valid_until > Instant::now() + Duration::from_secs(200));
+        assert!(block_on(resolver.get_srv_records_unordered("_http._tcp.example.org")).is_err());
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let line = |zone: &str| match zone.parse::<ZoneResolver>() {
+            Err(ZoneError::Malformed { line, .. }) => line,
+            other => panic!("parsed {other:?}"),
+        };
+        assert_eq!(line("\n_http._tcp.example.com. 60 IN A 192.0.2.1"), 2);
+        assert_eq!(line("_http._tcp.example.com. 60 IN SRV 1 100 443"), 1);
+        assert_eq!(line("; comment\n\n_a._tcp.example.com. SRV 1 100 x t."), 3);
+        assert_eq!(line("  60 IN SRV 1 100 443 test1.example.com."), 1);
+        assert_eq!(line("$ORIGIN example.com."), 1);
+    }
+}